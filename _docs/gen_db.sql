@@ -43,6 +43,7 @@ CREATE TABLE uploader.COLUMN_TYPE (
 -- DROP TABLE uploader.HIST_GROUP;
 
 CREATE TABLE uploader.HIST_GROUP (
+	pk int IDENTITY(1,1) NOT NULL,
 	Group_fk int NOT NULL,
 	Name varchar(100) COLLATE SQL_Latin1_General_CP1_CI_AI DEFAULT NULL NULL,
 	Active bit DEFAULT NULL NULL,
@@ -57,7 +58,8 @@ CREATE TABLE uploader.HIST_GROUP (
 	EditedBy_fk int NOT NULL,
 	EditedAt datetime DEFAULT getdate() NOT NULL,
 	EditAction varchar(30) COLLATE SQL_Latin1_General_CP1_CI_AI NOT NULL,
-	ImpersonateUsers bit DEFAULT NULL NULL
+	ImpersonateUsers bit DEFAULT NULL NULL,
+	CONSTRAINT HIST_GROUP_PK PRIMARY KEY (pk)
 );
 
 
@@ -68,6 +70,7 @@ CREATE TABLE uploader.HIST_GROUP (
 -- DROP TABLE uploader.HIST_SHEET;
 
 CREATE TABLE uploader.HIST_SHEET (
+	pk int IDENTITY(1,1) NOT NULL,
 	Sheet_fk int NOT NULL,
 	Description varchar(100) COLLATE SQL_Latin1_General_CP1_CI_AI NULL,
 	TableName varchar(100) COLLATE SQL_Latin1_General_CP1_CI_AI NULL,
@@ -76,7 +79,8 @@ CREATE TABLE uploader.HIST_SHEET (
 	DaysToRefresh int NULL,
 	Model varbinary(MAX) NULL,
 	EditAction varchar(30) COLLATE SQL_Latin1_General_CP1_CI_AI NOT NULL,
-	EditedAt datetime DEFAULT getdate() NOT NULL
+	EditedAt datetime DEFAULT getdate() NOT NULL,
+	CONSTRAINT HIST_SHEET_PK PRIMARY KEY (pk)
 );
 
 -- uploader.HIST_SHEET_META_DATA definition
@@ -86,6 +90,7 @@ CREATE TABLE uploader.HIST_SHEET (
 -- DROP TABLE uploader.HIST_SHEET_META_DATA;
 
 CREATE TABLE uploader.HIST_SHEET_META_DATA (
+	pk int IDENTITY(1,1) NOT NULL,
 	SheetMetaData_fk int NOT NULL,
 	Sheet_fk int NULL,
 	ColumnName varchar(100) COLLATE SQL_Latin1_General_CP1_CI_AI NULL,
@@ -94,7 +99,8 @@ CREATE TABLE uploader.HIST_SHEET_META_DATA (
 	RegexConstraint varchar(100) COLLATE SQL_Latin1_General_CP1_CI_AI NULL,
 	EditedBy_fk varchar(100) COLLATE SQL_Latin1_General_CP1_CI_AI NOT NULL,
 	EditAction varchar(30) COLLATE SQL_Latin1_General_CP1_CI_AI NOT NULL,
-	EditedAt datetime DEFAULT getdate() NOT NULL
+	EditedAt datetime DEFAULT getdate() NOT NULL,
+	CONSTRAINT HIST_SHEET_META_DATA_PK PRIMARY KEY (pk)
 );
 
 
@@ -105,13 +111,15 @@ CREATE TABLE uploader.HIST_SHEET_META_DATA (
 -- DROP TABLE uploader.HIST_UPLOADER_PERMISSION;
 
 CREATE TABLE uploader.HIST_UPLOADER_PERMISSION (
+	pk int IDENTITY(1,1) NOT NULL,
 	Group_fk int NOT NULL,
 	Sheet_fk int NOT NULL,
 	CanViewHist bit NULL,
 	CanUpload int NULL,
 	EditedBy_fk int NOT NULL,
 	ActionHist varchar(30) COLLATE SQL_Latin1_General_CP1_CI_AI NOT NULL,
-	EditedAt datetime DEFAULT getdate() NOT NULL
+	EditedAt datetime DEFAULT getdate() NOT NULL,
+	CONSTRAINT HIST_UPLOADER_PERMISSION_PK PRIMARY KEY (pk)
 );
 
 
@@ -219,11 +227,13 @@ CREATE TABLE uploader.SHEET_USED_BY_BOARD (
 -- DROP TABLE uploader.UPLOAD;
 
 CREATE TABLE uploader.UPLOAD (
+	pk int IDENTITY(1,1) NOT NULL,
 	Sheet_fk int NOT NULL,
 	FileUploaded varbinary(MAX) NOT NULL,
 	UploadedAt datetime DEFAULT getdate() NOT NULL,
 	UploadedBy_fk int NOT NULL,
 	SheetUsed varchar(100) COLLATE SQL_Latin1_General_CP1_CI_AI DEFAULT NULL NULL,
+	CONSTRAINT UPLOAD_PK PRIMARY KEY (pk),
 	CONSTRAINT UPLOAD_PROFILE_FK FOREIGN KEY (UploadedBy_fk) REFERENCES uploader.PROFILE(pk),
 	CONSTRAINT UPLOAD_SHEET_FK FOREIGN KEY (Sheet_fk) REFERENCES uploader.SHEET(pk)
 );
@@ -232,6 +242,29 @@ CREATE TABLE uploader.UPLOAD (
 
 EXEC DIGITAL_BRA_DEV.sys.sp_addextendedproperty @name=N'MS_Description', @value=N'SheetUsed -> What excel sheet was used to load data', @level0type=N'Schema', @level0name=N'uploader', @level1type=N'Table', @level1name=N'UPLOAD';
 
+-- uploader.UPLOAD_CALLBACK definition
+
+-- Drop table
+
+-- DROP TABLE uploader.UPLOAD_CALLBACK;
+
+CREATE TABLE uploader.UPLOAD_CALLBACK (
+	pk int IDENTITY(1,1) NOT NULL,
+	Upload_fk int NOT NULL,
+	Attempt int NOT NULL,
+	Url varchar(2000) COLLATE SQL_Latin1_General_CP1_CI_AI NOT NULL,
+	StatusCode int NULL,
+	Error varchar(MAX) COLLATE SQL_Latin1_General_CP1_CI_AI NULL,
+	AttemptedAt datetime DEFAULT getdate() NOT NULL,
+	CONSTRAINT UPLOAD_CALLBACK_PK PRIMARY KEY (pk),
+	CONSTRAINT UPLOAD_CALLBACK_UPLOAD_FK FOREIGN KEY (Upload_fk) REFERENCES uploader.UPLOAD(pk)
+);
+
+-- Extended properties
+
+EXEC sys.sp_addextendedproperty @name=N'MS_Description', @value=N'One line per attempt of the SHEET.RequestAfterUpdate request of an upload', @level0type=N'Schema', @level0name=N'uploader', @level1type=N'Table', @level1name=N'UPLOAD_CALLBACK';
+
+
 -- uploader.CUSTOM_SQL_SCRIPT definition
 
 -- Drop table
@@ -239,11 +272,13 @@ EXEC DIGITAL_BRA_DEV.sys.sp_addextendedproperty @name=N'MS_Description', @value=
 -- DROP TABLE uploader.CUSTOM_SQL_SCRIPT;
 
 CREATE TABLE uploader.CUSTOM_SQL_SCRIPT (
+	pk int IDENTITY(1,1) NOT NULL,
 	Sheet_fk int NOT NULL,
 	RunBeforeUpdate bit DEFAULT 0 NOT NULL,
 	RunAfterUpdate bit DEFAULT 0 NOT NULL,
 	RunAsUpdate bit DEFAULT 0 NOT NULL,
 	CustomScript varchar(MAX) COLLATE SQL_Latin1_General_CP1_CI_AI NOT NULL,
+	CONSTRAINT CUSTOM_SQL_SCRIPT_PK PRIMARY KEY (pk),
 	CONSTRAINT CUSTOM_SQL_SCRIPT_SHEET_FK FOREIGN KEY (Sheet_fk) REFERENCES uploader.SHEET(pk)
 );
 
@@ -0,0 +1,45 @@
+-- Brings a database created by an older gen_db.sql up to date.
+-- Every step checks whether it already ran, so the whole script can be run again.
+
+-- uploader.UPLOAD primary key, read by the upload history and UPLOAD_CALLBACK
+
+IF COL_LENGTH('uploader.UPLOAD', 'pk') IS NULL
+	ALTER TABLE uploader.UPLOAD ADD pk int IDENTITY(1,1) NOT NULL CONSTRAINT UPLOAD_PK PRIMARY KEY;
+
+-- uploader.UPLOAD_CALLBACK definition
+
+IF OBJECT_ID('uploader.UPLOAD_CALLBACK', 'U') IS NULL
+BEGIN
+	CREATE TABLE uploader.UPLOAD_CALLBACK (
+		pk int IDENTITY(1,1) NOT NULL,
+		Upload_fk int NOT NULL,
+		Attempt int NOT NULL,
+		Url varchar(2000) COLLATE SQL_Latin1_General_CP1_CI_AI NOT NULL,
+		StatusCode int NULL,
+		Error varchar(MAX) COLLATE SQL_Latin1_General_CP1_CI_AI NULL,
+		AttemptedAt datetime DEFAULT getdate() NOT NULL,
+		CONSTRAINT UPLOAD_CALLBACK_PK PRIMARY KEY (pk),
+		CONSTRAINT UPLOAD_CALLBACK_UPLOAD_FK FOREIGN KEY (Upload_fk) REFERENCES uploader.UPLOAD(pk)
+	);
+
+	EXEC sys.sp_addextendedproperty @name=N'MS_Description', @value=N'One line per attempt of the SHEET.RequestAfterUpdate request of an upload', @level0type=N'Schema', @level0name=N'uploader', @level1type=N'Table', @level1name=N'UPLOAD_CALLBACK';
+END;
+
+-- uploader.CUSTOM_SQL_SCRIPT primary key, the scripts of a sheet run in its order
+
+IF COL_LENGTH('uploader.CUSTOM_SQL_SCRIPT', 'pk') IS NULL
+	ALTER TABLE uploader.CUSTOM_SQL_SCRIPT ADD pk int IDENTITY(1,1) NOT NULL CONSTRAINT CUSTOM_SQL_SCRIPT_PK PRIMARY KEY;
+
+-- uploader.HIST_* primary keys, the history listings page by them
+
+IF COL_LENGTH('uploader.HIST_GROUP', 'pk') IS NULL
+	ALTER TABLE uploader.HIST_GROUP ADD pk int IDENTITY(1,1) NOT NULL CONSTRAINT HIST_GROUP_PK PRIMARY KEY;
+
+IF COL_LENGTH('uploader.HIST_SHEET', 'pk') IS NULL
+	ALTER TABLE uploader.HIST_SHEET ADD pk int IDENTITY(1,1) NOT NULL CONSTRAINT HIST_SHEET_PK PRIMARY KEY;
+
+IF COL_LENGTH('uploader.HIST_SHEET_META_DATA', 'pk') IS NULL
+	ALTER TABLE uploader.HIST_SHEET_META_DATA ADD pk int IDENTITY(1,1) NOT NULL CONSTRAINT HIST_SHEET_META_DATA_PK PRIMARY KEY;
+
+IF COL_LENGTH('uploader.HIST_UPLOADER_PERMISSION', 'pk') IS NULL
+	ALTER TABLE uploader.HIST_UPLOADER_PERMISSION ADD pk int IDENTITY(1,1) NOT NULL CONSTRAINT HIST_UPLOADER_PERMISSION_PK PRIMARY KEY;
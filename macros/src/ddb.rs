@@ -1,30 +1,33 @@
-use quote::{ToTokens, quote};
-use syn::{Ident, LitStr, Token, Type, parse::{Parse, ParseStream}};
-
-use crate::helper;
+use quote::{ToTokens, format_ident, quote};
+use syn::{Attribute, Data, DeriveInput, Fields, GenericArgument, Ident, LitStr, PathArguments, Type, parse::{Parse, ParseStream}};
 
 pub struct DBLoadInput {
-    table_type: Type,
+    table_type: Ident,
     table_name: LitStr,
     cols: Vec<Column>,
 }
 
 impl Parse for DBLoadInput {
     fn parse(input: ParseStream) -> syn::Result<Self> {
-        let table_type = input.parse()?;
-        input.parse::<Token![,]>()?;
-        let table_name = input.parse()?;
-        input.parse::<Token![,]>()?;
-
-        let mut cols = vec![];
-        while !input.is_empty() {
-            cols.push(input.parse()?);
-            if input.parse::<Token![,]>().is_err() {
-                break;
-            }
-        }
+        let input: DeriveInput = input.parse()?;
+
+        let table_name = find_attribute(&input.attrs, "table")
+            .ok_or_else(|| syn::Error::new(input.ident.span(), "expected `#[table(\"TABLE_NAME\")]`"))?
+            .parse_args()?;
+
+        let Data::Struct(data) = &input.data else {
+            return Err(syn::Error::new(input.ident.span(), "DBLoad can only be derived for structs"));
+        };
+
+        let Fields::Named(fields) = &data.fields else {
+            return Err(syn::Error::new(input.ident.span(), "DBLoad needs named fields"));
+        };
 
-        Ok(DBLoadInput { table_type, table_name, cols })
+        let cols = fields.named.iter()
+            .map(Column::from_field)
+            .collect::<syn::Result<Vec<_>>>()?;
+
+        Ok(DBLoadInput { table_type: input.ident, table_name, cols })
     }
 }
 
@@ -33,39 +36,67 @@ impl ToTokens for DBLoadInput {
         let DBLoadInput { table_type, table_name, cols } = &self;
 
         let count = cols.len();
-        let count_lit = syn::Index::from(count);
+
+        let consts = cols.iter().map(|col| {
+            let Column { constant, name, .. } = col;
+            quote!(pub const #constant: &'static str = #name;)
+        });
 
         let arr_cols = cols.iter().map(|col| {
-            let reference = &col.reference;
-            quote!(Self::#reference)
+            let constant = &col.constant;
+            quote!(Self::#constant)
+        });
+
+        let arr_pk = cols.iter().filter(|col| col.pk).map(|col| {
+            let constant = &col.constant;
+            quote!(Self::#constant)
+        });
+
+        let setters = cols.iter().map(|col| {
+            let Column { field, typing, .. } = col;
+            let setter = format_ident!("with_{}", field);
+            quote!(
+                pub fn #setter(mut self, #field: #typing) -> Self {
+                    self.#field = #field;
+                    self
+                }
+            )
         });
 
         let get_cols = cols.iter().map(|col| {
-            let reference = &col.reference;
-            if col.optional {
-                return quote!(row.try_get_by_name(Self::#reference)?);
+            let Column { field, constant, optional, .. } = col;
+            if *optional {
+                return quote!(#field: row.try_get_by_name(Self::#constant)?);
             }
 
-            quote!(row.try_get_by_name(Self::#reference)?.unwrap())
+            quote!(
+                #field: row.try_get_by_name(Self::#constant)?
+                    .ok_or_else(|| anyhow::anyhow!("Column '{}' of '{}' is NULL", Self::#constant, #table_name))?
+            )
         });
 
         tokens.extend(quote! {
-            impl DBLoad for #table_type {
+            impl #table_type {
+                #( #consts )*
+            }
+
+            /// Rows are only built from the database, tests build theirs from `Default` with these
+            #[cfg(test)]
+            impl #table_type {
+                #( #setters )*
+            }
+
+            impl crate::ddb::DBLoad for #table_type {
                 const LEN: usize = #count;
                 const TAB: &'static str = #table_name;
                 const COLS: &'static [&'static str] = &[ #( #arr_cols ),* ];
+                const PK: &'static [&'static str] = &[ #( #arr_pk ),* ];
+
+                fn from_row(row: ::tiberius::Row) -> anyhow::Result<Self> {
+                    use crate::ddb::tiberius_interface::FromOwnedSql;
 
-                fn from_stream(stream: QueryStream<'_>) -> Pin<Box<dyn Future<Output = anyhow::Result<Vec<Self>>> + Send + '_>> {
-                    let mut row_stream = stream.into_row_stream();
-                    let mut result = Vec::new();
-
-                    Box::pin(async move {
-                        while let Some(row) = row_stream.next().await.transpose()? as Option<tiberius::Row> {
-                            result.push(Self::db_new(
-                                #( #get_cols ),*
-                            ));
-                        }
-                        Ok(result)
+                    Ok(Self {
+                        #( #get_cols ),*
                     })
                 }
             }
@@ -74,15 +105,100 @@ impl ToTokens for DBLoadInput {
 }
 
 struct Column {
-    reference: Ident,
-    optional: bool
+    field: Ident,
+    typing: Type,
+    /// `COL_<FIELD>`
+    constant: Ident,
+    /// SQL column name
+    name: LitStr,
+    optional: bool,
+    pk: bool,
 }
 
-impl Parse for Column {
-    fn parse(input: ParseStream) -> syn::Result<Self> {
-        Ok( Self {
-            reference: input.parse()?,
-            optional: input.parse::<Token![?]>().is_ok()
+impl Column {
+    fn from_field(field: &syn::Field) -> syn::Result<Self> {
+        let ident = field.ident.clone().expect("named field");
+
+        let name = match find_attribute(&field.attrs, "column") {
+            Some(attr) => attr.parse_args()?,
+            None => LitStr::new(&default_column_name(&ident.to_string()), ident.span()),
+        };
+
+        Ok(Self {
+            constant: format_ident!("COL_{}", ident.to_string().to_uppercase()),
+            typing: field.ty.clone(),
+            optional: is_option(&field.ty),
+            pk: find_attribute(&field.attrs, "pk").is_some(),
+            field: ident,
+            name,
         })
     }
-}
\ No newline at end of file
+}
+
+fn find_attribute<'a>(attrs: &'a [Attribute], name: &str) -> Option<&'a Attribute> {
+    attrs.iter().find(|attr| attr.path().is_ident(name))
+}
+
+fn is_option(typing: &Type) -> bool {
+    let Type::Path(path) = typing else { return false };
+
+    path.path.segments.last().is_some_and(|segment| {
+        segment.ident == "Option"
+            && matches!(&segment.arguments, PathArguments::AngleBracketed(args) if matches!(args.args.first(), Some(GenericArgument::Type(_))))
+    })
+}
+
+/// Naming used by the database: `pk`, `Sheet_fk` for `sheet_fk`, `TableName` for `table_name`
+fn default_column_name(field: &str) -> String {
+    let pascal = |words: &str| -> String {
+        words.split('_')
+            .map(|word| {
+                let mut chars = word.chars();
+                match chars.next() {
+                    Some(first) => first.to_uppercase().chain(chars).collect(),
+                    None => String::new(),
+                }
+            })
+            .collect()
+    };
+
+    match field.strip_suffix("_fk") {
+        _ if field == "pk" => field.to_string(),
+        Some(prefix) => format!("{}_fk", pascal(prefix)),
+        None => pascal(field),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_default_column_name() {
+        assert_eq!(default_column_name("pk"), "pk");
+        assert_eq!(default_column_name("table_name"), "TableName");
+        assert_eq!(default_column_name("last_edited_by_fk"), "LastEditedBy_fk");
+        assert_eq!(default_column_name("sheet_fk"), "Sheet_fk");
+    }
+
+    #[test]
+    fn check_parse_columns() {
+        let input: DBLoadInput = syn::parse_quote! {
+            #[derive(Debug)]
+            #[table("SHEET")]
+            pub struct Sheet {
+                #[pk]
+                pk: i32,
+                #[column("Desc")]
+                description: String,
+                days_to_refresh: Option<i32>,
+            }
+        };
+
+        assert_eq!(input.table_name.value(), "SHEET");
+        assert_eq!(input.cols.iter().map(|c| c.name.value()).collect::<Vec<_>>(), vec!["pk", "Desc", "DaysToRefresh"]);
+        assert_eq!(input.cols.iter().map(|c| c.optional).collect::<Vec<_>>(), vec![false, false, true]);
+        assert_eq!(input.cols.iter().map(|c| c.pk).collect::<Vec<_>>(), vec![true, false, false]);
+        assert_eq!(input.cols[2].constant, "COL_DAYS_TO_REFRESH");
+    }
+}
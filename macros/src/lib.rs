@@ -57,16 +57,43 @@ pub fn unpack(input: TokenStream) -> proc_macro::TokenStream {
 
 mod ddb;
 
-/// # Macro that implements DBLoad into any struct
+/// # Derive that implements DBLoad into any struct
 /// 
-/// TableStruct -> Is a struct that implements db_new, and its db columns names
-/// col1 -> Is a const str declared in TableStruct that represents the name of a column in sql where that value can't be null
-/// col1 -> Is a const str declared in TableStruct that represents the name of a column in sql where that value can be null
+/// #[table("TABLE_NAME")] -> Name of the table in sql
+/// #[column("Name")] -> Optional, name of the column in sql when it is not the default one
+///                      (`pk`, `Sheet_fk` for `sheet_fk`, `TableName` for `table_name`)
+/// #[pk] -> Column that is part of the primary key
 /// 
-/// impl_dbload![TableStruct, "TABLE_NAME", col1, col2?]
+/// Fields of type `Option<T>` are the columns where the value can be null.
+/// Every field gets a `COL_<FIELD>` const with the name of its column.
+/// Under `cfg(test)` every field also gets a `with_<field>` setter, to build fixtures from `Default`.
 /// 
-#[proc_macro]
+/// #[derive(DBLoad)]
+/// #[table("TABLE_NAME")]
+/// struct TableStruct { #[pk] pk: i32, #[column("LastEditedBy_fk")] last_edited_by_fk: i32, col2: Option<String> }
+/// 
+#[proc_macro_derive(DBLoad, attributes(table, column, pk))]
 pub fn dbload(input: TokenStream) -> TokenStream {
     let meta = parse_macro_input!(input as ddb::DBLoadInput);
     quote! { #meta }.into()
 }
+
+mod params;
+
+/// # Derive that implements ToSqlParameters into a table struct or a request
+/// 
+/// #[sql(table = TableStruct)] -> Optional, table whose `COL_*` consts name the columns, `Self` by default
+/// #[sql(rename = table_field)] -> Column of the field when the table field is named differently
+/// #[sql(skip_insert)] / #[sql(skip_update)] / #[sql(skip)] -> Fields left out of the inserts, the updates or both
+/// 
+/// The `pk` field is the identity of the table, it is never inserted nor updated.
+/// 
+/// #[derive(ToSqlParameters)]
+/// #[sql(table = SheetMetaData)]
+/// struct NewColumn { #[sql(rename = column_name)] name: String, optional: bool }
+/// 
+#[proc_macro_derive(ToSqlParameters, attributes(sql))]
+pub fn to_sql_parameters(input: TokenStream) -> TokenStream {
+    let meta = parse_macro_input!(input as params::ToSqlParametersInput);
+    quote! { #meta }.into()
+}
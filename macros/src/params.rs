@@ -0,0 +1,169 @@
+use quote::{ToTokens, format_ident, quote};
+use syn::{Attribute, Data, DeriveInput, Fields, Generics, Ident, Path, parse::{Parse, ParseStream}};
+
+pub struct ToSqlParametersInput {
+    struct_type: Ident,
+    generics: Generics,
+    /// Table struct holding the `COL_*` consts, `Self` when not given
+    table: Path,
+    fields: Vec<ParameterField>,
+}
+
+impl Parse for ToSqlParametersInput {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let input: DeriveInput = input.parse()?;
+
+        let mut table: Path = syn::parse_quote!(Self);
+        for attr in input.attrs.iter().filter(|attr| attr.path().is_ident("sql")) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("table") {
+                    table = meta.value()?.parse()?;
+                    return Ok(());
+                }
+
+                Err(meta.error("expected `table = TableStruct`"))
+            })?;
+        }
+
+        let Data::Struct(data) = &input.data else {
+            return Err(syn::Error::new(input.ident.span(), "ToSqlParameters can only be derived for structs"));
+        };
+
+        let Fields::Named(fields) = &data.fields else {
+            return Err(syn::Error::new(input.ident.span(), "ToSqlParameters needs named fields"));
+        };
+
+        let fields = fields.named.iter()
+            .map(ParameterField::from_field)
+            .collect::<syn::Result<Vec<_>>>()?;
+
+        Ok(ToSqlParametersInput { struct_type: input.ident, generics: input.generics, table, fields })
+    }
+}
+
+impl ToTokens for ToSqlParametersInput {
+    fn to_tokens(&self, tokens: &mut proc_macro2::TokenStream) {
+        let ToSqlParametersInput { struct_type, generics, table, fields } = &self;
+        let (impl_generics, type_generics, where_clause) = generics.split_for_impl();
+
+        let insert = fields.iter().filter(|field| field.insert()).map(|field| {
+            let ParameterField { field, constant, .. } = field;
+            quote!((<#table>::#constant, self.#field.clone().to_sql_value()))
+        });
+
+        let update = fields.iter().filter(|field| field.update()).map(|field| {
+            let ParameterField { field, constant, .. } = field;
+            quote!(parameters.insert(<#table>::#constant.to_string(), self.#field.clone().to_sql_value());)
+        });
+
+        tokens.extend(quote! {
+            #[allow(clippy::clone_on_copy)]
+            impl #impl_generics crate::ddb::ToSqlParameters for #struct_type #type_generics #where_clause {
+                fn to_insert_params(&self) -> Vec<(&'static str, crate::ddb::context::db_types::SqlValue)> {
+                    use crate::ddb::context::db_types::ToSqlValue;
+
+                    vec![ #( #insert ),* ]
+                }
+
+                fn to_update_params(&self) -> crate::ddb::context::db_types::SqlSingleParameters {
+                    use crate::ddb::context::db_types::ToSqlValue;
+
+                    let mut parameters = crate::ddb::context::db_types::SqlSingleParameters::new();
+                    #( #update )*
+                    parameters
+                }
+            }
+        })
+    }
+}
+
+struct ParameterField {
+    field: Ident,
+    /// `COL_<FIELD>` of the table struct, or of the field named by `rename`
+    constant: Ident,
+    /// Every `pk` column of the database is an identity
+    identity: bool,
+    skip_insert: bool,
+    skip_update: bool,
+}
+
+impl ParameterField {
+    fn from_field(field: &syn::Field) -> syn::Result<Self> {
+        let ident = field.ident.clone().expect("named field");
+
+        let mut column = ident.clone();
+        let mut skip_insert = false;
+        let mut skip_update = false;
+
+        for attr in field.attrs.iter().filter(|attr| attr.path().is_ident("sql")) {
+            parse_field_attribute(attr, &mut column, &mut skip_insert, &mut skip_update)?;
+        }
+
+        Ok(Self {
+            constant: format_ident!("COL_{}", column.to_string().to_uppercase()),
+            identity: column == "pk",
+            field: ident,
+            skip_insert,
+            skip_update,
+        })
+    }
+
+    fn insert(&self) -> bool {
+        !self.identity && !self.skip_insert
+    }
+
+    fn update(&self) -> bool {
+        !self.identity && !self.skip_update
+    }
+}
+
+fn parse_field_attribute(attr: &Attribute, column: &mut Ident, skip_insert: &mut bool, skip_update: &mut bool) -> syn::Result<()> {
+    attr.parse_nested_meta(|meta| {
+        if meta.path.is_ident("rename") {
+            *column = meta.value()?.parse()?;
+        } else if meta.path.is_ident("skip_insert") {
+            *skip_insert = true;
+        } else if meta.path.is_ident("skip_update") {
+            *skip_update = true;
+        } else if meta.path.is_ident("skip") {
+            *skip_insert = true;
+            *skip_update = true;
+        } else {
+            return Err(meta.error("expected `rename = table_field`, `skip`, `skip_insert` or `skip_update`"));
+        }
+
+        Ok(())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_parse_sql_attributes() {
+        let input: ToSqlParametersInput = syn::parse_quote! {
+            #[derive(Debug)]
+            #[sql(table = SheetMetaData)]
+            pub struct NewColumn {
+                pk: Option<i32>,
+                #[sql(rename = column_name, skip_update)]
+                name: String,
+                #[sql(skip_insert)]
+                optional: bool,
+                #[sql(skip)]
+                note: String,
+                description: String,
+            }
+        };
+
+        assert!(input.table.is_ident("SheetMetaData"));
+        assert_eq!(input.fields[1].constant, "COL_COLUMN_NAME");
+
+        let inserted = input.fields.iter().filter(|f| f.insert()).map(|f| f.field.to_string()).collect::<Vec<_>>();
+        assert_eq!(inserted, vec!["name", "description"]);
+
+        let updated = input.fields.iter().filter(|f| f.update()).map(|f| f.field.to_string()).collect::<Vec<_>>();
+        assert_eq!(updated, vec!["optional", "description"]);
+    }
+}
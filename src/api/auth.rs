@@ -0,0 +1,153 @@
+use crate::model;
+use crate::service;
+
+use axum::http::{ header, HeaderMap, StatusCode, request::Parts };
+use axum::Json;
+use axum::extract::{ FromRequestParts, Path, State };
+use axum::response::{ IntoResponse, Response };
+
+use super::AppState;
+
+/// Profile of the bearer token, every handler that takes it refuses unauthenticated requests with 401
+#[derive(Debug, Clone)]
+pub struct CurrentProfile {
+    pub pk: i32,
+    pub worker: i32,
+    /// `None` for super users
+    pub board: Option<i32>,
+    pub is_super_user: bool,
+    /// Manager or super user acting as this profile
+    pub impersonator: Option<i32>,
+}
+
+impl CurrentProfile {
+    /// `LastEditedBy_fk` of the changes, the impersonator when there is one
+    pub fn audit_pk(&self) -> i32 {
+        self.impersonator.unwrap_or(self.pk)
+    }
+}
+
+impl FromRequestParts<AppState> for CurrentProfile {
+    type Rejection = Response;
+
+    async fn from_request_parts(parts: &mut Parts, state: &AppState) -> Result<Self, Self::Rejection> {
+        let Some(token) = bearer_token(&parts.headers) else {
+            return Err(unauthorized());
+        };
+
+        let claims = match service::auth::decode_token(&state.keys, token) {
+            Ok(claims) => claims,
+            Err(e) => {
+                log::debug!("Token refused: {e}");
+                return Err(unauthorized());
+            }
+        };
+
+        match service::auth::token_profile(&state.pool, &claims).await {
+            Ok(Some(profile)) => Ok(Self {
+                pk: profile.pk(),
+                worker: profile.worker_fk(),
+                board: profile.board_fk(),
+                is_super_user: profile.is_super_user(),
+                impersonator: claims.impersonator,
+            }),
+            Ok(None) => Err(unauthorized()),
+            Err(e) => {
+                log::error!("Profile {} of the token could not be loaded: {e:?}", claims.profile);
+                Err(StatusCode::INTERNAL_SERVER_ERROR.into_response())
+            }
+        }
+    }
+}
+
+fn bearer_token(headers: &HeaderMap) -> Option<&str> {
+    let value = headers.get(header::AUTHORIZATION)?.to_str().ok()?;
+    let (scheme, token) = value.split_once(' ')?;
+
+    scheme.eq_ignore_ascii_case("Bearer").then(|| token.trim())
+}
+
+fn unauthorized() -> Response {
+    (StatusCode::UNAUTHORIZED, [(header::WWW_AUTHENTICATE, "Bearer")]).into_response()
+}
+
+pub async fn login(
+    State(state): State<AppState>,
+    Json(request): Json<model::LoginRequest>,
+) -> Response
+{
+    match service::auth::login(&state.pool, &state.keys, &request).await {
+        Ok(Some(token)) => (StatusCode::OK, Json(token)).into_response(),
+        Ok(None) => unauthorized(),
+        Err(e) => {
+            log::error!("Login failed: {e:?}");
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+pub async fn list_profiles(
+    State(state): State<AppState>,
+    profile: CurrentProfile,
+) -> Response
+{
+    match service::auth::worker_profiles(&state.pool, profile.worker, profile.pk).await {
+        Ok(profiles) => (StatusCode::OK, Json(profiles)).into_response(),
+        Err(e) => {
+            log::error!("Profiles of worker {} could not be listed: {e:?}", profile.worker);
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+pub async fn switch_profile(
+    State(state): State<AppState>,
+    profile: CurrentProfile,
+    Path(pk): Path<i32>,
+) -> Response
+{
+    // The impersonated worker picks their own profiles
+    if profile.impersonator.is_some() {
+        return StatusCode::FORBIDDEN.into_response();
+    }
+
+    match service::auth::switch_profile(&state.pool, &state.keys, profile.worker, pk).await {
+        Ok(Some(token)) => (StatusCode::OK, Json(token)).into_response(),
+        // Inactive or of another worker
+        Ok(None) => StatusCode::FORBIDDEN.into_response(),
+        Err(e) => {
+            log::error!("Worker {} could not switch to profile {pk}: {e:?}", profile.worker);
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+pub async fn impersonate(
+    State(state): State<AppState>,
+    profile: CurrentProfile,
+    Path(pk): Path<i32>,
+) -> Response
+{
+    let manager = match service::policy::load_manager(&state.pool, profile.pk, profile.board, profile.is_super_user).await {
+        Ok(manager) => manager.impersonated_by(profile.impersonator),
+        Err(e) => {
+            log::error!("Manager permissions of profile {} could not be loaded: {e:?}", profile.pk);
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+
+    match service::auth::impersonate(&state.pool, &state.keys, &manager, pk).await {
+        Ok(Some(token)) => {
+            log::info!("Profile {} is impersonating profile {pk}", profile.pk);
+            (StatusCode::OK, Json(token)).into_response()
+        }
+        Ok(None) => StatusCode::NOT_FOUND.into_response(),
+        Err(e) => match e.downcast::<model::PolicyRefused>() {
+            Ok(refused) => (StatusCode::FORBIDDEN, refused.message).into_response(),
+            Err(e) => {
+                log::error!("Profile {} could not impersonate profile {pk}: {e:?}", profile.pk);
+                StatusCode::INTERNAL_SERVER_ERROR.into_response()
+            }
+        }
+    }
+}
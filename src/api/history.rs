@@ -0,0 +1,74 @@
+use crate::model;
+use crate::service;
+
+use super::AppState;
+use super::auth::CurrentProfile;
+
+use axum::http::StatusCode;
+use axum::extract::{ Path, Query, State };
+use axum::response::{ IntoResponse, Response };
+
+pub async fn sheet_history(
+    State(state): State<AppState>,
+    profile: CurrentProfile,
+    Path(pk): Path<i32>,
+    Query(query): Query<model::ListQuery>,
+) -> Response
+{
+    if let Err(refused) = super::permission::require(&state.pool, &profile, pk, model::UploaderRight::ViewHist).await {
+        return refused;
+    }
+
+    super::list_response(service::listing::sheet_history(&state.pool, pk, &query).await, &format!("History of sheet {pk}"))
+}
+
+pub async fn sheet_columns_history(
+    State(state): State<AppState>,
+    profile: CurrentProfile,
+    Path(pk): Path<i32>,
+    Query(query): Query<model::ListQuery>,
+) -> Response
+{
+    if let Err(refused) = super::permission::require(&state.pool, &profile, pk, model::UploaderRight::ViewHist).await {
+        return refused;
+    }
+
+    super::list_response(service::listing::sheet_columns_history(&state.pool, pk, &query).await, &format!("Column history of sheet {pk}"))
+}
+
+pub async fn sheet_permissions_history(
+    State(state): State<AppState>,
+    profile: CurrentProfile,
+    Path(pk): Path<i32>,
+    Query(query): Query<model::ListQuery>,
+) -> Response
+{
+    if let Err(refused) = super::permission::require(&state.pool, &profile, pk, model::UploaderRight::ViewHist).await {
+        return refused;
+    }
+
+    super::list_response(service::listing::sheet_permissions_history(&state.pool, pk, &query).await, &format!("Permission history of sheet {pk}"))
+}
+
+pub async fn group_history(
+    State(state): State<AppState>,
+    profile: CurrentProfile,
+    Path(pk): Path<i32>,
+    Query(query): Query<model::ListQuery>,
+) -> Response
+{
+    let manager = match super::users::load_manager(&state.pool, &profile).await {
+        Ok(manager) => manager,
+        Err(response) => return response,
+    };
+
+    let listing = format!("History of group {pk}");
+    match service::listing::group_history(&state.pool, &manager, pk, &query).await {
+        Ok(Some(page)) => super::list_response(Ok(page), &listing),
+        Ok(None) => StatusCode::NOT_FOUND.into_response(),
+        Err(e) => match e.downcast::<model::PolicyRefused>() {
+            Ok(refused) => (StatusCode::FORBIDDEN, refused.message).into_response(),
+            Err(e) => super::list_response::<()>(Err(e), &listing),
+        }
+    }
+}
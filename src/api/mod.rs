@@ -1,5 +1,68 @@
+use std::sync::Arc;
+
 use super::model;
 use super::ddb;
+use super::service;
+
+use axum::{ Json, body::Body, http::{StatusCode, header}, response::{IntoResponse, Response} };
+use serde::Serialize;
 
+use ddb::context::pool;
+use ddb::context::functions::RowStream;
+
+pub mod auth;
+pub mod history;
+pub mod permission;
 pub mod root;
 pub mod sheet;
+pub mod upload;
+pub mod users;
+
+/// Shared by every route, cloned for each request
+#[derive(Clone)]
+pub struct AppState {
+    pool: pool::Pool,
+    keys: Arc<service::auth::TokenKeys>,
+}
+
+impl AppState {
+    /// Connects the pool and reads the token secret, so a wrong configuration stops the app at startup
+    pub async fn from_env() -> anyhow::Result<Self> {
+        Ok(Self {
+            pool: pool::from_env().await?,
+            keys: Arc::new(service::auth::TokenKeys::from_env()?),
+        })
+    }
+}
+
+/// 400 for a bad page or order, the page as JSON otherwise
+fn list_response<T: Serialize>(result: anyhow::Result<model::Paged<T>>, listing: &str) -> Response {
+    match result {
+        Ok(page) => (StatusCode::OK, Json(page)).into_response(),
+        Err(e) => match e.downcast::<model::InvalidListQuery>() {
+            Ok(invalid) => (StatusCode::BAD_REQUEST, invalid.message).into_response(),
+            Err(e) => {
+                log::error!("{listing} could not be listed: {e:?}");
+                StatusCode::INTERNAL_SERVER_ERROR.into_response()
+            }
+        }
+    }
+}
+
+/// Body written chunk by chunk. A failure halfway aborts the response, the status is already sent by then.
+fn stream_response(content_type: &str, file_name: &str, chunks: RowStream<Vec<u8>>, export: &str) -> Response {
+    let export = export.to_string();
+    let chunks = futures::StreamExt::inspect(chunks, move |chunk| {
+        if let Err(e) = chunk {
+            log::error!("{export} stopped halfway: {e:?}");
+        }
+    });
+
+    (
+        [
+            (header::CONTENT_TYPE, content_type.to_string()),
+            (header::CONTENT_DISPOSITION, format!("attachment; filename=\"{file_name}\"")),
+        ],
+        Body::from_stream(chunks),
+    ).into_response()
+}
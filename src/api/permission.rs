@@ -0,0 +1,37 @@
+use crate::ddb::context::pool::Pool;
+use crate::model;
+use crate::service;
+
+use axum::http::StatusCode;
+use axum::Json;
+use axum::extract::State;
+use axum::response::{ IntoResponse, Response };
+
+use super::AppState;
+use super::auth::CurrentProfile;
+
+pub async fn my_permissions(
+    State(state): State<AppState>,
+    profile: CurrentProfile,
+) -> Response
+{
+    match service::permission::effective_permissions(&state.pool, profile.pk, profile.is_super_user).await {
+        Ok(permissions) => (StatusCode::OK, Json(permissions)).into_response(),
+        Err(e) => {
+            log::error!("Permissions of profile {} could not be resolved: {e:?}", profile.pk);
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+/// 403 unless one of the groups of the profile grants `right` on the sheet
+pub(super) async fn require(pool: &Pool, profile: &CurrentProfile, sheet_pk: i32, right: model::UploaderRight) -> Result<(), Response> {
+    match service::permission::sheet_permission(pool, profile.pk, profile.is_super_user, sheet_pk).await {
+        Ok(permission) if permission.allows(right) => Ok(()),
+        Ok(_) => Err(StatusCode::FORBIDDEN.into_response()),
+        Err(e) => {
+            log::error!("Permission of profile {} on sheet {sheet_pk} could not be resolved: {e:?}", profile.pk);
+            Err(StatusCode::INTERNAL_SERVER_ERROR.into_response())
+        }
+    }
+}
@@ -1,5 +1,23 @@
-use axum::http::StatusCode;
+use axum::{ Json, extract::State, http::StatusCode, response::{IntoResponse, Response} };
+use serde_json::json;
+
+use super::AppState;
 
 pub async fn api_scream() -> StatusCode {
     StatusCode::FORBIDDEN
 }
+
+pub async fn health(State(state): State<AppState>) -> Response {
+    let pool = &state.pool;
+
+    if let Err(e) = pool.get().await {
+        log::error!("Database unreachable: {e:?}");
+        return StatusCode::SERVICE_UNAVAILABLE.into_response();
+    }
+
+    let pool_state = pool.state();
+    Json(json!({
+        "connections": pool_state.connections,
+        "idle_connections": pool_state.idle_connections,
+    })).into_response()
+}
@@ -1,20 +1,36 @@
 use crate::model;
 use crate::service;
 
-use axum::http::StatusCode;
+use super::AppState;
+use super::auth::CurrentProfile;
+
+use axum::http::{ header, StatusCode };
 use axum::Json;
-use axum::extract::{ Query, Multipart };
+use axum::extract::{ Query, Multipart, Path, State };
 use axum::extract;
+use axum::response::{ IntoResponse, Response };
 
-pub async fn list_sheets() {
-    
+pub async fn list_sheets(
+    State(state): State<AppState>,
+    _profile: CurrentProfile,
+    Query(query): Query<model::ListQuery>,
+) -> Response
+{
+    super::list_response(service::listing::list_sheets(&state.pool, &query).await, "Sheets")
 }
 
-#[axum_macros::debug_handler]
+#[axum_macros::debug_handler(state = AppState)]
 pub async fn add_sheet(
+    State(state): State<AppState>,
+    profile: CurrentProfile,
     mut multipart: Multipart,
-) -> StatusCode 
+) -> Response
 {
+    // Only super users interact with the sheet model system
+    if !profile.is_super_user {
+        return StatusCode::FORBIDDEN.into_response();
+    }
+
     let mut new_sheet: Option<model::NewSheetRequest> = None;
     let mut columns: Vec<model::NewSheetMetaDataRequest> = Vec::new();
     let mut model_file: Option<Vec<u8>> = None;
@@ -35,7 +51,7 @@ pub async fn add_sheet(
                 }
                 "model" => {
                     if model_file.is_some() {
-                        return StatusCode::BAD_REQUEST;
+                        return StatusCode::BAD_REQUEST.into_response();
                     }
     
                     if let Ok(data) = field.bytes().await {
@@ -47,14 +63,116 @@ pub async fn add_sheet(
         }
     }
 
-    if columns.len() == 0 { return StatusCode::BAD_REQUEST; }
+    if columns.len() == 0 { return StatusCode::BAD_REQUEST.into_response(); }
 
     if let Some(new_sheet) = new_sheet {
-        match service::add_sheet_to_db_(new_sheet, columns, 1, model_file).await {
-            Ok(_) => return StatusCode::OK,
-            Err(_) => return StatusCode::INTERNAL_SERVER_ERROR,
+        match service::add_sheet_to_db_(&state.pool, new_sheet, columns, profile.audit_pk(), model_file).await {
+            Ok(_) => return StatusCode::OK.into_response(),
+            Err(e) => match e.downcast::<model::ModelFileMismatch>() {
+                Ok(mismatch) => return (StatusCode::UNPROCESSABLE_ENTITY, Json(mismatch)).into_response(),
+                Err(e) => match e.downcast::<model::InvalidSheetDefinition>() {
+                    Ok(invalid) => return (StatusCode::UNPROCESSABLE_ENTITY, Json(invalid)).into_response(),
+                    Err(e) => {
+                        log::error!("Sheet could not be added: {e:?}");
+                        return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+                    }
+                }
+            }
+        }
+    }
+
+    StatusCode::BAD_REQUEST.into_response()
+}
+
+#[axum_macros::debug_handler(state = AppState)]
+pub async fn update_columns(
+    State(state): State<AppState>,
+    profile: CurrentProfile,
+    Path(pk): Path<i32>,
+    Json(columns): Json<Vec<model::EditSheetMetaDataRequest>>,
+) -> Response
+{
+    // Only super users interact with the sheet model system
+    if !profile.is_super_user {
+        return StatusCode::FORBIDDEN.into_response();
+    }
+
+    match service::meta_data::update_sheet_meta_data(&state.pool, pk, columns, profile.audit_pk()).await {
+        Ok(Some(())) => StatusCode::OK.into_response(),
+        Ok(None) => StatusCode::NOT_FOUND.into_response(),
+        Err(e) => match e.downcast::<model::ColumnChangesRefused>() {
+            Ok(refused) => (StatusCode::CONFLICT, Json(refused.changes)).into_response(),
+            Err(e) => {
+                log::error!("Columns of sheet {pk} could not be updated: {e:?}");
+                StatusCode::INTERNAL_SERVER_ERROR.into_response()
+            }
+        }
+    }
+}
+
+#[axum_macros::debug_handler(state = AppState)]
+pub async fn sheet_template(
+    State(state): State<AppState>,
+    _profile: CurrentProfile,
+    Path(pk): Path<i32>,
+    Query(query): Query<model::TemplateQuery>,
+) -> Response
+{
+    match service::template::sheet_template(&state.pool, pk, query.format).await {
+        Ok(Some((file_name, file))) => (
+            [
+                (header::CONTENT_TYPE, query.format.content_type().to_string()),
+                (header::CONTENT_DISPOSITION, format!("attachment; filename=\"{file_name}\"")),
+            ],
+            file,
+        ).into_response(),
+        Ok(None) => StatusCode::NOT_FOUND.into_response(),
+        Err(e) => {
+            log::error!("Template of sheet {pk} could not be generated: {e:?}");
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+#[axum_macros::debug_handler(state = AppState)]
+pub async fn store_model(
+    State(state): State<AppState>,
+    profile: CurrentProfile,
+    Path(pk): Path<i32>,
+    Query(query): Query<model::TemplateQuery>,
+) -> Response
+{
+    // Only super users interact with the sheet model system
+    if !profile.is_super_user {
+        return StatusCode::FORBIDDEN.into_response();
+    }
+
+    match service::template::store_model(&state.pool, pk, query.format, profile.audit_pk()).await {
+        Ok(Some(())) => StatusCode::OK.into_response(),
+        Ok(None) => StatusCode::NOT_FOUND.into_response(),
+        Err(e) => {
+            log::error!("Model of sheet {pk} could not be stored: {e:?}");
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
         }
     }
+}
 
-    StatusCode::BAD_REQUEST
-}
\ No newline at end of file
+pub async fn export_sheet(
+    State(state): State<AppState>,
+    profile: CurrentProfile,
+    Path(pk): Path<i32>,
+) -> Response
+{
+    if let Err(refused) = super::permission::require(&state.pool, &profile, pk, model::UploaderRight::ViewHist).await {
+        return refused;
+    }
+
+    match service::export::export_sheet(&state.pool, pk).await {
+        Ok(Some((file_name, file))) => super::stream_response("text/csv; charset=utf-8", &file_name, file, &format!("Export of sheet {pk}")),
+        Ok(None) => StatusCode::NOT_FOUND.into_response(),
+        Err(e) => {
+            log::error!("Export of sheet {pk} could not be started: {e:?}");
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
@@ -0,0 +1,127 @@
+use crate::model;
+use crate::service;
+
+use super::AppState;
+use super::auth::CurrentProfile;
+
+use axum::http::StatusCode;
+use axum::Json;
+use axum::extract::{ Multipart, Path, Query, State };
+use axum::response::{ IntoResponse, Response };
+
+#[axum_macros::debug_handler(state = AppState)]
+pub async fn upload_sheet(
+    State(state): State<AppState>,
+    profile: CurrentProfile,
+    Path(pk): Path<i32>,
+    Query(query): Query<model::UploadQuery>,
+    mut multipart: Multipart,
+) -> Response
+{
+    if let Err(refused) = super::permission::require(&state.pool, &profile, pk, model::UploaderRight::Upload).await {
+        return refused;
+    }
+
+    let mut file: Option<Vec<u8>> = None;
+    let mut worksheet: Option<String> = None;
+
+    while let Ok(Some(field)) = multipart.next_field().await {
+        if let Some(name) = field.name() {
+            match name {
+                "file" => {
+                    if file.is_some() {
+                        return StatusCode::BAD_REQUEST.into_response();
+                    }
+
+                    if let Ok(data) = field.bytes().await {
+                        file = Some(data.to_vec());
+                    }
+                }
+                "worksheet" => {
+                    if let Ok(data) = field.text().await {
+                        worksheet = Some(data);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    let Some(file) = file else { return StatusCode::BAD_REQUEST.into_response(); };
+
+    match service::upload::upload_file(&state.pool, pk, file, worksheet, profile.audit_pk(), query.dry_run).await {
+        Ok(Some(report)) if report.errors.is_empty() => (StatusCode::OK, Json(report)).into_response(),
+        Ok(Some(report)) => (StatusCode::UNPROCESSABLE_ENTITY, Json(report)).into_response(),
+        Ok(None) => StatusCode::NOT_FOUND.into_response(),
+        Err(e) => match e.downcast::<model::SheetConfigError>() {
+            // Not the uploader's fault, the sheet must be fixed by a super user
+            Ok(config_error) => {
+                log::warn!("Sheet {pk} is misconfigured: {config_error}");
+                (StatusCode::CONFLICT, Json(config_error)).into_response()
+            }
+            Err(e) => {
+                log::error!("Upload to sheet {pk} failed: {e:?}");
+                StatusCode::INTERNAL_SERVER_ERROR.into_response()
+            }
+        }
+    }
+}
+
+pub async fn list_uploads(
+    State(state): State<AppState>,
+    profile: CurrentProfile,
+    Path(pk): Path<i32>,
+    Query(query): Query<model::ListQuery>,
+) -> Response
+{
+    if let Err(refused) = super::permission::require(&state.pool, &profile, pk, model::UploaderRight::ViewHist).await {
+        return refused;
+    }
+
+    super::list_response(service::listing::list_uploads(&state.pool, pk, &query).await, &format!("Uploads of sheet {pk}"))
+}
+
+pub async fn export_uploads(
+    State(state): State<AppState>,
+    profile: CurrentProfile,
+    Path(pk): Path<i32>,
+) -> Response
+{
+    if let Err(refused) = super::permission::require(&state.pool, &profile, pk, model::UploaderRight::ViewHist).await {
+        return refused;
+    }
+
+    match service::export::export_uploads(&state.pool, pk).await {
+        Ok(uploads) => super::stream_response(
+            "application/x-ndjson",
+            &format!("uploads_{pk}.jsonl"),
+            uploads,
+            &format!("Export of the uploads of sheet {pk}"),
+        ),
+        Err(e) => {
+            log::error!("Export of the uploads of sheet {pk} could not be started: {e:?}");
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+pub async fn list_worksheets(
+    _profile: CurrentProfile,
+    mut multipart: Multipart,
+) -> Response
+{
+    let mut file: Option<Vec<u8>> = None;
+
+    while let Ok(Some(field)) = multipart.next_field().await {
+        if field.name() == Some("file") && let Ok(data) = field.bytes().await {
+            file = Some(data.to_vec());
+        }
+    }
+
+    let Some(file) = file else { return StatusCode::BAD_REQUEST.into_response(); };
+
+    match service::upload::list_worksheets(&file) {
+        Ok(names) => (StatusCode::OK, Json(names)).into_response(),
+        Err(e) => (StatusCode::BAD_REQUEST, e.to_string()).into_response(),
+    }
+}
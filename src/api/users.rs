@@ -0,0 +1,158 @@
+use crate::ddb::context::pool::Pool;
+use crate::model;
+use crate::service;
+
+use axum::http::StatusCode;
+use axum::Json;
+use axum::extract::{ Path, State };
+use axum::response::{ IntoResponse, Response };
+
+use service::policy::Manager;
+
+use super::AppState;
+use super::auth::CurrentProfile;
+
+pub(super) async fn load_manager(pool: &Pool, profile: &CurrentProfile) -> Result<Manager, Response> {
+    match service::policy::load_manager(pool, profile.pk, profile.board, profile.is_super_user).await {
+        Ok(manager) => Ok(manager.impersonated_by(profile.impersonator)),
+        Err(e) => {
+            log::error!("Manager permissions of profile {} could not be loaded: {e:?}", profile.pk);
+            Err(StatusCode::INTERNAL_SERVER_ERROR.into_response())
+        }
+    }
+}
+
+/// 403 with the broken rule, 409 for a taken Linde ID
+fn change_response<T: serde::Serialize>(result: anyhow::Result<Option<T>>, change: &str) -> Response {
+    match result {
+        Ok(Some(value)) => (StatusCode::OK, Json(value)).into_response(),
+        Ok(None) => StatusCode::NOT_FOUND.into_response(),
+        Err(e) => {
+            if let Some(refused) = e.downcast_ref::<model::PolicyRefused>() {
+                return (StatusCode::FORBIDDEN, refused.message.clone()).into_response();
+            }
+
+            if let Some(exists) = e.downcast_ref::<model::WorkerExists>() {
+                return (StatusCode::CONFLICT, exists.to_string()).into_response();
+            }
+
+            log::error!("{change} failed: {e:?}");
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+pub async fn add_worker(
+    State(state): State<AppState>,
+    profile: CurrentProfile,
+    Json(request): Json<model::NewWorkerRequest>,
+) -> Response
+{
+    let manager = match load_manager(&state.pool, &profile).await {
+        Ok(manager) => manager,
+        Err(response) => return response,
+    };
+
+    change_response(service::users::add_worker(&state.pool, &manager, request).await.map(Some), "Adding a worker")
+}
+
+pub async fn edit_worker(
+    State(state): State<AppState>,
+    profile: CurrentProfile,
+    Path(pk): Path<i32>,
+    Json(request): Json<model::EditWorkerRequest>,
+) -> Response
+{
+    let manager = match load_manager(&state.pool, &profile).await {
+        Ok(manager) => manager,
+        Err(response) => return response,
+    };
+
+    change_response(service::users::edit_worker(&state.pool, &manager, pk, request).await, &format!("Editing worker {pk}"))
+}
+
+pub async fn add_profile(
+    State(state): State<AppState>,
+    profile: CurrentProfile,
+    Json(request): Json<model::NewProfileRequest>,
+) -> Response
+{
+    let manager = match load_manager(&state.pool, &profile).await {
+        Ok(manager) => manager,
+        Err(response) => return response,
+    };
+
+    change_response(service::users::add_profile(&state.pool, &manager, request).await, "Adding a profile")
+}
+
+pub async fn remove_profile(
+    State(state): State<AppState>,
+    profile: CurrentProfile,
+    Path(pk): Path<i32>,
+) -> Response
+{
+    let manager = match load_manager(&state.pool, &profile).await {
+        Ok(manager) => manager,
+        Err(response) => return response,
+    };
+
+    change_response(service::users::remove_profile(&state.pool, &manager, pk).await, &format!("Removing profile {pk}"))
+}
+
+pub async fn edit_profile_groups(
+    State(state): State<AppState>,
+    profile: CurrentProfile,
+    Path(pk): Path<i32>,
+    Json(group_pks): Json<Vec<i32>>,
+) -> Response
+{
+    let manager = match load_manager(&state.pool, &profile).await {
+        Ok(manager) => manager,
+        Err(response) => return response,
+    };
+
+    change_response(service::users::edit_profile_groups(&state.pool, &manager, pk, group_pks).await, &format!("Editing the groups of profile {pk}"))
+}
+
+pub async fn add_group(
+    State(state): State<AppState>,
+    profile: CurrentProfile,
+    Json(request): Json<model::NewGroupRequest>,
+) -> Response
+{
+    let manager = match load_manager(&state.pool, &profile).await {
+        Ok(manager) => manager,
+        Err(response) => return response,
+    };
+
+    change_response(service::users::add_group(&state.pool, &manager, request).await.map(Some), "Adding a group")
+}
+
+pub async fn remove_group(
+    State(state): State<AppState>,
+    profile: CurrentProfile,
+    Path(pk): Path<i32>,
+) -> Response
+{
+    let manager = match load_manager(&state.pool, &profile).await {
+        Ok(manager) => manager,
+        Err(response) => return response,
+    };
+
+    change_response(service::users::remove_group(&state.pool, &manager, pk).await, &format!("Removing group {pk}"))
+}
+
+pub async fn edit_group_permissions(
+    State(state): State<AppState>,
+    profile: CurrentProfile,
+    Path(pk): Path<i32>,
+    Json(permissions): Json<Vec<model::GroupPermissionRequest>>,
+) -> Response
+{
+    let manager = match load_manager(&state.pool, &profile).await {
+        Ok(manager) => manager,
+        Err(response) => return response,
+    };
+
+    change_response(service::users::edit_group_permissions(&state.pool, &manager, pk, permissions).await, &format!("Editing the permissions of group {pk}"))
+}
@@ -1,22 +1,75 @@
-use std::iter::{Map, Zip};
-use std::slice::Iter;
+use std::collections::HashSet;
+use std::ops::Deref;
 
-use tiberius::Query;
+use super::{GenericTable, SqlMultipleParameters, SqlSingleParameters, SqlValue};
 
-use super::{SqlMultipleParameters, SqlSingleParameters, SqlValue};
+pub enum ChainStepKind<'a> {
+    Exec(ChainExec<'a>, Option<SqlMultipleParameters>, Option<SqlSingleParameters>),
+    BulkInsert(BulkInsert),
+}
+
+/// What a step keeps in the `ChainContext` for the steps after it
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum StepReturns {
+    #[default]
+    RowsAffected,
+    /// `SCOPE_IDENTITY()` of the insert
+    Identity,
+    /// First column of the first row
+    Scalar,
+    Rows,
+}
+
+pub struct ChainStep<'a> {
+    name: String,
+    critical: bool,
+    returns: StepReturns,
+    kind: ChainStepKind<'a>,
+}
+
+impl<'a> ChainStep<'a> {
+    /// Key of the step output, unique in the chain. Defaults to `step <position>`.
+    pub fn named(&mut self, name: &str) -> &mut Self {
+        self.name = name.to_string();
+        self
+    }
+
+    /// Runs behind a `SAVE TRANSACTION`, a failure is undone and recorded instead of ending the chain
+    pub fn non_critical(&mut self) -> &mut Self {
+        self.critical = false;
+        self
+    }
+
+    pub fn returns(&mut self, returns: StepReturns) -> &mut Self {
+        self.returns = returns;
+        self
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn critical(&self) -> bool {
+        self.critical
+    }
+
+    pub fn returns_kind(&self) -> StepReturns {
+        self.returns
+    }
+
+    pub fn into_kind(self) -> ChainStepKind<'a> {
+        self.kind
+    }
+}
 
 pub struct ChainMap<'a> {
-    execs: Vec<ChainExec<'a>>,
-    mults: Vec<Option<SqlMultipleParameters>>,
-    sings: Vec<Option<SqlSingleParameters>>,
+    steps: Vec<ChainStep<'a>>,
 }
 
 impl<'a> ChainMap<'a> {
     pub fn new() -> Self {
         Self {
-            execs: vec![],
-            mults: vec![], 
-            sings: vec![], 
+            steps: vec![],
         }
     }
 
@@ -25,38 +78,230 @@ impl<'a> ChainMap<'a> {
         exec: ChainExec<'a>,
         mult: Option<SqlMultipleParameters>,
         sing: Option<SqlSingleParameters>
-    ) {
-        self.execs.push(exec);
-        self.mults.push(mult);
-        self.sings.push(sing);
+    ) -> &mut ChainStep<'a> {
+        self.push_step(ChainStepKind::Exec(exec, mult, sing))
+    }
+
+    pub fn push_bulk_insert(&mut self, bulk: BulkInsert) -> &mut ChainStep<'a> {
+        self.push_step(ChainStepKind::BulkInsert(bulk))
+    }
+
+    fn push_step(&mut self, kind: ChainStepKind<'a>) -> &mut ChainStep<'a> {
+        let name = format!("step {}", self.steps.len() + 1);
+        self.steps.push(ChainStep { name, critical: true, returns: StepReturns::default(), kind });
+
+        self.steps.last_mut().expect("step was just pushed")
+    }
+
+    pub fn len(&self) -> usize {
+        self.steps.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.steps.is_empty()
+    }
+
+    /// Outputs are looked up by name, two steps can not share one
+    pub fn check_names(&self) -> anyhow::Result<()> {
+        let mut names = HashSet::new();
+
+        for step in &self.steps {
+            anyhow::ensure!(names.insert(step.name()), "Chain has more than one step named '{}'", step.name());
+        }
+
+        Ok(())
     }
 }
 
 impl<'a> IntoIterator for ChainMap<'a> {
-    type Item = (ChainExec<'a>, Option<SqlMultipleParameters>, Option<SqlSingleParameters>);
-    type IntoIter = 
-    Map<
-        Zip<Zip<
-                std::vec::IntoIter<ChainExec<'a>>, 
-                std::vec::IntoIter<Option<SqlMultipleParameters>>
-            >,
-            std::vec::IntoIter<Option<SqlSingleParameters>>
-        >,
-        fn(((ChainExec<'a>, Option<SqlMultipleParameters>), Option<SqlSingleParameters>)) -> Self::Item
-    >;
+    type Item = ChainStep<'a>;
+    type IntoIter = std::vec::IntoIter<ChainStep<'a>>;
 
     fn into_iter(self) -> Self::IntoIter {
-        self.execs.into_iter()
-            .zip(self.mults.into_iter())
-            .zip(self.sings.into_iter())
-            .map(|((exec, mult), sing)| (exec, mult, sing))
+        self.steps.into_iter()
+    }
+}
+
+/// Rows loaded with a TDS bulk insert, no statement nor parameters are built for them.
+/// Each row has one value per column of `table`, in the same order, and the values are sent with the column typing.
+#[derive(Debug)]
+pub struct BulkInsert {
+    table: GenericTable,
+    rows: Vec<Vec<SqlValue>>,
+}
+
+impl BulkInsert {
+    pub fn new(table: GenericTable, rows: Vec<Vec<SqlValue>>) -> Self {
+        Self { table, rows }
+    }
+
+    pub fn table(&self) -> &GenericTable {
+        &self.table
+    }
+
+    pub fn rows(&self) -> &[Vec<SqlValue>] {
+        &self.rows
+    }
+}
+
+#[derive(Debug)]
+pub enum StepOutput {
+    /// One count per statement of the step
+    RowsAffected(Vec<u64>),
+    Identity(i64),
+    Scalar(SqlValue),
+    Rows(Vec<tiberius::Row>),
+    /// Non critical step that failed, rolled back to its savepoint
+    Skipped(String),
+}
+
+/// Global values and the outputs of the steps already run.
+/// Derefs to the globals, which is what most steps read.
+#[derive(Debug, Default)]
+pub struct ChainContext {
+    globals: SqlSingleParameters,
+    outputs: Vec<(String, StepOutput)>,
+}
+
+impl ChainContext {
+    pub fn new(globals: SqlSingleParameters) -> Self {
+        Self { globals, outputs: Vec::new() }
+    }
+
+    pub fn set_global(&mut self, name: String, value: SqlValue) {
+        self.globals.insert(name, value);
+    }
+
+    pub fn push_output(&mut self, step: &str, output: StepOutput) {
+        self.outputs.push((step.to_string(), output));
+    }
+
+    pub fn globals(&self) -> &SqlSingleParameters {
+        &self.globals
+    }
+
+    pub fn output(&self, step: &str) -> Option<&StepOutput> {
+        self.outputs.iter()
+            .find(|(name, _)| name == step)
+            .map(|(_, output)| output)
+    }
+
+    /// Outputs in the order the steps ran
+    pub fn outputs(&self) -> impl Iterator<Item = (&str, &StepOutput)> {
+        self.outputs.iter().map(|(name, output)| (name.as_str(), output))
+    }
+
+    pub fn identity(&self, step: &str) -> anyhow::Result<i64> {
+        match self.output(step) {
+            Some(StepOutput::Identity(identity)) => Ok(*identity),
+            other => Err(unexpected_output(step, "an identity", other)),
+        }
+    }
+
+    pub fn scalar(&self, step: &str) -> anyhow::Result<&SqlValue> {
+        match self.output(step) {
+            Some(StepOutput::Scalar(value)) => Ok(value),
+            other => Err(unexpected_output(step, "a scalar", other)),
+        }
+    }
+
+    pub fn rows(&self, step: &str) -> anyhow::Result<&[tiberius::Row]> {
+        match self.output(step) {
+            Some(StepOutput::Rows(rows)) => Ok(rows),
+            other => Err(unexpected_output(step, "rows", other)),
+        }
+    }
+
+    /// Non critical steps that failed, with their error
+    pub fn skipped(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.outputs.iter().filter_map(|(name, output)| match output {
+            StepOutput::Skipped(error) => Some((name.as_str(), error.as_str())),
+            _ => None,
+        })
     }
 }
 
+impl Deref for ChainContext {
+    type Target = SqlSingleParameters;
+
+    fn deref(&self) -> &Self::Target {
+        &self.globals
+    }
+}
+
+fn unexpected_output(step: &str, expected: &str, found: Option<&StepOutput>) -> anyhow::Error {
+    match found {
+        Some(StepOutput::Skipped(error)) => anyhow::anyhow!("Step '{step}' was skipped: {error}"),
+        Some(_) => anyhow::anyhow!("Step '{step}' did not return {expected}"),
+        None => anyhow::anyhow!("No step '{step}' ran before"),
+    }
+}
+
+/// Context added to the error of the step that ended a chain, the original error can still be downcast
+#[derive(Debug, derive_more::Display, derive_more::Error)]
+#[display("Step '{step}' failed: {message} (SQL: {sql})")]
+pub struct ChainStepFailed {
+    pub step: String,
+    /// Empty when the step failed before building its statement
+    pub sql: String,
+    pub message: String,
+}
+
 pub type ChainReturn = anyhow::Result<(String, Option<SqlSingleParameters>, Option<String>)>;
 
 pub type ChainExec<'a> = &'a (dyn Fn(
     Option<SqlMultipleParameters>,
-    Option<SqlSingleParameters>, 
-    &SqlSingleParameters
+    Option<SqlSingleParameters>,
+    &ChainContext
 ) -> ChainReturn + Send + Sync);
+
+/// Owned step, for executions built around values that are not sql parameters (e.g. DDL)
+pub type ChainExecBox = Box<dyn Fn(
+    Option<SqlMultipleParameters>,
+    Option<SqlSingleParameters>,
+    &ChainContext
+) -> ChainReturn + Send + Sync>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::st;
+
+    fn nothing(
+        mult: Option<SqlMultipleParameters>,
+        sing: Option<SqlSingleParameters>,
+        glob: &ChainContext
+    ) -> ChainReturn {
+        Ok((st!(""), None, None))
+    }
+
+    #[test]
+    fn check_step_names() {
+        let mut chain_map = ChainMap::new();
+        chain_map.push(&nothing, None, None).named("sheet").returns(StepReturns::Identity);
+        chain_map.push(&nothing, None, None).non_critical();
+        assert!(chain_map.check_names().is_ok());
+
+        let steps = chain_map.into_iter().collect::<Vec<_>>();
+        assert_eq!((steps[0].name(), steps[0].critical(), steps[0].returns_kind()), ("sheet", true, StepReturns::Identity));
+        assert_eq!((steps[1].name(), steps[1].critical()), ("step 2", false));
+
+        let mut chain_map = ChainMap::new();
+        chain_map.push(&nothing, None, None).named("step 2");
+        chain_map.push(&nothing, None, None);
+        assert!(chain_map.check_names().is_err());
+    }
+
+    #[test]
+    fn check_context_outputs() {
+        let mut context = ChainContext::new(SqlSingleParameters::new());
+        context.push_output("sheet", StepOutput::Identity(7));
+        context.push_output("permissions", StepOutput::Skipped(st!("Deadlock")));
+
+        assert_eq!(context.identity("sheet").unwrap(), 7);
+        assert!(context.scalar("sheet").is_err());
+        assert!(context.identity("permissions").unwrap_err().to_string().contains("skipped: Deadlock"));
+        assert!(context.identity("board").is_err());
+        assert_eq!(context.skipped().collect::<Vec<_>>(), vec![("permissions", "Deadlock")]);
+    }
+}
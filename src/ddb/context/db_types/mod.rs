@@ -7,6 +7,12 @@ pub use sql_value::*;
 mod chain;
 pub use chain::*;
 
+mod select;
+pub use select::*;
+
+mod predicate;
+pub use predicate::*;
+
 
 #[derive(Debug, Clone)]
 pub struct GenericTable {
@@ -14,6 +20,20 @@ pub struct GenericTable {
     columns: Vec<GenericColumn>
 }
 
+impl GenericTable {
+    pub fn new(name: String, columns: Vec<GenericColumn>) -> Self {
+        Self { name, columns }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn columns(&self) -> &[GenericColumn] {
+        &self.columns
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct GenericColumn {
     name: String,
@@ -21,6 +41,31 @@ pub struct GenericColumn {
     optional: bool,
 }
 
+impl GenericColumn {
+    pub fn new(name: String, typing: tiberius::ColumnType, optional: bool) -> Self {
+        Self { name, typing, optional }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn typing(&self) -> tiberius::ColumnType {
+        self.typing
+    }
+
+    pub fn optional(&self) -> bool {
+        self.optional
+    }
+
+    /// Column as written in a `CREATE TABLE`, e.g. `[Name] NVARCHAR(MAX) NOT NULL`
+    pub fn sql_definition(&self) -> anyhow::Result<String> {
+        let nullable = if self.optional { "NULL" } else { "NOT NULL" };
+
+        Ok(format!("[{}] {} {nullable}", self.name, self.typing.to_sql_type_name()?))
+    }
+}
+
 
 pub trait ToGenericColumnType {
     fn to_generic_column_type(&self) -> anyhow::Result<tiberius::ColumnType>;
@@ -69,3 +114,52 @@ impl ToGenericColumnType for String {
         }
     }
 }
+
+pub trait ToSqlTypeName {
+    fn to_sql_type_name(&self) -> anyhow::Result<&'static str>;
+}
+
+/// Scale of the `DECIMAL(38, 10)` sheet columns
+pub const DECIMAL_SCALE: u8 = 10;
+
+// Inverse of `ToGenericColumnType`, used to create the sheet data tables
+impl ToSqlTypeName for tiberius::ColumnType {
+    fn to_sql_type_name(&self) -> anyhow::Result<&'static str> {
+        match self {
+            // Integer types
+            tiberius::ColumnType::Int4 => Ok("INT"),
+            tiberius::ColumnType::Int8 => Ok("BIGINT"),
+            tiberius::ColumnType::Int2 => Ok("SMALLINT"),
+            tiberius::ColumnType::Int1 => Ok("TINYINT"),
+
+            // Float types
+            tiberius::ColumnType::Float8 => Ok("FLOAT"),
+            tiberius::ColumnType::Float4 => Ok("REAL"),
+            tiberius::ColumnType::Decimaln => Ok("DECIMAL(38, 10)"),
+
+            // Boolean types
+            tiberius::ColumnType::Bit => Ok("BIT"),
+
+            // String types
+            tiberius::ColumnType::BigVarChar => Ok("VARCHAR(MAX)"),
+            tiberius::ColumnType::NVarchar => Ok("NVARCHAR(MAX)"),
+
+            // Date/Time types
+            tiberius::ColumnType::Daten => Ok("DATE"),
+            tiberius::ColumnType::Timen => Ok("TIME"),
+            tiberius::ColumnType::Datetime2 => Ok("DATETIME2"),
+            tiberius::ColumnType::DatetimeOffsetn => Ok("DATETIMEOFFSET"),
+
+            // Binary types
+            tiberius::ColumnType::BigVarBin => Ok("VARBINARY(MAX)"),
+
+            tiberius::ColumnType::Guid => Ok("UNIQUEIDENTIFIER"),
+            tiberius::ColumnType::Xml => Ok("XML"),
+
+            _ => Err(anyhow::anyhow!(
+                "No SQL type for '{:?}'. Please see './src/ddb/context/db_types::ToSqlTypeName' before adding types",
+                self
+            ))
+        }
+    }
+}
@@ -0,0 +1,166 @@
+use super::{SqlSingleParameters, SqlValue, ToSqlValue};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Comparison {
+    Eq,
+    NotEq,
+    Lt,
+    LtEq,
+    Gt,
+    GtEq,
+    Like,
+    NotLike,
+    /// The value must be a list
+    In,
+    /// The value must be a list
+    NotIn,
+}
+
+impl Comparison {
+    pub fn to_sql(self) -> &'static str {
+        match self {
+            Comparison::Eq => "=",
+            Comparison::NotEq => "<>",
+            Comparison::Lt => "<",
+            Comparison::LtEq => "<=",
+            Comparison::Gt => ">",
+            Comparison::GtEq => ">=",
+            Comparison::Like => "LIKE",
+            Comparison::NotLike => "NOT LIKE",
+            Comparison::In => "IN",
+            Comparison::NotIn => "NOT IN",
+        }
+    }
+}
+
+/// Condition tree for a `WHERE`, e.g.
+/// `Predicate::or(vec![Predicate::eq("Active", true), Predicate::eq("IsSuperUser", true)])`
+#[derive(Debug, Clone)]
+pub enum Predicate {
+    Compare { column: String, comparison: Comparison, value: SqlValue },
+    Between { column: String, low: SqlValue, high: SqlValue },
+    IsNull(String),
+    IsNotNull(String),
+    Not(Box<Predicate>),
+    /// Empty is always true
+    And(Vec<Predicate>),
+    /// Empty is always false
+    Or(Vec<Predicate>),
+}
+
+impl Predicate {
+    pub fn compare(column: &str, comparison: Comparison, value: impl ToSqlValue) -> Self {
+        Predicate::Compare { column: column.to_string(), comparison, value: value.to_sql_value() }
+    }
+
+    pub fn eq(column: &str, value: impl ToSqlValue) -> Self {
+        Self::compare(column, Comparison::Eq, value)
+    }
+
+    pub fn not_eq(column: &str, value: impl ToSqlValue) -> Self {
+        Self::compare(column, Comparison::NotEq, value)
+    }
+
+    pub fn lt(column: &str, value: impl ToSqlValue) -> Self {
+        Self::compare(column, Comparison::Lt, value)
+    }
+
+    pub fn lt_eq(column: &str, value: impl ToSqlValue) -> Self {
+        Self::compare(column, Comparison::LtEq, value)
+    }
+
+    pub fn gt(column: &str, value: impl ToSqlValue) -> Self {
+        Self::compare(column, Comparison::Gt, value)
+    }
+
+    pub fn gt_eq(column: &str, value: impl ToSqlValue) -> Self {
+        Self::compare(column, Comparison::GtEq, value)
+    }
+
+    pub fn like(column: &str, value: impl ToSqlValue) -> Self {
+        Self::compare(column, Comparison::Like, value)
+    }
+
+    pub fn is_in(column: &str, values: impl ToSqlValue) -> Self {
+        Self::compare(column, Comparison::In, values)
+    }
+
+    pub fn not_in(column: &str, values: impl ToSqlValue) -> Self {
+        Self::compare(column, Comparison::NotIn, values)
+    }
+
+    pub fn between(column: &str, low: impl ToSqlValue, high: impl ToSqlValue) -> Self {
+        Predicate::Between { column: column.to_string(), low: low.to_sql_value(), high: high.to_sql_value() }
+    }
+
+    pub fn is_null(column: &str) -> Self {
+        Predicate::IsNull(column.to_string())
+    }
+
+    pub fn is_not_null(column: &str) -> Self {
+        Predicate::IsNotNull(column.to_string())
+    }
+
+    pub fn not(predicate: Predicate) -> Self {
+        Predicate::Not(Box::new(predicate))
+    }
+
+    pub fn and(predicates: Vec<Predicate>) -> Self {
+        Predicate::And(predicates)
+    }
+
+    pub fn or(predicates: Vec<Predicate>) -> Self {
+        Predicate::Or(predicates)
+    }
+
+    /// Condition of `SqlSingleParameters` used as a filter, one comparison per column in the order of their names
+    pub fn from_parameters(parameters: &SqlSingleParameters) -> Self {
+        let mut parameters = parameters.iter().collect::<Vec<_>>();
+        parameters.sort_by_key(|(column, _)| *column);
+
+        let predicates = parameters.into_iter()
+            .map(|(column, value)| {
+                let comparison = match value {
+                    SqlValue::IntList(_) | SqlValue::FloatList(_) | SqlValue::StrList(_) => Comparison::In,
+                    SqlValue::StrL(_) => Comparison::Like,
+                    _ => Comparison::Eq,
+                };
+
+                Predicate::Compare { column: column.clone(), comparison, value: value.clone() }
+            })
+            .collect();
+
+        Predicate::And(predicates)
+    }
+}
+
+/// What the select, update and delete builders filter rows by
+#[derive(Debug, Clone, Copy)]
+pub enum Filter<'a> {
+    /// No `WHERE`, every row
+    All,
+    /// Every column equal to its value, see `Predicate::from_parameters`
+    Parameters(&'a SqlSingleParameters),
+    Predicate(&'a Predicate),
+}
+
+impl<'a> From<Option<&'a SqlSingleParameters>> for Filter<'a> {
+    fn from(parameters: Option<&'a SqlSingleParameters>) -> Self {
+        match parameters {
+            Some(parameters) => Filter::Parameters(parameters),
+            None => Filter::All,
+        }
+    }
+}
+
+impl<'a> From<&'a SqlSingleParameters> for Filter<'a> {
+    fn from(parameters: &'a SqlSingleParameters) -> Self {
+        Filter::Parameters(parameters)
+    }
+}
+
+impl<'a> From<&'a Predicate> for Filter<'a> {
+    fn from(predicate: &'a Predicate) -> Self {
+        Filter::Predicate(predicate)
+    }
+}
@@ -0,0 +1,77 @@
+/// Largest page a listing can ask for
+pub const MAX_PAGE_SIZE: u32 = 500;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum OrderDirection {
+    #[default]
+    Asc,
+    Desc,
+}
+
+impl OrderDirection {
+    pub fn to_sql(self) -> &'static str {
+        match self {
+            OrderDirection::Asc => "ASC",
+            OrderDirection::Desc => "DESC",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct OrderBy {
+    column: String,
+    direction: OrderDirection,
+}
+
+impl OrderBy {
+    pub fn new(column: &str, direction: OrderDirection) -> Self {
+        Self { column: column.to_string(), direction }
+    }
+
+    pub fn asc(column: &str) -> Self {
+        Self::new(column, OrderDirection::Asc)
+    }
+
+    pub fn desc(column: &str) -> Self {
+        Self::new(column, OrderDirection::Desc)
+    }
+
+    pub fn column(&self) -> &str {
+        &self.column
+    }
+
+    pub fn direction(&self) -> OrderDirection {
+        self.direction
+    }
+}
+
+/// 1-based page of `size` rows, written as `OFFSET ... FETCH NEXT ...`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Page {
+    number: u32,
+    size: u32,
+}
+
+impl Page {
+    pub fn new(number: u32, size: u32) -> anyhow::Result<Self> {
+        anyhow::ensure!(number > 0, "Pages start at 1");
+        anyhow::ensure!(
+            (1..=MAX_PAGE_SIZE).contains(&size),
+            "Page size must be between 1 and {MAX_PAGE_SIZE}"
+        );
+
+        Ok(Self { number, size })
+    }
+
+    pub fn number(&self) -> u32 {
+        self.number
+    }
+
+    pub fn size(&self) -> u32 {
+        self.size
+    }
+
+    pub fn offset(&self) -> u64 {
+        (self.number as u64 - 1) * self.size as u64
+    }
+}
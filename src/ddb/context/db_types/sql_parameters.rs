@@ -2,6 +2,7 @@ use std::{any, collections::{HashMap, HashSet}, marker::PhantomData};
 use anyhow::Ok;
 
 use super::SqlValue;
+use crate::ddb::ToSqlParameters;
 
 pub type SqlSingleParameters = HashMap<String, SqlValue>;
 
@@ -71,6 +72,20 @@ impl SqlMultipleParameters {
         Ok(())
     }
 
+    /// Line with the insert values of `row`
+    pub fn add_row(&mut self, row: &impl ToSqlParameters) -> anyhow::Result<()> {
+        self.add_line(row.to_insert_params())
+    }
+
+    pub fn from_rows<'a, T: ToSqlParameters + 'a>(rows: impl IntoIterator<Item = &'a T>) -> anyhow::Result<Self> {
+        let mut parameters = Self::new();
+        for row in rows {
+            parameters.add_row(row)?;
+        }
+
+        Ok(parameters)
+    }
+
     pub fn add_const_column(&mut self, value: SqlValue, name: &str) {
         let idx = self.len();
         self.core.insert(name.to_string(), idx);
@@ -90,10 +105,16 @@ impl SqlMultipleParameters {
     }
 
     pub fn hight(&self) -> usize {
-        self.columns[0].len()
+        self.columns.first().map_or(0, Vec::len)
     }
 
-    fn get_column_idx(&self, column_name: &str) -> anyhow::Result<usize> {
+    /// Parameter name of the value of column `column_idx` in line `line_idx`, see `to_single`
+    pub fn tag_name(column_idx: usize, line_idx: usize) -> String {
+        format!("c{column_idx}_{line_idx}")
+    }
+
+    /// Index of `column_name`, insert tags are built from it since column names may hold spaces or accents
+    pub fn get_column_idx(&self, column_name: &str) -> anyhow::Result<usize> {
         self.core
             .get(column_name)
             .copied()
@@ -110,9 +131,9 @@ impl SqlMultipleParameters {
         let mut single = SqlSingleParameters::new();
 
 
-        for (key, column_idx) in self.core {
+        for column_idx in self.core.into_values() {
             for (line_idx, line_value) in self.columns[column_idx].iter().enumerate() {
-                single.insert(format!("{key}_{line_idx}"), line_value.clone());
+                single.insert(Self::tag_name(column_idx, line_idx), line_value.clone());
             }
         }
 
@@ -161,5 +182,28 @@ mod tests {
         ]);
 
         let single = mult.to_single();
+        assert_eq!(single.len(), 9);
+        assert!(matches!(single["c1_2"], SqlValue::Int(8)));
+    }
+
+    #[test]
+    fn check_rows_to_parameters() {
+        let upload = Upload::default()
+            .with_pk(7)
+            .with_sheet_fk(3)
+            .with_file_uploaded(vec![1, 2])
+            .with_uploaded_by_fk(1)
+            .with_sheet_used(Some(st!("Sheet1")));
+
+        let columns = upload.to_insert_params().into_iter().map(|(name, _)| name).collect::<Vec<_>>();
+        assert_eq!(columns, vec![Upload::COL_SHEET_FK, Upload::COL_FILE_UPLOADED, Upload::COL_UPLOADED_BY_FK, Upload::COL_SHEET_USED]);
+
+        let update = upload.to_update_params();
+        assert!(!update.contains_key(Upload::COL_PK));
+        assert!(matches!(update.get(Upload::COL_UPLOADED_AT), Some(SqlValue::DateTime(_))));
+
+        let mult = SqlMultipleParameters::from_rows(&[upload]).unwrap();
+        assert_eq!((mult.len(), mult.hight()), (4, 1));
+        assert!(matches!(mult.get_value(Upload::COL_SHEET_USED, 0).unwrap(), SqlValue::Str(used) if used == "Sheet1"));
     }
-}
\ No newline at end of file
+}
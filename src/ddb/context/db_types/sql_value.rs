@@ -1,8 +1,13 @@
-use crate::{ddb::context::utils::escape, st};
+use crate::st;
 use crate::impl_to_sql_value;
 
-use tiberius::Query;
+use std::borrow::Cow;
+
+use tiberius::{ColumnData, ColumnType, FromSql, IntoSql, Query};
+use tiberius::{numeric::Numeric, xml::XmlData};
 use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
+
+use super::DECIMAL_SCALE;
 use serde::de::value;
 
 #[derive(Debug, Clone)]
@@ -48,9 +53,9 @@ impl SqlValue {
 
             SqlValue::Bool(v) => format!("{}", if *v { 1 } else { 0 }),
 
-            SqlValue::Str(v) => format!("{}", escape(v)),
+            SqlValue::Str(v) => v.clone(),
 
-            SqlValue::StrL(v) => format!("{}", escape(v)),
+            SqlValue::StrL(v) => v.clone(),
 
             SqlValue::Date(v) => format!("{v}"),
 
@@ -72,7 +77,7 @@ impl SqlValue {
 
             SqlValue::StrList(list) => list
                 .iter()
-                .map(|v| format!("'{}'", escape(v)))
+                .map(|v| format!("'{v}'"))
                 .collect::<Vec<_>>()
                 .join(", "),
 
@@ -80,63 +85,279 @@ impl SqlValue {
 
             SqlValue::Guid(v) => format!("{v}"),
 
-            SqlValue::Xml(v) => format!("{}", escape(v)),
+            SqlValue::Xml(v) => v.clone(),
 
             SqlValue::None => format!("NULL"),
         }
     }
 
-    pub fn to_sql(&self) -> String {
+    /// Number of `@Pn` parameters taken by the value, one per item for lists and none for `SqlValue::None`
+    pub fn parameter_count(&self) -> usize {
         match self {
-            SqlValue::IntList(_) | SqlValue::FloatList(_) | SqlValue::StrList(_) => {
-                format!("({})", self.to_string())
-            }
+            SqlValue::IntList(list) => list.len(),
+            SqlValue::FloatList(list) => list.len(),
+            SqlValue::StrList(list) => list.len(),
+            SqlValue::None => 0,
+            _ => 1,
+        }
+    }
 
-            SqlValue::Str(_)
-            | SqlValue::StrL(_)
-            | SqlValue::Date(_)
-            | SqlValue::Time(_)
-            | SqlValue::DateTime(_)
-            | SqlValue::Guid(_)
-            | SqlValue::Xml(_) => format!("'{}'", self.to_string()),
+    /// Binds the value to the next `parameter_count()` parameters of `query`
+    pub fn bind_value(&self, query: &mut Query<'_>) -> anyhow::Result<()> {
+        match self {
+            SqlValue::Int1(v) => query.bind(*v),
+            SqlValue::Int2(v) => query.bind(*v),
+            SqlValue::Int(v) => query.bind(*v),
+            SqlValue::Int8(v) => query.bind(*v),
+            SqlValue::Float4(v) => query.bind(*v),
+            SqlValue::Float(v) => query.bind(*v),
+            // Converted by the server to the decimal column, keeping every digit
+            SqlValue::Decimal(v) => query.bind(v.clone()),
+            SqlValue::Bool(v) => query.bind(*v),
+            SqlValue::Str(v) => query.bind(v.clone()),
+            // Dinamic searchs (like) can use '*' in place of '%'
+            SqlValue::StrL(v) => query.bind(v.replace('*', "%")),
+            SqlValue::Date(v) => query.bind(*v),
+            SqlValue::Time(v) => query.bind(*v),
+            SqlValue::DateTime(v) => query.bind(*v),
+            SqlValue::IntList(list) => list.iter().for_each(|v| query.bind(*v)),
+            SqlValue::FloatList(list) => list.iter().for_each(|v| query.bind(*v)),
+            SqlValue::StrList(list) => list.iter().for_each(|v| query.bind(v.clone())),
+            SqlValue::Bin(v) => query.bind(v.clone()),
+            SqlValue::Guid(v) => query.bind(tiberius::Uuid::parse_str(v)?),
+            SqlValue::Xml(v) => query.bind(v.clone()),
+            SqlValue::None => {}
+        };
 
-            _ => format!("{}", self.to_string()),
-        }
+        Ok(())
     }
 
-    pub fn tag_sql_where(&self, tag_name: &str) -> String {
-        let tag = self.tag(tag_name);
+    pub fn tag(&self, tag_name: &str) -> String {
+        format!("@{tag_name}")
+    }
 
-        match self {
-            SqlValue::IntList(_) | SqlValue::FloatList(_) | SqlValue::StrList(_) => {
-                format!("IN ({tag})")
+    /// Coerces a raw spreadsheet cell into the value expected by a column of type `typing`.
+    /// Empty cells become `SqlValue::None`, deciding if that is allowed is up to the caller.
+    pub fn from_raw(raw: &str, typing: ColumnType) -> anyhow::Result<SqlValue> {
+        let raw = raw.trim();
+        if raw.is_empty() {
+            return Ok(SqlValue::None);
+        }
+
+        let value = match typing {
+            ColumnType::Int1 => SqlValue::Int1(raw.parse()?),
+            ColumnType::Int2 => SqlValue::Int2(raw.parse()?),
+            ColumnType::Int4 => SqlValue::Int(raw.parse()?),
+            ColumnType::Int8 => SqlValue::Int8(raw.parse()?),
+
+            ColumnType::Float4 => {
+                let value = normalize_decimal(raw).parse::<f32>()?;
+                anyhow::ensure!(value.is_finite(), "'{raw}' is not a finite number");
+                SqlValue::Float4(value)
+            }
+            ColumnType::Float8 => SqlValue::Float(parse_finite(raw)?),
+            ColumnType::Decimaln => {
+                parse_finite(raw)?;
+                SqlValue::Decimal(normalize_decimal(raw))
             }
 
-            SqlValue::StrL(_) => format!("LIKE {tag}"),
+            ColumnType::Bit => match raw.to_lowercase().as_str() {
+                "1" | "true" => SqlValue::Bool(true),
+                "0" | "false" => SqlValue::Bool(false),
+                _ => anyhow::bail!("'{raw}' is not a boolean (use 1, 0, true or false)"),
+            },
 
-            _ => format!("= {tag}"),
-        }
+            ColumnType::BigVarChar | ColumnType::NVarchar => SqlValue::Str(st!(raw)),
+
+            ColumnType::Daten => SqlValue::Date(parse_first(raw, DATE_FORMATS, NaiveDate::parse_from_str)?),
+            ColumnType::Timen => SqlValue::Time(parse_first(raw, TIME_FORMATS, NaiveTime::parse_from_str)?),
+            ColumnType::Datetime2 | ColumnType::DatetimeOffsetn => {
+                let date_time = parse_first(raw, DATE_TIME_FORMATS, NaiveDateTime::parse_from_str)
+                    .or_else(|_| parse_first(raw, DATE_FORMATS, NaiveDate::parse_from_str).map(|d| d.and_time(NaiveTime::MIN)))?;
+                SqlValue::DateTime(date_time)
+            }
+
+            ColumnType::BigVarBin => SqlValue::Bin(hex::decode(raw.trim_start_matches("0x"))?),
+            ColumnType::Guid => SqlValue::Guid(uuid::Uuid::parse_str(raw)?.to_string()),
+            ColumnType::Xml => SqlValue::Xml(st!(raw)),
+
+            _ => anyhow::bail!("Column type '{typing:?}' can not be loaded from a file"),
+        };
+
+        Ok(value)
     }
 
-    pub fn bind_value(&self, query: &mut Query<'_>) -> anyhow::Result<()> {
-        match self {
-            SqlValue::Bin(value) => query.bind(value.clone()),
-            
-            _ => anyhow::bail!("Only binary values can be binded this way, you tried -> {self:?}")
+    /// Value of a result column, e.g. the output of a scalar chain step
+    pub fn from_column_data(data: &ColumnData<'static>) -> anyhow::Result<SqlValue> {
+        fn value<T>(value: Option<T>, variant: fn(T) -> SqlValue) -> SqlValue {
+            value.map_or(SqlValue::None, variant)
+        }
+
+        let value = match data {
+            ColumnData::U8(v) => value(*v, SqlValue::Int1),
+            ColumnData::I16(v) => value(*v, SqlValue::Int2),
+            ColumnData::I32(v) => value(*v, SqlValue::Int),
+            ColumnData::I64(v) => value(*v, SqlValue::Int8),
+            ColumnData::F32(v) => value(*v, SqlValue::Float4),
+            ColumnData::F64(v) => value(*v, SqlValue::Float),
+            ColumnData::Numeric(v) => value(v.map(numeric_to_decimal), SqlValue::Decimal),
+            ColumnData::Bit(v) => value(*v, SqlValue::Bool),
+            ColumnData::String(v) => value(v.as_deref().map(str::to_string), SqlValue::Str),
+            ColumnData::Guid(v) => value(v.map(|v| v.to_string()), SqlValue::Guid),
+            ColumnData::Binary(v) => value(v.as_deref().map(<[u8]>::to_vec), SqlValue::Bin),
+            ColumnData::Xml(v) => value(v.as_deref().map(|v| v.to_string()), SqlValue::Xml),
+            ColumnData::Date(_) => value(NaiveDate::from_sql(data)?, SqlValue::Date),
+            ColumnData::Time(_) => value(NaiveTime::from_sql(data)?, SqlValue::Time),
+            ColumnData::DateTime(_) | ColumnData::SmallDateTime(_) | ColumnData::DateTime2(_) => {
+                value(NaiveDateTime::from_sql(data)?, SqlValue::DateTime)
+            }
+            ColumnData::DateTimeOffset(_) => {
+                value(chrono::DateTime::<chrono::Utc>::from_sql(data)?.map(|v| v.naive_utc()), SqlValue::DateTime)
+            }
         };
 
-        Ok(())
+        Ok(value)
     }
 
-    pub fn tag(&self, tag_name: &str) -> String {
-        match self {
-            SqlValue::Bin(_) => format!("@{tag_name}"),
-            
-            _ => format!("@_{tag_name}")
+    /// Cell of a bulk insert into a column of type `typing`, which is also the type of its NULL.
+    /// The value must be the one `from_raw` gives for that type.
+    pub fn to_column_data(&self, typing: ColumnType) -> anyhow::Result<ColumnData<'static>> {
+        if let SqlValue::None = self {
+            return null_column_data(typing);
         }
+
+        let data = match (typing, self) {
+            (ColumnType::Int1, SqlValue::Int1(v)) => ColumnData::U8(Some(*v)),
+            (ColumnType::Int2, SqlValue::Int2(v)) => ColumnData::I16(Some(*v)),
+            (ColumnType::Int4, SqlValue::Int(v)) => ColumnData::I32(Some(*v)),
+            (ColumnType::Int8, SqlValue::Int8(v)) => ColumnData::I64(Some(*v)),
+
+            (ColumnType::Float4, SqlValue::Float4(v)) => ColumnData::F32(Some(*v)),
+            (ColumnType::Float8, SqlValue::Float(v)) => ColumnData::F64(Some(*v)),
+            (ColumnType::Decimaln, SqlValue::Decimal(v)) => ColumnData::Numeric(Some(to_numeric(v, DECIMAL_SCALE)?)),
+
+            (ColumnType::Bit, SqlValue::Bool(v)) => ColumnData::Bit(Some(*v)),
+
+            (ColumnType::BigVarChar | ColumnType::NVarchar, SqlValue::Str(v)) => ColumnData::String(Some(Cow::Owned(v.clone()))),
+
+            (ColumnType::Daten, SqlValue::Date(v)) => v.into_sql(),
+            (ColumnType::Timen, SqlValue::Time(v)) => v.into_sql(),
+            (ColumnType::Datetime2, SqlValue::DateTime(v)) => v.into_sql(),
+            // Files carry no offset, the values are taken as UTC
+            (ColumnType::DatetimeOffsetn, SqlValue::DateTime(v)) => v.and_utc().fixed_offset().into_sql(),
+
+            (ColumnType::BigVarBin, SqlValue::Bin(v)) => ColumnData::Binary(Some(Cow::Owned(v.clone()))),
+            (ColumnType::Guid, SqlValue::Guid(v)) => ColumnData::Guid(Some(tiberius::Uuid::parse_str(v)?)),
+            (ColumnType::Xml, SqlValue::Xml(v)) => ColumnData::Xml(Some(Cow::Owned(XmlData::new(v)))),
+
+            _ => anyhow::bail!("{self:?} can not be bulk inserted into a '{typing:?}' column"),
+        };
+
+        Ok(data)
     }
 }
 
+/// `Numeric` only displays its debug form, e.g. `-12.5` for a value of -125 with scale 1
+pub fn numeric_to_decimal(numeric: Numeric) -> String {
+    let digits = format!("{:0>width$}", numeric.value().unsigned_abs(), width = numeric.scale() as usize + 1);
+    let (integer, fraction) = digits.split_at(digits.len() - numeric.scale() as usize);
+    let fraction = fraction.trim_end_matches('0');
+    let sign = if numeric.value() < 0 { "-" } else { "" };
+
+    match fraction.is_empty() {
+        true => format!("{sign}{integer}"),
+        false => format!("{sign}{integer}.{fraction}"),
+    }
+}
+
+fn null_column_data(typing: ColumnType) -> anyhow::Result<ColumnData<'static>> {
+    let data = match typing {
+        ColumnType::Int1 => ColumnData::U8(None),
+        ColumnType::Int2 => ColumnData::I16(None),
+        ColumnType::Int4 => ColumnData::I32(None),
+        ColumnType::Int8 => ColumnData::I64(None),
+        ColumnType::Float4 => ColumnData::F32(None),
+        ColumnType::Float8 => ColumnData::F64(None),
+        ColumnType::Decimaln => ColumnData::Numeric(None),
+        ColumnType::Bit => ColumnData::Bit(None),
+        ColumnType::BigVarChar | ColumnType::NVarchar => ColumnData::String(None),
+        ColumnType::Daten => ColumnData::Date(None),
+        ColumnType::Timen => ColumnData::Time(None),
+        ColumnType::Datetime2 => ColumnData::DateTime2(None),
+        ColumnType::DatetimeOffsetn => ColumnData::DateTimeOffset(None),
+        ColumnType::BigVarBin => ColumnData::Binary(None),
+        ColumnType::Guid => ColumnData::Guid(None),
+        ColumnType::Xml => ColumnData::Xml(None),
+        _ => anyhow::bail!("Column type '{typing:?}' can not be bulk inserted"),
+    };
+
+    Ok(data)
+}
+
+/// Bulk inserts refuse a numeric whose scale is not the one of the column, so `decimal` is rounded to `scale`
+fn to_numeric(decimal: &str, scale: u8) -> anyhow::Result<Numeric> {
+    let overflow = || anyhow::anyhow!("'{decimal}' does not fit in a decimal");
+
+    // Exponents are not written by people, the value is close enough through a float
+    if decimal.contains(['e', 'E']) {
+        let value = decimal.parse::<f64>()? * 10f64.powi(scale as i32);
+        anyhow::ensure!(value.is_finite() && value.abs() < i128::MAX as f64, overflow());
+
+        return Ok(Numeric::new_with_scale(value.round() as i128, scale));
+    }
+
+    let (negative, digits) = match decimal.strip_prefix('-') {
+        Some(digits) => (true, digits),
+        None => (false, decimal.strip_prefix('+').unwrap_or(decimal)),
+    };
+    let (integer, fraction) = digits.split_once('.').unwrap_or((digits, ""));
+    anyhow::ensure!(
+        !(integer.is_empty() && fraction.is_empty())
+            && integer.chars().chain(fraction.chars()).all(|c| c.is_ascii_digit()),
+        "'{decimal}' is not a decimal"
+    );
+
+    let digits = format!("{integer}{fraction:0<width$.width$}", width = scale as usize);
+    let mut value = digits.parse::<i128>().map_err(|_| overflow())?;
+    if fraction.chars().nth(scale as usize).is_some_and(|c| c >= '5') {
+        value = value.checked_add(1).ok_or_else(overflow)?;
+    }
+
+    Ok(Numeric::new_with_scale(if negative { -value } else { value }, scale))
+}
+
+
+const DATE_FORMATS: &[&str] = &["%Y-%m-%d", "%d/%m/%Y"];
+const TIME_FORMATS: &[&str] = &["%H:%M:%S", "%H:%M"];
+const DATE_TIME_FORMATS: &[&str] = &["%Y-%m-%d %H:%M:%S", "%Y-%m-%dT%H:%M:%S", "%d/%m/%Y %H:%M:%S", "%d/%m/%Y %H:%M"];
+
+fn parse_first<T>(
+    raw: &str,
+    formats: &[&str],
+    parse: fn(&str, &str) -> chrono::ParseResult<T>
+) -> anyhow::Result<T> {
+    formats.iter()
+        .find_map(|format| parse(raw, format).ok())
+        .ok_or_else(|| anyhow::anyhow!("'{raw}' does not match any of the accepted formats {formats:?}"))
+}
+
+// Files exported with brazilian locale use ',' as decimal separator
+fn normalize_decimal(raw: &str) -> String {
+    if raw.contains(',') && !raw.contains('.') {
+        return raw.replace(',', ".");
+    }
+
+    st!(raw)
+}
+
+// Rust also parses "NaN", "inf" and "infinity", none of them can be stored
+fn parse_finite(raw: &str) -> anyhow::Result<f64> {
+    let value = normalize_decimal(raw).parse::<f64>()?;
+    anyhow::ensure!(value.is_finite(), "'{raw}' is not a finite number");
+
+    Ok(value)
+}
+
 pub trait ToSqlValue {
     fn to_sql_value(self) -> SqlValue;
 }
@@ -157,6 +378,13 @@ impl_to_sql_value!(Vec<f64>, FloatList);
 impl_to_sql_value!(Vec<String>, StrList);
 impl_to_sql_value!(Vec<u8>, Bin);
 
+// Lets values that have no Rust type of their own (`StrL`, `Decimal`, `None`...) be passed where a `ToSqlValue` is taken
+impl ToSqlValue for SqlValue {
+    fn to_sql_value(self) -> SqlValue {
+        self
+    }
+}
+
 impl<T: ToSqlValue> ToSqlValue for Option<T> {
     fn to_sql_value(self) -> SqlValue {
         match self {
@@ -164,4 +392,76 @@ impl<T: ToSqlValue> ToSqlValue for Option<T> {
             None => SqlValue::None,
         }
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_from_raw() {
+        assert!(matches!(SqlValue::from_raw(" 42 ", ColumnType::Int4).unwrap(), SqlValue::Int(42)));
+        assert!(matches!(SqlValue::from_raw("", ColumnType::Int4).unwrap(), SqlValue::None));
+        assert!(SqlValue::from_raw("4.2", ColumnType::Int4).is_err());
+
+        assert!(matches!(SqlValue::from_raw("1,5", ColumnType::Float8).unwrap(), SqlValue::Float(v) if v == 1.5));
+        assert!(matches!(SqlValue::from_raw("TRUE", ColumnType::Bit).unwrap(), SqlValue::Bool(true)));
+        assert!(SqlValue::from_raw("yes", ColumnType::Bit).is_err());
+
+        for raw in ["NaN", "inf", "-Infinity", "1e39"] {
+            assert!(SqlValue::from_raw(raw, ColumnType::Float4).is_err(), "{raw}");
+        }
+        assert!(SqlValue::from_raw("infinity", ColumnType::Float8).is_err());
+        assert!(SqlValue::from_raw("nan", ColumnType::Decimaln).is_err());
+
+        let date = NaiveDate::from_ymd_opt(2025, 12, 21).unwrap();
+        assert!(matches!(SqlValue::from_raw("21/12/2025", ColumnType::Daten).unwrap(), SqlValue::Date(d) if d == date));
+        assert!(matches!(
+            SqlValue::from_raw("2025-12-21", ColumnType::Datetime2).unwrap(),
+            SqlValue::DateTime(d) if d == date.and_hms_opt(0, 0, 0).unwrap()
+        ));
+        assert!(SqlValue::from_raw("2025-13-40", ColumnType::Daten).is_err());
+    }
+
+    #[test]
+    fn check_to_column_data() {
+        assert!(matches!(SqlValue::Int(4).to_column_data(ColumnType::Int4).unwrap(), ColumnData::I32(Some(4))));
+        assert!(matches!(SqlValue::None.to_column_data(ColumnType::NVarchar).unwrap(), ColumnData::String(None)));
+        assert!(matches!(SqlValue::None.to_column_data(ColumnType::Datetime2).unwrap(), ColumnData::DateTime2(None)));
+        assert!(SqlValue::Int(4).to_column_data(ColumnType::Int8).is_err());
+
+        let date = NaiveDate::from_ymd_opt(2025, 12, 21).unwrap();
+        assert!(matches!(SqlValue::Date(date).to_column_data(ColumnType::Daten).unwrap(), ColumnData::Date(Some(_))));
+        assert!(matches!(
+            SqlValue::DateTime(date.and_hms_opt(8, 30, 0).unwrap()).to_column_data(ColumnType::DatetimeOffsetn).unwrap(),
+            ColumnData::DateTimeOffset(Some(_))
+        ));
+
+        let ColumnData::Numeric(Some(numeric)) = SqlValue::Decimal(st!("-12.5")).to_column_data(ColumnType::Decimaln).unwrap() else {
+            panic!("Decimal must be a numeric");
+        };
+        assert_eq!((numeric.value(), numeric.scale()), (-125_000_000_000, DECIMAL_SCALE));
+    }
+
+    #[test]
+    fn check_to_numeric() {
+        assert_eq!(to_numeric("3", 2).unwrap().value(), 300);
+        assert_eq!(to_numeric(".25", 2).unwrap().value(), 25);
+        assert_eq!(to_numeric("+1.005", 2).unwrap().value(), 101);
+        assert_eq!(to_numeric("-0.004", 2).unwrap().value(), 0);
+        assert_eq!(to_numeric("1.5e2", 2).unwrap().value(), 15000);
+        assert!(to_numeric("1.2.3", 2).is_err());
+        assert!(to_numeric("NaN", 2).is_err());
+    }
+
+    #[test]
+    fn check_from_column_data() {
+        assert!(matches!(SqlValue::from_column_data(&ColumnData::I64(Some(9))).unwrap(), SqlValue::Int8(9)));
+        assert!(matches!(SqlValue::from_column_data(&ColumnData::String(None)).unwrap(), SqlValue::None));
+
+        let date = NaiveDate::from_ymd_opt(2025, 12, 21).unwrap();
+        assert!(matches!(SqlValue::from_column_data(&date.into_sql()).unwrap(), SqlValue::Date(d) if d == date));
+
+        assert_eq!(numeric_to_decimal(Numeric::new_with_scale(5, 3)), "0.005");
+        assert_eq!(numeric_to_decimal(Numeric::new_with_scale(-300, 2)), "-3");
+    }
+}
@@ -1,49 +1,139 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
+use std::pin::Pin;
 use std::{env, fs};
 
 use chrono::{NaiveDate, NaiveDateTime};
-use futures::StreamExt;
+use futures::{Stream, StreamExt, TryStreamExt};
 use regex::Regex;
-use tiberius::{AuthMethod, Client, Config, ExecuteResult, IntoSql, Query};
-use tokio::net::TcpStream;
-use tokio_util::compat::{Compat, TokioAsyncWriteCompatExt};
+use tiberius::{ExecuteResult, IntoSql, Query, QueryStream};
 
 use super::super::DBLoad;
-use super::db_types::{ChainExec, ChainMap, SqlValue, ToSqlValue, SqlSingleParameters, SqlMultipleParameters};
+use super::db_types::{BulkInsert, ChainContext, ChainExec, ChainMap, ChainStep, ChainStepFailed, ChainStepKind, StepOutput, StepReturns, Comparison, Filter, GenericColumn, GenericTable, OrderBy, Page, Predicate, SqlValue, ToSqlValue, ToSqlTypeName, SqlSingleParameters, SqlMultipleParameters};
+use super::pool::{Pool, PooledClient};
 use super::tiberius_interface::{FromOwnedSql, TiberiusCoversion};
 use crate::st;
 
 const GET_IDTT: &str = "SELECT CAST(SCOPE_IDENTITY() AS BIGINT)";
+const SQL_PARAMETER: &str = r"@[A-Za-z_][A-Za-z0-9_]*";
+
+/// Rows decoded ahead of the consumer of a `RowStream`
+const STREAM_BUFFER: usize = 256;
+
+/// A `RowStream` consumer that takes no row for this long is given up on, so a stalled download
+/// does not keep a connection of the pool forever
+const STREAM_STALL_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(120);
+
+/// Rows sent by each `INSERT BULK` of a `BulkInsert` step
+pub const BULK_INSERT_BATCH: usize = 5000;
+
+/// SQL Server accepts up to 2100 parameters per request, some are left for the statements around the values
+pub const MAX_QUERY_PARAMETERS: usize = 2000;
 
 /* #region PRIVATE FUNCTIONS */
 
-fn build_where_clause(map: &SqlSingleParameters) -> String {
-    let mut where_clause = String::new();
+/// Stores `value` as the next `@_wN` parameter, numbered after the ones already taken
+fn push_predicate_value(parameters: &mut SqlSingleParameters, value: &SqlValue) -> String {
+    let name = format!("_w{}", parameters.len() + 1);
+    let tag = value.tag(&name);
+    parameters.insert(name, value.clone());
 
-    for (key, value) in map {
-        where_clause.push_str("AND [");
-        where_clause.push_str(&key);
-        where_clause.push_str("] ");
+    tag
+}
 
-        where_clause.push_str(&value.tag_sql_where(&key));
-    }
+fn build_predicate_clause(predicate: &Predicate, parameters: &mut SqlSingleParameters) -> anyhow::Result<String> {
+    let clause = match predicate {
+        Predicate::Compare { column, comparison, value } => {
+            check_identifier(column)?;
+            let is_list = matches!(value, SqlValue::IntList(_) | SqlValue::FloatList(_) | SqlValue::StrList(_));
+
+            match (comparison, value) {
+                (Comparison::Eq, SqlValue::None) => format!("[{column}] IS NULL"),
+                (Comparison::NotEq, SqlValue::None) => format!("[{column}] IS NOT NULL"),
+                (Comparison::In | Comparison::NotIn, _) => {
+                    anyhow::ensure!(is_list, "'{}' on [{column}] needs a list", comparison.to_sql());
+
+                    // `IN (NULL)` matches nothing, which is wrong for an empty `NOT IN`
+                    match (comparison, value.parameter_count()) {
+                        (Comparison::In, 0) => st!("1 = 0"),
+                        (_, 0) => st!("1 = 1"),
+                        _ => format!("[{column}] {} ({})", comparison.to_sql(), push_predicate_value(parameters, value)),
+                    }
+                }
+                _ => {
+                    anyhow::ensure!(!is_list, "'{}' on [{column}] can not take a list", comparison.to_sql());
+                    format!("[{column}] {} {}", comparison.to_sql(), push_predicate_value(parameters, value))
+                }
+            }
+        }
+        Predicate::Between { column, low, high } => {
+            check_identifier(column)?;
+            let low = push_predicate_value(parameters, low);
+            let high = push_predicate_value(parameters, high);
+
+            format!("[{column}] BETWEEN {low} AND {high}")
+        }
+        Predicate::IsNull(column) => {
+            check_identifier(column)?;
+            format!("[{column}] IS NULL")
+        }
+        Predicate::IsNotNull(column) => {
+            check_identifier(column)?;
+            format!("[{column}] IS NOT NULL")
+        }
+        Predicate::Not(predicate) => format!("NOT ({})", build_predicate_clause(predicate, parameters)?),
+        Predicate::And(predicates) if predicates.is_empty() => st!("1 = 1"),
+        Predicate::Or(predicates) if predicates.is_empty() => st!("1 = 0"),
+        Predicate::And(predicates) | Predicate::Or(predicates) if predicates.len() == 1 => {
+            build_predicate_clause(&predicates[0], parameters)?
+        }
+        Predicate::And(predicates) | Predicate::Or(predicates) => {
+            let operator = if matches!(predicate, Predicate::And(_)) { " AND " } else { " OR " };
+
+            let mut clauses = Vec::with_capacity(predicates.len());
+            for predicate in predicates {
+                clauses.push(format!("({})", build_predicate_clause(predicate, parameters)?));
+            }
+
+            clauses.join(operator)
+        }
+    };
 
-    format!("WHERE {}", &where_clause[4..])
+    Ok(clause)
 }
 
-fn build_set_clause(map: &SqlSingleParameters) -> anyhow::Result<String> {
+/// The `WHERE` of `filter`, empty for `Filter::All`, and the parameters its tags are bound to
+fn build_filter_clause(filter: Filter<'_>) -> anyhow::Result<(String, SqlSingleParameters)> {
+    match filter {
+        Filter::All => Ok((String::new(), SqlSingleParameters::new())),
+        Filter::Parameters(parameters) if parameters.is_empty() => Ok((String::new(), SqlSingleParameters::new())),
+        Filter::Parameters(parameters) => build_filter_clause(Filter::Predicate(&Predicate::from_parameters(parameters))),
+        Filter::Predicate(predicate) => {
+            let mut parameters = SqlSingleParameters::new();
+            let clause = build_predicate_clause(predicate, &mut parameters)?;
+
+            Ok((format!("WHERE {clause}"), parameters))
+        }
+    }
+}
+
+/// Each new value is tagged `@sN` in the order of its column name, column names may hold spaces or accents
+fn build_set_clause(map: &SqlSingleParameters) -> anyhow::Result<(String, SqlSingleParameters)> {
     anyhow::ensure!(!map.is_empty(), "Update must set at least one column");
 
-    let mut set_clause = String::new();
+    let mut columns = map.iter().collect::<Vec<_>>();
+    columns.sort_by_key(|(column, _)| *column);
+
+    let mut assignments = Vec::with_capacity(columns.len());
+    let mut parameters = SqlSingleParameters::with_capacity(columns.len());
+    for (idx, (column, value)) in columns.into_iter().enumerate() {
+        check_identifier(column)?;
 
-    for (key, value) in map {
-        set_clause.push_str(", [");
-        set_clause.push_str(&key);
-        set_clause.push_str("] = ");
-        set_clause.push_str(&value.tag(&key));
+        let name = format!("s{}", idx + 1);
+        assignments.push(format!("[{column}] = {}", value.tag(&name)));
+        parameters.insert(name, value.clone());
     }
 
-    Ok(format!("SET {}", &set_clause[2..]))
+    Ok((format!("SET {}", assignments.join(", ")), parameters))
 }
 
 fn build_columns_clause(columns: &[String]) -> String {
@@ -71,7 +161,7 @@ fn build_values_clause(
         let mut values = Vec::with_capacity(header.len());
         for col_name in header {
             let v = insert_parameters.get_value(col_name, row_idx)?;
-            values.push(v.tag(&format!("{col_name}_{row_idx}")));
+            values.push(v.tag(&SqlMultipleParameters::tag_name(insert_parameters.get_column_idx(col_name)?, row_idx)));
         }
         rows_sql.push(format!("({})", values.join(", ")));
     }
@@ -80,6 +170,26 @@ fn build_values_clause(
     Ok(format!("VALUES {rows};"))
 }
 
+pub fn check_identifier(name: &str) -> anyhow::Result<()> {
+    anyhow::ensure!(
+        !name.trim().is_empty() && !name.contains(['[', ']']) && !name.chars().any(char::is_control),
+        "'{name}' is not a valid table or column name"
+    );
+
+    Ok(())
+}
+
+fn build_order_clause(order_by: &[OrderBy]) -> anyhow::Result<String> {
+    let mut columns = Vec::with_capacity(order_by.len());
+
+    for order in order_by {
+        check_identifier(order.column())?;
+        columns.push(format!("[{}] {}", order.column(), order.direction().to_sql()));
+    }
+
+    Ok(format!("ORDER BY {}", columns.join(", ")))
+}
+
 fn remove_sql_comments(sql: &str) -> String {
     let mut result = String::with_capacity(sql.len());
     let mut chars = sql.chars().peekable();
@@ -136,7 +246,7 @@ fn remove_sql_comments(sql: &str) -> String {
 }
 
 fn extract_sql_params(sql: &str) -> Vec<String> {
-    let re = Regex::new(r"@[A-Za-z_][A-Za-z0-9_]*").unwrap();
+    let re = Regex::new(SQL_PARAMETER).unwrap();
 
     let mut params = Vec::new();
     let mut seen = HashSet::new();
@@ -162,37 +272,46 @@ fn get_query_text(sql: &str) -> String {
     }
 }
 
+/// Every `@name` tag becomes a `@Pn` parameter bound to its value (see `SqlValue::bind_value`),
+/// lists are expanded to one parameter per item and `SqlValue::None` is written as `NULL`
 fn parse_sql(sql: String, parameters: Option<&SqlSingleParameters>) -> anyhow::Result<(String, Vec<&SqlValue>)> {
-    let mut sql_final = get_query_text(&sql);
+    let sql_final = get_query_text(&sql);
+
+    let Some(parameters_map) = parameters else {
+        return Ok((sql_final, Vec::new()));
+    };
 
     let mut sql_parameters = Vec::<&SqlValue>::new();
-    if let Some(parameters_map) = parameters {
-        let mut connection_parameter_idx = 1;
-        let parameters_tags = extract_sql_params(&sql_final);
-
-        for parameter in parameters_tags {
-            let mut key = parameter
-                .strip_prefix("@_")
-                .or_else(|| parameter.strip_prefix("@"))
-                .unwrap_or(&parameter);
-
-            let value = parameters_map
-                .get(key)
-                .ok_or_else(|| anyhow::anyhow!("Parameter '{parameter}' not passed"))?;
-
-            // Parameters that can be inserted in place
-            if parameter.starts_with("@_") {
-                sql_final = sql_final.replace(&parameter, &value.to_sql());
-            }
-            // Parameters that must be passed throw the sql interface
-            else {
-                sql_parameters.push(value);
-                sql_final = sql_final.replace(&parameter, &format!("@P{connection_parameter_idx}"));
-                connection_parameter_idx += 1;
-            }
+    let mut placeholders = HashMap::<String, String>::new();
+    let mut connection_parameter_idx = 1;
+
+    for parameter in extract_sql_params(&sql_final) {
+        let value = parameters_map
+            .get(&parameter[1..])
+            .ok_or_else(|| anyhow::anyhow!("Parameter '{parameter}' not passed"))?;
+
+        let count = value.parameter_count();
+        let placeholder = match (value, count) {
+            (SqlValue::None, _) | (_, 0) => st!("NULL"),
+            _ => (connection_parameter_idx..connection_parameter_idx + count)
+                .map(|idx| format!("@P{idx}"))
+                .collect::<Vec<_>>()
+                .join(", "),
+        };
+
+        if count > 0 {
+            sql_parameters.push(value);
+            connection_parameter_idx += count;
         }
+
+        placeholders.insert(parameter, placeholder);
     }
 
+    // Single pass, so a tag is never replaced inside another tag or inside a placeholder
+    let sql_final = Regex::new(SQL_PARAMETER)?
+        .replace_all(&sql_final, |tag: &regex::Captures| placeholders[&tag[0]].clone())
+        .into_owned();
+
     Ok((sql_final, sql_parameters))
 }
 
@@ -219,16 +338,18 @@ fn parse_query<'a>(
 
 /* #region PUBLIC BUILD SQL */
 
-pub fn build_select_clause(
+/// `page` needs an `order_by`, SQL Server only pages ordered results, and can not be mixed with `top`
+pub fn build_select_clause<'a>(
     table_name: &str,
-    where_parameters: Option<&SqlSingleParameters>,
+    filter: impl Into<Filter<'a>>,
     columns: Option<Vec<&str>>,
-    top: Option<u8>,
-) -> String {
-    let mut where_clause = st!("");
-    if let Some(where_parameters) = where_parameters {
-        where_clause = build_where_clause(where_parameters);
-    }
+    top: Option<u32>,
+    order_by: &[OrderBy],
+    page: Option<Page>,
+) -> anyhow::Result<String> {
+    anyhow::ensure!(top.is_none() || page.is_none(), "Select can not use both TOP and a page");
+
+    let (where_clause, _) = build_filter_clause(filter.into())?;
 
     let top = match top {
         Some(value) => format!("TOP {value}"),
@@ -240,7 +361,28 @@ pub fn build_select_clause(
         None => st!("*"),
     };
 
-    format!("SELECT {top} {columns} FROM uploader.[{table_name}] {where_clause}")
+    let mut sql = format!("SELECT {top} {columns} FROM uploader.[{table_name}] {where_clause}");
+
+    if !order_by.is_empty() {
+        sql.push(' ');
+        sql.push_str(&build_order_clause(order_by)?);
+    }
+
+    if let Some(page) = page {
+        anyhow::ensure!(!order_by.is_empty(), "Select of '{table_name}' needs an order to be paged");
+        sql.push_str(&format!(" OFFSET {} ROWS FETCH NEXT {} ROWS ONLY", page.offset(), page.size()));
+    }
+
+    Ok(sql)
+}
+
+pub fn build_count_clause<'a>(
+    table_name: &str,
+    filter: impl Into<Filter<'a>>,
+) -> anyhow::Result<String> {
+    let (where_clause, _) = build_filter_clause(filter.into())?;
+
+    Ok(format!("SELECT COUNT_BIG(*) FROM uploader.[{table_name}] {where_clause}"))
 }
 
 pub fn build_insert_clause(
@@ -254,83 +396,163 @@ pub fn build_insert_clause(
     Ok(format!("INSERT INTO uploader.[{table_name}] {columns} {values}"))
 }
 
-pub fn build_delete_clause(
+pub fn build_delete_clause<'a>(
     table_name: &str,
-    where_parameters: Option<&SqlSingleParameters>,
-) -> String {
-    let mut where_clause = st!("");
-    if let Some(where_parameters) = where_parameters {
-        where_clause = build_where_clause(where_parameters);
-    }
+    filter: impl Into<Filter<'a>>,
+) -> anyhow::Result<String> {
+    let (where_clause, _) = build_filter_clause(filter.into())?;
 
-    format!("DELETE FROM uploader.[{table_name}] {where_clause}")
+    Ok(format!("DELETE FROM uploader.[{table_name}] {where_clause}"))
 }
 
-pub fn build_update_clause(
+/// The update and the parameters its `SET` and `WHERE` tags are bound to
+pub fn build_update_clause<'a>(
     table_name: &str,
     new_values: &SqlSingleParameters,
-    where_parameters: Option<&SqlSingleParameters>,
-) -> anyhow::Result<String> {
+    filter: impl Into<Filter<'a>>,
+) -> anyhow::Result<(String, SqlSingleParameters)> {
 
-    let set_clause = build_set_clause(new_values)?;
+    let (set_clause, mut parameters) = build_set_clause(new_values)?;
 
-    let mut where_clause = st!("");
-    if let Some(where_parameters) = where_parameters {
-        where_clause = build_where_clause(where_parameters);
-    }
+    let (where_clause, where_parameters) = build_filter_clause(filter.into())?;
+    parameters.extend(where_parameters);
+
+    Ok((format!("UPDATE uploader.[{table_name}] {set_clause} {where_clause}"), parameters))
+}
+
+/// Parameters the tags of the `WHERE` built from `filter` are bound to
+pub fn build_filter_parameters<'a>(filter: impl Into<Filter<'a>>) -> anyhow::Result<SqlSingleParameters> {
+    let (_, parameters) = build_filter_clause(filter.into())?;
 
-    Ok(format!("UPDATE uploader.[{table_name}] {set_clause} {where_clause}"))
+    Ok(parameters)
 }
 
 // CREATE NEW SHEET TABLE DATA
 
-/* #endregion */
+pub fn build_create_table_clause(table: &GenericTable) -> anyhow::Result<String> {
+    anyhow::ensure!(!table.columns().is_empty(), "Table '{}' must have at least one column", table.name());
+    check_identifier(table.name())?;
 
-/* #region PUBLIC SQL FUNCS */
+    let columns = table.columns()
+        .iter()
+        .map(|column| {
+            check_identifier(column.name())?;
+            column.sql_definition()
+        })
+        .collect::<anyhow::Result<Vec<String>>>()?;
+
+    Ok(format!("CREATE TABLE uploader.[{}] ({})", table.name(), columns.join(", ")))
+}
+
+pub fn build_add_column_clause(table_name: &str, column: &GenericColumn) -> anyhow::Result<String> {
+    check_identifier(table_name)?;
+    check_identifier(column.name())?;
+
+    Ok(format!("ALTER TABLE uploader.[{table_name}] ADD {}", column.sql_definition()?))
+}
+
+/// With `check_conversion` the statement throws, before altering anything, if a
+/// stored value can not be converted to the new column type
+pub fn build_alter_column_clause(
+    table_name: &str,
+    column: &GenericColumn,
+    check_conversion: bool,
+) -> anyhow::Result<String> {
+    check_identifier(table_name)?;
+    check_identifier(column.name())?;
+
+    let alter = format!("ALTER TABLE uploader.[{table_name}] ALTER COLUMN {}", column.sql_definition()?);
+    if !check_conversion {
+        return Ok(alter);
+    }
+
+    let name = column.name();
+    let sql_type = column.typing().to_sql_type_name()?;
+    let message = format!("Column '{name}' has values that can not be converted to {sql_type}").replace('\'', "''");
 
-pub async fn mssql_client() -> anyhow::Result<Client<Compat<TcpStream>>> {
-    let mut config = Config::new();
+    Ok(format!(
+        "IF EXISTS (SELECT 1 FROM uploader.[{table_name}] WHERE [{name}] IS NOT NULL AND TRY_CONVERT({sql_type}, [{name}]) IS NULL) \
+        THROW 50000, N'{message}', 1; \
+        {alter}"
+    ))
+}
+
+pub fn build_drop_table_clause(table_name: &str) -> anyhow::Result<String> {
+    check_identifier(table_name)?;
+
+    Ok(format!("DROP TABLE uploader.[{table_name}]"))
+}
+
+// CUSTOM SQL SCRIPTS
+
+/// Error number thrown when a custom script fails, its message starts with the script `label`
+pub const CUSTOM_SCRIPT_ERROR: u32 = 50001;
+
+/// Runs a `CUSTOM_SQL_SCRIPT` in its own scope, so even its compile errors are caught and thrown
+/// again as `CUSTOM_SCRIPT_ERROR`. With `staged_table` the script reads the uploaded rows from `#STAGED`,
+/// dropped before and after so several scripts and the next users of the pooled connection never find it.
+/// The returned sql must be executed without parameters, scripts are free to use `@` variables.
+pub fn build_custom_script_clause(
+    script: &str,
+    label: &str,
+    staged_table: Option<&str>,
+) -> anyhow::Result<String> {
+    let (stage, unstage) = match staged_table {
+        Some(staged_table) => {
+            check_identifier(staged_table)?;
+            (
+                format!("DROP TABLE IF EXISTS #STAGED; SELECT * INTO #STAGED FROM uploader.[{staged_table}]; "),
+                st!("; DROP TABLE IF EXISTS #STAGED;"),
+            )
+        }
+        None => (String::new(), String::new()),
+    };
 
-    let url = env::var("DB_URL")?;
-    let port: u16 = env::var("DB_PORT")?.parse()?;
-    let database = env::var("DB_NAME")?;
-    let user = env::var("DB_USER")?;
-    let password = env::var("DB_PASS")?;
+    let script = script.replace('\'', "''");
+    let label = label.replace('\'', "''");
 
-    config.host(url);
-    config.port(port);
-    config.database(database);
-    config.authentication(AuthMethod::sql_server(user, password));
-    config.trust_cert(); // remove in production
+    Ok(format!(
+        "{stage}BEGIN TRY EXEC sp_executesql N'{script}'; END TRY \
+        BEGIN CATCH \
+        DECLARE @ScriptError NVARCHAR(2048) = CONCAT(N'{label}: ', ERROR_MESSAGE()); \
+        THROW {CUSTOM_SCRIPT_ERROR}, @ScriptError, 1; \
+        END CATCH{unstage}"
+    ))
+}
 
-    let tcp = TcpStream::connect(config.get_addr()).await?;
+/* #endregion */
 
-    let client = Client::connect(config, tcp.compat_write()).await?;
+/* #region PUBLIC SQL FUNCS */
 
-    Ok(client)
+/// Connection taken from the shared pool, it goes back to the pool when dropped
+pub async fn mssql_client(pool: &Pool) -> anyhow::Result<PooledClient> {
+    Ok(pool.get_owned().await?)
 }
 
 pub async fn get_query_result(
+    pool: &Pool,
     sql: String,
     sql_parameters: Option<&SqlSingleParameters>,
 ) -> anyhow::Result<ExecuteResult> {
     let query = parse_query(sql, sql_parameters)?;
-    let mut client = mssql_client().await?;
+    let mut client = mssql_client(pool).await?;
     let result = query.execute(&mut client).await?;
 
     Ok(result)
 }
 
 pub async fn run_query(
+    pool: &Pool,
     sql: String,
     sql_parameters: Option<&SqlSingleParameters>,
 ) -> anyhow::Result<Vec<u64>> {
-    let result = get_query_result(sql, sql_parameters).await?;
+    let result = get_query_result(pool, sql, sql_parameters).await?;
 
     Ok(result.rows_affected().to_vec())
 }
 
 pub async fn get_response_from<T>(
+    pool: &Pool,
     sql: String,
     sql_parameters: Option<&SqlSingleParameters>,
 ) -> anyhow::Result<Vec<T>>
@@ -338,13 +560,14 @@ where
     T: DBLoad,
 {
     let query = parse_query(sql, sql_parameters)?;
-    let mut client = mssql_client().await?;
+    let mut client = mssql_client(pool).await?;
     let mut stream = query.query(&mut client).await?;
 
     T::from_stream(stream).await
 }
 
 pub async fn get_single_response_from<R>(
+    pool: &Pool,
     sql: String,
     column_name: Option<&str>,
     sql_parameters: Option<&SqlSingleParameters>,
@@ -353,7 +576,7 @@ where
     R: TiberiusCoversion,
 {
     let mut query = parse_query(sql, sql_parameters)?;    
-    let mut client = mssql_client().await?;
+    let mut client = mssql_client(pool).await?;
     let mut stream = query.query(&mut client).await?;
 
     let row = stream
@@ -377,42 +600,67 @@ where
 }
 
 pub async fn get_identity(
+    pool: &Pool,
     sql: String,
     sql_parameters: Option<&SqlSingleParameters>,
 ) -> anyhow::Result<Option<i64>> {
     let sql = format!("{sql} {GET_IDTT}");
 
-    let id = get_single_response_from::<i64>(sql, None, sql_parameters).await?;
+    let id = get_single_response_from::<i64>(pool, sql, None, sql_parameters).await?;
 
     Ok(id)
 }
 
-pub async fn select_from<T>(
-    where_parameters: Option<&SqlSingleParameters>,
+pub async fn select_from<'a, T>(
+    pool: &Pool,
+    filter: impl Into<Filter<'a>>,
     columns: Option<Vec<&str>>,
-    top: Option<u8>,
+    top: Option<u32>,
+    order_by: &[OrderBy],
+    page: Option<Page>,
 ) -> anyhow::Result<Vec<T>>
 where
     T: DBLoad,
 {
-    let sql = build_select_clause(T::TAB, where_parameters, columns, top);
-    get_response_from(sql, None).await
+    let filter = filter.into();
+    let sql = build_select_clause(T::TAB, filter, columns, top, order_by, page)?;
+    let parameters = build_filter_parameters(filter)?;
+
+    get_response_from(pool, sql, Some(&parameters)).await
+}
+
+pub async fn count_from<'a, T>(
+    pool: &Pool,
+    filter: impl Into<Filter<'a>>,
+) -> anyhow::Result<i64>
+where
+    T: DBLoad,
+{
+    let filter = filter.into();
+    let sql = build_count_clause(T::TAB, filter)?;
+    let parameters = build_filter_parameters(filter)?;
+    let count = get_single_response_from::<i64>(pool, sql, None, Some(&parameters)).await?;
+
+    Ok(count.unwrap_or_default())
 }
 
-pub async fn select_column_from<T, R>(
+pub async fn select_column_from<'a, T, R>(
+    pool: &Pool,
     column_name: &str,
-    where_parameters: Option<&SqlSingleParameters>,
-    top: Option<u8>,
+    filter: impl Into<Filter<'a>>,
+    top: Option<u32>,
 ) -> anyhow::Result<Vec<Option<R>>>
 where
     T: DBLoad,
     R: TiberiusCoversion,
 {
+    let filter = filter.into();
     let columns = Some(vec![column_name]);
-    let sql = build_select_clause(T::TAB, where_parameters, columns, top);
-    let query = parse_query(sql, None)?;
+    let sql = build_select_clause(T::TAB, filter, columns, top, &[], None)?;
+    let parameters = build_filter_parameters(filter)?;
+    let query = parse_query(sql, Some(&parameters))?;
 
-    let mut client = mssql_client().await?;
+    let mut client = mssql_client(pool).await?;
     let mut stream = query.query(&mut client).await?;
     let mut row_stream = stream.into_row_stream();
 
@@ -425,18 +673,22 @@ where
     Ok(result)
 }
 
-pub async fn select_single_from<T, R>(
-    where_parameters: Option<SqlSingleParameters>,
+/// `column_name` of the first row matching `filter`
+pub async fn select_single_from<'a, T, R>(
+    pool: &Pool,
+    filter: impl Into<Filter<'a>>,
     column_name: &str,
 ) -> anyhow::Result<Option<R>>
 where
     T: DBLoad,
     R: TiberiusCoversion,
 {
-    let sql = build_select_clause(T::TAB, None, None, None);
-    let query = parse_query(sql, None)?;
+    let filter = filter.into();
+    let sql = build_select_clause(T::TAB, filter, None, Some(1), &[], None)?;
+    let parameters = build_filter_parameters(filter)?;
+    let query = parse_query(sql, Some(&parameters))?;
 
-    let mut client = mssql_client().await?;
+    let mut client = mssql_client(pool).await?;
     let mut stream = query.query(&mut client).await?;
     let mut row_stream = stream.into_row_stream();
 
@@ -448,87 +700,366 @@ where
     Ok(result)
 }
 
+/// The only row matching `filter`, an error if there is more than one
+pub async fn find_one<'a, T>(
+    pool: &Pool,
+    filter: impl Into<Filter<'a>>,
+) -> anyhow::Result<Option<T>>
+where
+    T: DBLoad,
+{
+    // A second row is enough to know the match is not unique
+    let mut rows = select_from::<T>(pool, filter, None, Some(2), &[], None).await?;
+
+    anyhow::ensure!(rows.len() < 2, "More than one row of '{}' matches, at most one was expected", T::TAB);
+
+    Ok(rows.pop())
+}
+
+/// Row of a table whose primary key is a single column, see `DBLoad::PK`
+pub async fn find_by_pk<T>(
+    pool: &Pool,
+    pk: impl ToSqlValue,
+) -> anyhow::Result<Option<T>>
+where
+    T: DBLoad,
+{
+    let [pk_column] = T::PK else {
+        anyhow::bail!("'{}' has no single column primary key, use find_one", T::TAB);
+    };
+
+    find_one::<T>(pool, &Predicate::eq(pk_column, pk)).await
+}
+
+pub async fn exists<'a, T>(
+    pool: &Pool,
+    filter: impl Into<Filter<'a>>,
+) -> anyhow::Result<bool>
+where
+    T: DBLoad,
+{
+    let filter = filter.into();
+    let sql = build_select_clause(T::TAB, filter, Some(vec!["1 AS [Found]"]), Some(1), &[], None)?;
+    let parameters = build_filter_parameters(filter)?;
+
+    Ok(!get_generic_response(pool, sql, Some(&parameters)).await?.is_empty())
+}
+
 pub async fn get_generic_response(
+    pool: &Pool,
     sql: String,
     sql_parameters: Option<&SqlSingleParameters>,
 ) -> anyhow::Result<Vec<tiberius::Row>> {
     let query = parse_query(sql, sql_parameters)?;
-    let mut client = mssql_client().await?;
-    let mut stream = query.query(&mut client).await?;
-    let mut row_stream = stream.into_row_stream();
+    let mut client = mssql_client(pool).await?;
+    let stream = query.query(&mut client).await?;
 
-    let mut result = Vec::new();
+    into_generic_stream(stream).try_collect().await
+}
 
-    while let Some(row) = row_stream.next().await.transpose()? as Option<tiberius::Row> {
-        result.push(row);
-    }
+pub async fn select_generic<'a>(
+    pool: &Pool,
+    table_name: &str,
+    filter: impl Into<Filter<'a>>,
+    columns: Option<Vec<&str>>,
+    top: Option<u32>,
+    order_by: &[OrderBy],
+    page: Option<Page>,
+) -> anyhow::Result<Vec<tiberius::Row>> {
+    let filter = filter.into();
+    let sql = build_select_clause(table_name, filter, columns, top, order_by, page)?;
+    let parameters = build_filter_parameters(filter)?;
 
-    Ok(result)
+    get_generic_response(pool, sql, Some(&parameters)).await
+}
+
+/// Rows of a query that outlive the call, e.g. to be written into a response as they arrive.
+/// The stream holds its own connection. It goes back to the pool once every row is read,
+/// a stream dropped or stalled halfway closes it instead.
+pub type RowStream<T> = Pin<Box<dyn Stream<Item = anyhow::Result<T>> + Send>>;
+
+/// How the rows of a `QueryStream` are decoded, e.g. `DBLoad::into_stream`
+type RowDecoder<T> = for<'s> fn(QueryStream<'s>) -> Pin<Box<dyn Stream<Item = anyhow::Result<T>> + Send + 's>>;
+
+fn into_generic_stream(stream: QueryStream<'_>) -> Pin<Box<dyn Stream<Item = anyhow::Result<tiberius::Row>> + Send + '_>> {
+    Box::pin(stream.into_row_stream().map(|row| Ok(row?)))
+}
+
+/// Reads the rows in a task of its own, at most `STREAM_BUFFER` rows ahead of the consumer
+async fn spawn_row_stream<T>(
+    pool: &Pool,
+    sql: String,
+    sql_parameters: Option<&SqlSingleParameters>,
+    decode: RowDecoder<T>,
+) -> anyhow::Result<RowStream<T>>
+where
+    T: Send + 'static,
+{
+    let query = parse_query(sql, sql_parameters)?;
+    let mut client = mssql_client(pool).await?;
+    let (sender, receiver) = tokio::sync::mpsc::channel(STREAM_BUFFER);
+
+    tokio::spawn(async move {
+        let mut rows = match query.query(&mut client).await {
+            Ok(stream) => decode(stream),
+            Err(e) => {
+                let _ = sender.send(Err(e.into())).await;
+                return;
+            }
+        };
+
+        while let Some(row) = rows.next().await {
+            let sent = tokio::time::timeout(STREAM_STALL_TIMEOUT, sender.send(row)).await;
+
+            // Nobody is reading anymore. The rest of the result is still on the connection,
+            // it is closed instead of making its next user drain it.
+            if !matches!(sent, Ok(Ok(()))) {
+                drop(rows);
+                client.mark_broken();
+                return;
+            }
+        }
+    });
+
+    Ok(Box::pin(futures::stream::unfold(receiver, |mut receiver| async move {
+        receiver.recv().await.map(|row| (row, receiver))
+    })))
 }
 
-pub async fn select_generic(
+/// Same as `get_response_from`, without keeping the rows in memory
+pub async fn stream_response_from<T>(
+    pool: &Pool,
+    sql: String,
+    sql_parameters: Option<&SqlSingleParameters>,
+) -> anyhow::Result<RowStream<T>>
+where
+    T: DBLoad,
+{
+    spawn_row_stream(pool, sql, sql_parameters, T::into_stream).await
+}
+
+/// Same as `get_generic_response`, without keeping the rows in memory
+pub async fn stream_generic_response(
+    pool: &Pool,
+    sql: String,
+    sql_parameters: Option<&SqlSingleParameters>,
+) -> anyhow::Result<RowStream<tiberius::Row>> {
+    spawn_row_stream(pool, sql, sql_parameters, into_generic_stream).await
+}
+
+pub async fn stream_from<'a, T>(
+    pool: &Pool,
+    filter: impl Into<Filter<'a>>,
+    columns: Option<Vec<&str>>,
+    order_by: &[OrderBy],
+) -> anyhow::Result<RowStream<T>>
+where
+    T: DBLoad,
+{
+    let filter = filter.into();
+    let sql = build_select_clause(T::TAB, filter, columns, None, order_by, None)?;
+    let parameters = build_filter_parameters(filter)?;
+
+    stream_response_from(pool, sql, Some(&parameters)).await
+}
+
+pub async fn stream_generic<'a>(
+    pool: &Pool,
     table_name: &str,
-    where_parameters: Option<&SqlSingleParameters>,
+    filter: impl Into<Filter<'a>>,
     columns: Option<Vec<&str>>,
-    top: Option<u8>,
-) -> anyhow::Result<Vec<tiberius::Row>> {
-    let sql = build_select_clause(table_name, where_parameters, columns, top);
+    order_by: &[OrderBy],
+) -> anyhow::Result<RowStream<tiberius::Row>> {
+    let filter = filter.into();
+    let sql = build_select_clause(table_name, filter, columns, None, order_by, None)?;
+    let parameters = build_filter_parameters(filter)?;
+
+    stream_generic_response(pool, sql, Some(&parameters)).await
+}
+
+/// Loads the rows of `bulk` in batches of `BULK_INSERT_BATCH`, on the connection (and transaction) of the chain.
+/// Rows are matched to the table columns by name, the order of the columns in the database does not matter.
+async fn bulk_insert(client: &mut PooledClient, bulk: &BulkInsert) -> anyhow::Result<u64> {
+    let table = bulk.table();
+    check_identifier(table.name())?;
+    let table_name = format!("uploader.[{}]", table.name());
+
+    // Same column list `INSERT BULK` is going to expect
+    let database_columns = client
+        .simple_query(format!("SELECT TOP 0 * FROM {table_name}"))
+        .await?
+        .columns()
+        .await?
+        .map(|columns| columns.iter().map(|column| column.name().to_string()).collect::<Vec<_>>())
+        .ok_or_else(|| anyhow::anyhow!("No columns returned for '{}'", table.name()))?;
+
+    let order = database_columns.iter()
+        .map(|name| {
+            table.columns().iter()
+                .position(|column| column.name().eq_ignore_ascii_case(name))
+                .ok_or_else(|| anyhow::anyhow!("Column '{name}' of '{}' has no values in the bulk insert", table.name()))
+        })
+        .collect::<anyhow::Result<Vec<usize>>>()?;
+
+    let mut total = 0;
+
+    for batch in bulk.rows().chunks(BULK_INSERT_BATCH) {
+        let mut request = client.bulk_insert(&table_name).await?;
+
+        for row in batch {
+            anyhow::ensure!(
+                row.len() == table.columns().len(),
+                "Row must provide exactly one value per column of '{}' (expected {}, got {})",
+                table.name(),
+                table.columns().len(),
+                row.len()
+            );
 
-    get_generic_response(sql, None).await
+            let mut token_row = tiberius::TokenRow::with_capacity(order.len());
+            for &idx in &order {
+                let column = &table.columns()[idx];
+                let data = row[idx].to_column_data(column.typing())
+                    .map_err(|e| anyhow::anyhow!("Column '{}': {e}", column.name()))?;
+                token_row.push(data);
+            }
+
+            request.send(token_row).await?;
+        }
+
+        total += request.finalize().await?.total();
+    }
+
+    Ok(total)
 }
 
+/// Runs every step in one transaction, committed only if all the critical steps succeed.
+/// Non critical steps run behind a `SAVE TRANSACTION`, when they fail only their changes are undone.
+/// Returns the globals with the outputs of every step, see `ChainStep::returns`.
+/// The error of a failed step has a `ChainStepFailed` context with the step name and its SQL.
 pub async fn chain_executions<'a>(
+    pool: &Pool,
     chain_map: ChainMap<'a>,
-    mut global_values: SqlSingleParameters,
-) -> anyhow::Result<Vec<u64>> {
-    let mut rows_affected = Vec::<u64>::new();
+    global_values: SqlSingleParameters,
+) -> anyhow::Result<ChainContext> {
+    chain_map.check_names()?;
 
-    let mut client = mssql_client().await?;
+    let mut context = ChainContext::new(global_values);
+
+    let mut client = mssql_client(pool).await?;
     client.simple_query(st!("BEGIN TRANSACTION")).await?;
-    
+
     let result = async {
-        for (exec, mult, sing) in chain_map {
-            let (sql, parameters, new_global) = exec(mult, sing, &mut global_values)?;
-            
-            match new_global {
-                Some(name) => {
-                    let sql = format!("{sql} {GET_IDTT}");
-                    let query = parse_query(sql.clone(), parameters.as_ref())?;
-                    let stream = query.query(&mut client).await?;
-                    let row = stream
-                        .into_row()
-                        .await?
-                        .ok_or(anyhow::anyhow!("No data returned from query: {sql}"))?;
-                    
-                    let identity = row.try_get_by_index::<i64>(0)?
-                        .ok_or(anyhow::anyhow!("No identity value found in query: {sql}"))?;
-                
-                    global_values.insert(name, identity.to_sql_value());
-                    rows_affected.push(1);
-                },
-                None => {
-                    let query = parse_query(sql, parameters.as_ref())?;
-                    let partial_result = query.execute(&mut client).await?;
-                    rows_affected.extend(partial_result.rows_affected());
+        for (idx, step) in chain_map.into_iter().enumerate() {
+            let name = st!(step.name());
+            let critical = step.critical();
+            let savepoint = format!("chain_step_{idx}");
+
+            if !critical {
+                client.simple_query(format!("SAVE TRANSACTION {savepoint}")).await?.into_results().await?;
+            }
+
+            let mut sql = String::new();
+            let output = run_step(&mut client, step, &mut context, &mut sql).await;
+
+            match output {
+                Ok(output) => context.push_output(&name, output),
+                Err(e) if !critical => {
+                    log::warn!("Non critical step '{name}' failed, its changes are undone: {e:?}");
+                    client.simple_query(format!("ROLLBACK TRANSACTION {savepoint}")).await?.into_results().await?;
+                    context.push_output(&name, StepOutput::Skipped(e.to_string()));
+                }
+                Err(e) => {
+                    let message = e.to_string();
+                    return Err(e.context(ChainStepFailed { step: name, sql, message }));
                 }
             }
         }
-        
-        Ok::<Vec<u64>, anyhow::Error>(rows_affected)
+
+        Ok::<(), anyhow::Error>(())
     };
 
     match result.await {
-        Ok(affected) => {
+        Ok(()) => {
             client.simple_query(st!("COMMIT")).await?;
-            Ok(affected)
+            Ok(context)
         }
         Err(e) => {
-            client.simple_query(st!("ROLLBACK")).await?;
+            let rolled_back = match client.simple_query(st!("ROLLBACK")).await {
+                Ok(results) => results.into_results().await.is_ok(),
+                Err(_) => false,
+            };
+
+            // Only an error returned by the server leaves the connection in a known state, e.g. not halfway a bulk insert
+            let server_error = matches!(e.downcast_ref::<tiberius::error::Error>(), Some(tiberius::error::Error::Server(_)));
+            if !rolled_back || !server_error {
+                client.mark_broken();
+            }
+
             Err(e)
         }
     }
 }
 
+/// `sql` is left with the statement of the step, for the error when it fails
+async fn run_step(
+    client: &mut PooledClient,
+    step: ChainStep<'_>,
+    context: &mut ChainContext,
+    sql: &mut String,
+) -> anyhow::Result<StepOutput> {
+    let returns = step.returns_kind();
+
+    let (exec, mult, sing) = match step.into_kind() {
+        ChainStepKind::Exec(exec, mult, sing) => (exec, mult, sing),
+        ChainStepKind::BulkInsert(bulk) => {
+            *sql = format!("INSERT BULK uploader.[{}]", bulk.table().name());
+            return Ok(StepOutput::RowsAffected(vec![bulk_insert(client, &bulk).await?]));
+        }
+    };
+
+    let (step_sql, parameters, new_global) = exec(mult, sing, context)?;
+
+    // Steps that write their identity into the globals
+    let returns = if new_global.is_some() { StepReturns::Identity } else { returns };
+    *sql = match returns {
+        StepReturns::Identity => format!("{step_sql} {GET_IDTT}"),
+        _ => step_sql,
+    };
+
+    let query = parse_query(sql.clone(), parameters.as_ref())?;
+
+    let output = match returns {
+        StepReturns::RowsAffected => StepOutput::RowsAffected(query.execute(&mut **client).await?.rows_affected().to_vec()),
+        StepReturns::Identity => {
+            let row = query.query(&mut **client).await?
+                .into_row()
+                .await?
+                .ok_or_else(|| anyhow::anyhow!("No data returned from query: {sql}"))?;
+
+            let identity = row.try_get_by_index::<i64>(0)?
+                .ok_or_else(|| anyhow::anyhow!("No identity value found in query: {sql}"))?;
+
+            if let Some(name) = new_global {
+                context.set_global(name, identity.to_sql_value());
+            }
+
+            StepOutput::Identity(identity)
+        }
+        StepReturns::Scalar => {
+            let row = query.query(&mut **client).await?.into_row().await?;
+            let value = match row.as_ref().and_then(|row| row.cells().next()) {
+                Some((_, data)) => SqlValue::from_column_data(data)?,
+                None => SqlValue::None,
+            };
+
+            StepOutput::Scalar(value)
+        }
+        StepReturns::Rows => StepOutput::Rows(query.query(&mut **client).await?.into_first_result().await?),
+    };
+
+    Ok(output)
+}
+
 /* #endregion */
 
 #[cfg(test)]
@@ -537,6 +1068,7 @@ mod tests {
     use tiberius::ToSql;
 
     use super::*;
+    use super::super::pool;
     use crate::ddb::{
         DBLoad,
         context::db_types::{ChainReturn, SqlValue},
@@ -568,8 +1100,12 @@ mod tests {
             SqlValue::Str(st!("TEST")),
         );
 
-        let set_clause = build_set_clause(&new_values).unwrap();
-        assert_eq!(set_clause, st!("SET [SqlType] = @_SqlType"));
+        new_values.insert(st!("Sold At"), SqlValue::Int(3));
+
+        let (set_clause, parameters) = build_set_clause(&new_values).unwrap();
+        assert_eq!(set_clause, st!("SET [Sold At] = @s1, [SqlType] = @s2"));
+        assert!(matches!(parameters["s1"], SqlValue::Int(3)));
+        assert!(parse_sql(set_clause, Some(&parameters)).is_ok());
     }
 
     #[test]
@@ -585,6 +1121,14 @@ mod tests {
         assert_eq!(clause, st!("([SqlType], [ViewType])"));
     }
 
+    #[test]
+    fn check_identifiers() {
+        assert!(check_identifier("Sales 2024").is_ok());
+        assert!(check_identifier(" ").is_err());
+        assert!(check_identifier("A]; DROP TABLE SHEET; --").is_err());
+        assert!(check_identifier("A\nB").is_err());
+    }
+
     #[test]
     fn check_remove_sql_comments() {
         let sql = r#"
@@ -614,68 +1158,87 @@ AND name = '/* not a comment */'
         let sql = "
             SELECT *
             FROM users
-            WHERE id = @_user_id
+            WHERE id = @user_id
             AND status = @status
-            AND owner = @_user_id
+            AND owner = @user_id
         ";
 
         let params = extract_sql_params(sql);
 
-        assert!(params.contains(&st!("@_user_id")));
+        assert!(params.contains(&st!("@user_id")));
         assert!(params.contains(&st!("@status")));
     }
 
     #[test]
     fn check_parse_sql() {
-        let sql = "SELECT * FROM users /* aaa */ WHERE id = @_user_id AND status IN @_status AND bin = @BIN-- AAAAAAAAA";
+        let sql = "SELECT * FROM users /* aaa */ WHERE id = @user_id AND status IN (@status) AND name = @name AND note = @note AND bin = @BIN-- AAAAAAAAA";
         let mut sql_parameters = SqlSingleParameters::new();
         sql_parameters.insert(st!("user_id"), SqlValue::Int(123456));
         sql_parameters.insert(st!("status"), vec![st!("ON"), st!("OFF")].to_sql_value());
-        sql_parameters.insert(st!("BIN"), st!("0x...").to_sql_value());
+        sql_parameters.insert(st!("name"), st!("O'Brien; R&D -- 2*3").to_sql_value());
+        sql_parameters.insert(st!("note"), SqlValue::None);
+        sql_parameters.insert(st!("BIN"), vec![0u8, 1u8].to_sql_value());
 
-        let output = "SELECT * FROM users  WHERE id = 123456 AND status IN ('ON', 'OFF') AND bin = @P1";
+        let output = "SELECT * FROM users  WHERE id = @P1 AND status IN (@P2, @P3) AND name = @P4 AND note = NULL AND bin = @P5";
 
         let (new_sql, parameters) = parse_sql(st!(sql), Some(&sql_parameters)).unwrap();
 
         assert_eq!(new_sql, output);
-        assert_eq!(parameters.len(), 1);
-        assert_eq!(parameters[0].to_string(), st!("0x..."));
+        assert_eq!(parameters.len(), 4);
+        assert!(matches!(parameters[2], SqlValue::Str(v) if v == "O'Brien; R&D -- 2*3"));
+    }
+
+    #[test]
+    fn check_parse_sql_similar_tags() {
+        let mut sql_parameters = SqlSingleParameters::new();
+        for idx in 0..12 {
+            sql_parameters.insert(format!("Col_{idx}"), SqlValue::Int(idx));
+        }
+        sql_parameters.insert(st!("P1"), SqlValue::IntList(vec![]));
+
+        let sql = (0..12).map(|idx| format!("@Col_{idx}")).collect::<Vec<_>>().join(", ");
+        let (new_sql, parameters) = parse_sql(format!("{sql}, @P1"), Some(&sql_parameters)).unwrap();
+
+        let output = (1..=12).map(|idx| format!("@P{idx}")).collect::<Vec<_>>().join(", ");
+        assert_eq!(new_sql, format!("{output}, NULL"));
+        assert_eq!(parameters.len(), 12);
+        assert!(matches!(parameters[11], SqlValue::Int(11)));
     }
 
     #[test]
     fn check_build_where() {
         let mut a = SqlSingleParameters::new();
         a.insert(st!("Int"), SqlValue::Int(2));
-        let where_c = build_where_clause(&a);
-        assert_eq!(where_c, st!("WHERE [Int] = @_Int"));
+        let (where_c, _) = build_filter_clause(Filter::from(&a)).unwrap();
+        assert_eq!(where_c, st!("WHERE [Int] = @_w1"));
 
         let mut a = SqlSingleParameters::new();
         a.insert(st!("Float"), SqlValue::Float(2.5));
-        let where_c = build_where_clause(&a);
-        assert_eq!(where_c, st!("WHERE [Float] = @_Float"));
+        let (where_c, _) = build_filter_clause(Filter::from(&a)).unwrap();
+        assert_eq!(where_c, st!("WHERE [Float] = @_w1"));
 
         let mut a = SqlSingleParameters::new();
         a.insert(st!("Bool"), SqlValue::Bool(true));
-        let where_c = build_where_clause(&a);
-        assert_eq!(where_c, st!("WHERE [Bool] = @_Bool"));
+        let (where_c, _) = build_filter_clause(Filter::from(&a)).unwrap();
+        assert_eq!(where_c, st!("WHERE [Bool] = @_w1"));
 
         let mut a = SqlSingleParameters::new();
         a.insert(st!("StringL"), SqlValue::StrL(st!("OLOKO")));
-        let where_c = build_where_clause(&a);
-        assert_eq!(where_c, st!("WHERE [StringL] LIKE @_StringL"));
+        let (where_c, _) = build_filter_clause(Filter::from(&a)).unwrap();
+        assert_eq!(where_c, st!("WHERE [StringL] LIKE @_w1"));
 
         let mut a = SqlSingleParameters::new();
         a.insert(st!("StringN"), SqlValue::Str(st!(";-;")));
-        let where_c = build_where_clause(&a);
-        assert_eq!(where_c, st!("WHERE [StringN] = @_StringN"));
+        let (where_c, _) = build_filter_clause(Filter::from(&a)).unwrap();
+        assert_eq!(where_c, st!("WHERE [StringN] = @_w1"));
 
         let mut a = SqlSingleParameters::new();
         a.insert(
             st!("Date"),
             SqlValue::Date(NaiveDate::parse_from_str("2025-12-21", "%Y-%m-%d").unwrap()),
         );
-        let where_c = build_where_clause(&a);
-        assert_eq!(where_c, st!("WHERE [Date] = @_Date"));
+        let (where_c, _) = build_filter_clause(Filter::from(&a)).unwrap();
+        assert_eq!(where_c, st!("WHERE [Date] = @_w1"));
 
         let mut a = SqlSingleParameters::new();
         a.insert(
@@ -684,34 +1247,98 @@ AND name = '/* not a comment */'
                 NaiveDateTime::parse_from_str("2025-12-21 10:30:00", "%Y-%m-%d %H:%M:%S").unwrap(),
             ),
         );
-        let where_c = build_where_clause(&a);
-        assert_eq!(where_c, st!("WHERE [DateTime] = @_DateTime"));
+        let (where_c, _) = build_filter_clause(Filter::from(&a)).unwrap();
+        assert_eq!(where_c, st!("WHERE [DateTime] = @_w1"));
 
         let mut a = SqlSingleParameters::new();
         a.insert(st!("IntVec"), SqlValue::IntList(vec![1, 2, 3]));
-        let where_c = build_where_clause(&a);
-        assert_eq!(where_c, st!("WHERE [IntVec] IN (@_IntVec)"));
+        let (where_c, _) = build_filter_clause(Filter::from(&a)).unwrap();
+        assert_eq!(where_c, st!("WHERE [IntVec] IN (@_w1)"));
 
         let mut a = SqlSingleParameters::new();
         a.insert(st!("FloatVec"), SqlValue::FloatList(vec![1.5, 2.5, 3.5]));
-        let where_c = build_where_clause(&a);
-        assert_eq!(where_c, st!("WHERE [FloatVec] IN (@_FloatVec)"));
+        let (where_c, _) = build_filter_clause(Filter::from(&a)).unwrap();
+        assert_eq!(where_c, st!("WHERE [FloatVec] IN (@_w1)"));
 
         let mut a = SqlSingleParameters::new();
         a.insert(
             st!("StrVec"),
             SqlValue::StrList(vec![st!("1"), st!("2"), st!("3")]),
         );
-        let where_c = build_where_clause(&a);
-        assert_eq!(where_c, st!("WHERE [StrVec] IN (@_StrVec)"));
+        let (where_c, _) = build_filter_clause(Filter::from(&a)).unwrap();
+        assert_eq!(where_c, st!("WHERE [StrVec] IN (@_w1)"));
 
         let mut a = SqlSingleParameters::new();
         a.insert(
             st!("BIN"),
             SqlValue::Bin(vec![1, 2, 3]),
         );
-        let where_c = build_where_clause(&a);
-        assert_eq!(where_c, st!("WHERE [BIN] = @BIN"));
+        let (where_c, _) = build_filter_clause(Filter::from(&a)).unwrap();
+        assert_eq!(where_c, st!("WHERE [BIN] = @_w1"));
+
+        let mut a = SqlSingleParameters::new();
+        a.insert(st!("Sold At"), SqlValue::Int(2));
+        a.insert(st!("Amount"), SqlValue::None);
+        let (where_c, parameters) = build_filter_clause(Filter::from(&a)).unwrap();
+        assert_eq!(where_c, st!("WHERE ([Amount] IS NULL) AND ([Sold At] = @_w1)"));
+        assert!(parse_sql(where_c, Some(&parameters)).is_ok());
+    }
+
+    #[test]
+    fn check_build_predicate_clause() {
+        let from = NaiveDate::from_ymd_opt(2025, 1, 1).unwrap().and_hms_opt(0, 0, 0).unwrap();
+        let to = NaiveDate::from_ymd_opt(2025, 1, 31).unwrap().and_hms_opt(23, 59, 59).unwrap();
+
+        let predicate = Predicate::and(vec![
+            Predicate::between(HistSheet::COL_EDITED_AT, from, to),
+            Predicate::is_null(HistSheetMetaData::COL_REGEX_CONSTRAINT),
+            Predicate::or(vec![Predicate::eq(Profile::COL_ACTIVE, true), Predicate::eq(Profile::COL_IS_SUPER_USER, true)]),
+            Predicate::not(Predicate::is_in(HistSheet::COL_SHEET_FK, vec![1, 2])),
+            Predicate::not_in(Sheet::COL_PK, Vec::<i32>::new()),
+            Predicate::not_eq(Sheet::COL_DAYS_TO_REFRESH, SqlValue::None),
+        ]);
+
+        let (clause, parameters) = build_filter_clause(Filter::from(&predicate)).unwrap();
+        assert_eq!(
+            clause,
+            st!("WHERE ([EditedAt] BETWEEN @_w1 AND @_w2) AND ([RegexConstraint] IS NULL) \
+            AND (([Active] = @_w3) OR ([IsSuperUser] = @_w4)) AND (NOT ([Sheet_fk] IN (@_w5))) \
+            AND (1 = 1) AND ([DaysToRefresh] IS NOT NULL)")
+        );
+        assert_eq!(parameters.len(), 5);
+        assert!(matches!(parameters["_w5"], SqlValue::IntList(ref list) if list == &vec![1, 2]));
+
+        let (parsed, bound) = parse_sql(clause, Some(&parameters)).unwrap();
+        assert!(parsed.contains("[Sheet_fk] IN (@P5, @P6)"));
+        assert_eq!(bound.len(), 5);
+
+        assert_eq!(build_filter_clause(Filter::from(&Predicate::or(vec![]))).unwrap().0, st!("WHERE 1 = 0"));
+        assert!(build_filter_clause(Filter::from(&Predicate::is_in(Sheet::COL_PK, 1))).is_err());
+        assert!(build_filter_clause(Filter::from(&Predicate::gt(Sheet::COL_PK, vec![1]))).is_err());
+        assert!(build_filter_clause(Filter::from(&Predicate::is_null("pk] = 1 --"))).is_err());
+    }
+
+    #[test]
+    fn check_builders_take_predicates() {
+        let predicate = Predicate::or(vec![Predicate::lt(Sheet::COL_PK, 10), Predicate::like(Sheet::COL_DESCRIPTION, SqlValue::StrL(st!("Sales*")))]);
+
+        let sql = build_select_clause(Sheet::TAB, &predicate, None, Some(5), &[], None).unwrap();
+        assert_eq!(sql, st!("SELECT TOP 5 * FROM uploader.[SHEET] WHERE ([pk] < @_w1) OR ([Description] LIKE @_w2)"));
+
+        let sql = build_delete_clause(Sheet::TAB, &predicate).unwrap();
+        assert_eq!(sql, st!("DELETE FROM uploader.[SHEET] WHERE ([pk] < @_w1) OR ([Description] LIKE @_w2)"));
+
+        let mut new_values = SqlSingleParameters::new();
+        new_values.insert(st!(Sheet::COL_ACTIVE), SqlValue::Bool(false));
+        let (sql, parameters) = build_update_clause(Sheet::TAB, &new_values, &predicate).unwrap();
+        assert_eq!(sql, st!("UPDATE uploader.[SHEET] SET [Active] = @s1 WHERE ([pk] < @_w1) OR ([Description] LIKE @_w2)"));
+        assert!(parse_sql(sql, Some(&parameters)).is_ok());
+
+        // Parameters filter the same way as their predicate
+        let mut where_parameters = SqlSingleParameters::new();
+        where_parameters.insert(st!(Sheet::COL_PK), SqlValue::IntList(vec![1, 2]));
+        let (clause, _) = build_filter_clause(Filter::from(&Predicate::from_parameters(&where_parameters))).unwrap();
+        assert_eq!(clause, build_filter_clause(Filter::from(&where_parameters)).unwrap().0);
     }
 
     #[test]
@@ -723,19 +1350,19 @@ AND name = '/* not a comment */'
 
         assert_eq!(
             result,
-            st!("VALUES (@_SqlType_0, @_ViewType_0), (@_SqlType_1, @_ViewType_1), (@_SqlType_2, @_ViewType_2);")
+            st!("VALUES (@c0_0, @c1_0), (@c0_1, @c1_1), (@c0_2, @c1_2);")
         );
     }
 
     #[test]
     fn check_build_delete_clause() {
-        let sql = build_delete_clause(ColumnType::TAB, None);
+        let sql = build_delete_clause(ColumnType::TAB, None).unwrap();
         assert_eq!(sql, st!("DELETE FROM uploader.[COLUMN_TYPE] "));
 
         let mut where_parameters = SqlSingleParameters::new();
         where_parameters.insert(st!(ColumnType::COL_PK), SqlValue::Int(1));
-        let sql = build_delete_clause(ColumnType::TAB, Some(&where_parameters));
-        assert_eq!(sql, st!("DELETE FROM uploader.[COLUMN_TYPE] WHERE [pk] = @_pk"));
+        let sql = build_delete_clause(ColumnType::TAB, Some(&where_parameters)).unwrap();
+        assert_eq!(sql, st!("DELETE FROM uploader.[COLUMN_TYPE] WHERE [pk] = @_w1"));
     }
 
     /* #endregion */
@@ -744,27 +1371,58 @@ AND name = '/* not a comment */'
 
     #[test]
     fn check_build_select() {
-        let build = build_select_clause(ColumnType::TAB, None, None, None);
+        let build = build_select_clause(ColumnType::TAB, None, None, None, &[], None).unwrap();
         assert_eq!(build, st!("SELECT  * FROM uploader.[COLUMN_TYPE] "));
 
-        let build = build_select_clause(ColumnType::TAB, None, None, Some(10));
+        let build = build_select_clause(ColumnType::TAB, None, None, Some(10), &[], None).unwrap();
         assert_eq!(build, st!("SELECT TOP 10 * FROM uploader.[COLUMN_TYPE] "));
 
+        let build = build_select_clause(ColumnType::TAB, None, None, Some(1000), &[], None).unwrap();
+        assert_eq!(build, st!("SELECT TOP 1000 * FROM uploader.[COLUMN_TYPE] "));
+
+        let mut a = SqlSingleParameters::new();
+        a.insert(st!("Int"), SqlValue::Int(2));
+        let build = build_select_clause(ColumnType::TAB, Some(&a), None, None, &[], None).unwrap();
+        assert_eq!(
+            build,
+            st!("SELECT  * FROM uploader.[COLUMN_TYPE] WHERE [Int] = @_w1")
+        );
+
         let mut a = SqlSingleParameters::new();
         a.insert(st!("Int"), SqlValue::Int(2));
-        let build = build_select_clause(ColumnType::TAB, Some(&a), None, None);
+        let build = build_select_clause(ColumnType::TAB, Some(&a), None, Some(10), &[], None).unwrap();
         assert_eq!(
             build,
-            st!("SELECT  * FROM uploader.[COLUMN_TYPE] WHERE [Int] = @_Int")
+            st!("SELECT TOP 10 * FROM uploader.[COLUMN_TYPE] WHERE [Int] = @_w1")
         );
+    }
+
+    #[test]
+    fn check_build_select_ordered_page() {
+        let order = [OrderBy::desc(ColumnType::COL_SQL_TYPE), OrderBy::asc(ColumnType::COL_PK)];
+
+        let build = build_select_clause(ColumnType::TAB, None, None, Some(5), &order, None).unwrap();
+        assert_eq!(build, st!("SELECT TOP 5 * FROM uploader.[COLUMN_TYPE]  ORDER BY [SqlType] DESC, [pk] ASC"));
 
         let mut a = SqlSingleParameters::new();
         a.insert(st!("Int"), SqlValue::Int(2));
-        let build = build_select_clause(ColumnType::TAB, Some(&a), None, Some(10));
+        let page = Page::new(3, 20).unwrap();
+        let build = build_select_clause(ColumnType::TAB, Some(&a), None, None, &order, Some(page)).unwrap();
         assert_eq!(
             build,
-            st!("SELECT TOP 10 * FROM uploader.[COLUMN_TYPE] WHERE [Int] = @_Int")
+            st!("SELECT  * FROM uploader.[COLUMN_TYPE] WHERE [Int] = @_w1 ORDER BY [SqlType] DESC, [pk] ASC OFFSET 40 ROWS FETCH NEXT 20 ROWS ONLY")
         );
+
+        // Paging needs an order, and can not be mixed with TOP
+        assert!(build_select_clause(ColumnType::TAB, None, None, None, &[], Some(page)).is_err());
+        assert!(build_select_clause(ColumnType::TAB, None, None, Some(5), &order, Some(page)).is_err());
+        assert!(build_select_clause(ColumnType::TAB, None, None, None, &[OrderBy::asc("pk] --")], None).is_err());
+
+        assert!(Page::new(0, 20).is_err());
+        assert!(Page::new(1, 0).is_err());
+        assert!(Page::new(1, 501).is_err());
+
+        assert_eq!(build_count_clause(ColumnType::TAB, Some(&a)).unwrap(), st!("SELECT COUNT_BIG(*) FROM uploader.[COLUMN_TYPE] WHERE [Int] = @_w1"));
     }
 
     #[test]
@@ -776,10 +1434,17 @@ AND name = '/* not a comment */'
         // But values are generated using the same header, so the statement should match
         // one of these two possible orderings.
         let expected_a =
-            "INSERT INTO uploader.[COLUMN_TYPE] ([SqlType], [ViewType]) VALUES (@_SqlType_0, @_ViewType_0), (@_SqlType_1, @_ViewType_1), (@_SqlType_2, @_ViewType_2);";
+            "INSERT INTO uploader.[COLUMN_TYPE] ([SqlType], [ViewType]) VALUES (@c0_0, @c1_0), (@c0_1, @c1_1), (@c0_2, @c1_2);";
         let expected_b =
-            "INSERT INTO uploader.[COLUMN_TYPE] ([ViewType], [SqlType]) VALUES (@_ViewType_0, @_SqlType_0), (@_ViewType_1, @_SqlType_1), (@_ViewType_2, @_SqlType_2);";
+            "INSERT INTO uploader.[COLUMN_TYPE] ([ViewType], [SqlType]) VALUES (@c1_0, @c0_0), (@c1_1, @c0_1), (@c1_2, @c0_2);";
         assert!(sql == expected_a || sql == expected_b);
+
+        // Tags come from the column index, column names may hold spaces or accents
+        let mut a = SqlMultipleParameters::new();
+        a.add_line(vec![("Sold At", SqlValue::Int(1)), ("Preço", SqlValue::Float(2.5))]).unwrap();
+        let sql = build_insert_clause("SALES", &a).unwrap();
+        assert!(sql.ends_with("VALUES (@c0_0, @c1_0);") || sql.ends_with("VALUES (@c1_0, @c0_0);"));
+        assert!(parse_sql(sql, Some(&a.to_single())).is_ok());
     }
 
     #[test]
@@ -793,22 +1458,85 @@ AND name = '/* not a comment */'
         let mut where_parameters = SqlSingleParameters::new();
         where_parameters.insert(st!(ColumnType::COL_PK), SqlValue::Int(1));
 
-        let sql = build_update_clause(ColumnType::TAB, &new_values, Some(&where_parameters)).unwrap();
+        let (sql, parameters) = build_update_clause(ColumnType::TAB, &new_values, Some(&where_parameters)).unwrap();
+        assert_eq!(parameters.len(), 2);
         assert_eq!(
             sql,
-            st!("UPDATE uploader.[COLUMN_TYPE] SET [SqlType] = @_SqlType WHERE [pk] = @_pk")
+            st!("UPDATE uploader.[COLUMN_TYPE] SET [SqlType] = @s1 WHERE [pk] = @_w1")
         );
     }
 
+    #[test]
+    fn check_build_create_table_clause() {
+        let table = GenericTable::new(st!("SALES"), vec![
+            GenericColumn::new(st!("Region"), tiberius::ColumnType::NVarchar, false),
+            GenericColumn::new(st!("Sold At"), tiberius::ColumnType::Datetime2, false),
+            GenericColumn::new(st!("Amount"), tiberius::ColumnType::Float8, true),
+        ]);
+
+        let sql = build_create_table_clause(&table).unwrap();
+        assert_eq!(
+            sql,
+            st!("CREATE TABLE uploader.[SALES] ([Region] NVARCHAR(MAX) NOT NULL, [Sold At] DATETIME2 NOT NULL, [Amount] FLOAT NULL)")
+        );
+
+        let table = GenericTable::new(st!("SALES]; DROP TABLE x; --"), vec![
+            GenericColumn::new(st!("Region"), tiberius::ColumnType::NVarchar, false),
+        ]);
+        assert!(build_create_table_clause(&table).is_err());
+
+        let table = GenericTable::new(st!("EMPTY"), vec![]);
+        assert!(build_create_table_clause(&table).is_err());
+    }
+
+    #[test]
+    fn check_build_alter_clauses() {
+        let column = GenericColumn::new(st!("Amount"), tiberius::ColumnType::Int4, true);
+
+        let sql = build_add_column_clause("SALES", &column).unwrap();
+        assert_eq!(sql, st!("ALTER TABLE uploader.[SALES] ADD [Amount] INT NULL"));
+
+        let sql = build_alter_column_clause("SALES", &column, false).unwrap();
+        assert_eq!(sql, st!("ALTER TABLE uploader.[SALES] ALTER COLUMN [Amount] INT NULL"));
+
+        let sql = build_alter_column_clause("SALES", &column, true).unwrap();
+        assert_eq!(
+            sql,
+            st!("IF EXISTS (SELECT 1 FROM uploader.[SALES] WHERE [Amount] IS NOT NULL AND TRY_CONVERT(INT, [Amount]) IS NULL) \
+            THROW 50000, N'Column ''Amount'' has values that can not be converted to INT', 1; \
+            ALTER TABLE uploader.[SALES] ALTER COLUMN [Amount] INT NULL")
+        );
+    }
+
+    #[test]
+    fn check_build_custom_script_clause() {
+        let sql = build_custom_script_clause("UPDATE uploader.[SALES] SET [Note] = 'ok'", "Custom script 1", None).unwrap();
+        assert_eq!(
+            sql,
+            st!("BEGIN TRY EXEC sp_executesql N'UPDATE uploader.[SALES] SET [Note] = ''ok'''; END TRY \
+            BEGIN CATCH \
+            DECLARE @ScriptError NVARCHAR(2048) = CONCAT(N'Custom script 1: ', ERROR_MESSAGE()); \
+            THROW 50001, @ScriptError, 1; \
+            END CATCH")
+        );
+
+        let sql = build_custom_script_clause("SELECT 1", "Custom script 2", Some("SALES_STAGED")).unwrap();
+        assert!(sql.starts_with("DROP TABLE IF EXISTS #STAGED; SELECT * INTO #STAGED FROM uploader.[SALES_STAGED]; BEGIN TRY"));
+        assert!(sql.ends_with("END CATCH; DROP TABLE IF EXISTS #STAGED;"));
+
+        assert!(build_custom_script_clause("SELECT 1", "Custom script 3", Some("SALES]")).is_err());
+        assert_eq!(build_drop_table_clause("SALES").unwrap(), st!("DROP TABLE uploader.[SALES]"));
+    }
+
     /* #endregion */
 
     /* #region PUBLIC FUNCITONS */
 
     #[tokio::test]
     async fn check_get_query_result() {
-        dotenvy::dotenv().ok();
+        let pool = &pool::test_pool().await;
         let sql = st!("UPDATE uploader.COLUMN_TYPE SET [SqlType] = 'INT' WHERE [SqlType] = 'INT'");
-        let result = get_query_result(sql, None).await.unwrap();
+        let result = get_query_result(pool, sql, None).await.unwrap();
         let a = result.rows_affected();
 
         // Assuming that INT if a default type (every will use it)
@@ -819,13 +1547,13 @@ AND name = '/* not a comment */'
 
     #[tokio::test]
     async fn check_get_query() {
-        dotenvy::dotenv().ok();
+        let pool = &pool::test_pool().await;
         let sql = st!("SELECT * FROM uploader.COLUMN_TYPE");
-        let result = run_query(sql, None).await.unwrap();
+        let result = run_query(pool, sql, None).await.unwrap();
         assert!(result.len() > 0);
 
         let sql = st!("UPDATE uploader.COLUMN_TYPE SET [SqlType] = 'INT' WHERE [SqlType] = 'INT'");
-        let result = run_query(sql, None).await.unwrap();
+        let result = run_query(pool, sql, None).await.unwrap();
 
         // Assuming that INT if a default type (every will use it)
         assert_eq!(result, vec![1]);
@@ -833,9 +1561,9 @@ AND name = '/* not a comment */'
 
     #[tokio::test]
     async fn check_get_query_response() {
-        dotenvy::dotenv().ok();
+        let pool = &pool::test_pool().await;
 
-        let result = get_response_from::<ColumnType>(st!("_test"), None)
+        let result = get_response_from::<ColumnType>(pool, st!("_test"), None)
             .await
             .unwrap();
 
@@ -844,16 +1572,16 @@ AND name = '/* not a comment */'
 
     #[tokio::test]
     async fn check_get_query_single_response() {
-        dotenvy::dotenv().ok();
+        let pool = &pool::test_pool().await;
 
         let result =
-            get_single_response_from::<String>(st!("_test"), Some(ColumnType::COL_SQL_TYPE), None)
+            get_single_response_from::<String>(pool, st!("_test"), Some(ColumnType::COL_SQL_TYPE), None)
                 .await
                 .unwrap();
 
         assert!(result.is_some());
 
-        let result = get_single_response_from::<i32>(st!("_test"), None, None)
+        let result = get_single_response_from::<i32>(pool, st!("_test"), None, None)
             .await
             .unwrap();
 
@@ -862,18 +1590,18 @@ AND name = '/* not a comment */'
 
     #[tokio::test]
     async fn check_select_from() {
-        dotenvy::dotenv().ok();
+        let pool = &pool::test_pool().await;
 
-        let result = select_from::<ColumnType>(None, None, None).await.unwrap();
+        let result = select_from::<ColumnType>(pool, None, None, None, &[], None).await.unwrap();
 
         assert!(result.len() > 0);
     }
 
     #[tokio::test]
     async fn check_select_column_from() {
-        dotenvy::dotenv().ok();
+        let pool = &pool::test_pool().await;
 
-        let result = select_column_from::<ColumnType, String>(ColumnType::COL_SQL_TYPE, None, None)
+        let result = select_column_from::<ColumnType, String>(pool, ColumnType::COL_SQL_TYPE, None, None)
             .await
             .unwrap();
 
@@ -882,18 +1610,46 @@ AND name = '/* not a comment */'
 
     #[tokio::test]
     async fn check_select_single() {
-        dotenvy::dotenv().ok();
+        let pool = &pool::test_pool().await;
 
-        let result = select_single_from::<ColumnType, i32>(None, ColumnType::COL_PK)
+        let result = select_single_from::<ColumnType, i32>(pool, None, ColumnType::COL_PK)
             .await
             .unwrap();
         assert!(result.is_some());
     }
 
+    #[tokio::test]
+    async fn check_select_find_by_pk() {
+        let pool = &pool::test_pool().await;
+
+        let first = select_single_from::<ColumnType, i32>(pool, None, ColumnType::COL_PK).await.unwrap().unwrap();
+        let found = find_by_pk::<ColumnType>(pool, first).await.unwrap().unwrap();
+        assert_eq!(found.pk(), first);
+
+        let mut where_parameters = SqlSingleParameters::new();
+        where_parameters.insert(st!(ColumnType::COL_PK), SqlValue::Int(first));
+        let single = select_single_from::<ColumnType, i32>(pool, Some(&where_parameters), ColumnType::COL_PK).await.unwrap();
+        assert_eq!(single, Some(first));
+
+        assert!(find_by_pk::<ColumnType>(pool, -1).await.unwrap().is_none());
+        assert!(find_by_pk::<ProfileGroups>(pool, 1).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn check_select_find_one_and_exists() {
+        let pool = &pool::test_pool().await;
+
+        // Every type is greater than -1, so more than one row matches
+        assert!(find_one::<ColumnType>(pool, &Predicate::gt(ColumnType::COL_PK, -1)).await.is_err());
+
+        assert!(exists::<ColumnType>(pool, &Predicate::gt(ColumnType::COL_PK, -1)).await.unwrap());
+        assert!(!exists::<ColumnType>(pool, &Predicate::lt(ColumnType::COL_PK, -1)).await.unwrap());
+    }
+
     #[tokio::test]
     async fn check_get_generic_response() {
-        dotenvy::dotenv().ok();
-        let rows = get_generic_response(st!("SELECT * FROM uploader.[COLUMN_TYPE]"), None)
+        let pool = &pool::test_pool().await;
+        let rows = get_generic_response(pool, st!("SELECT * FROM uploader.[COLUMN_TYPE]"), None)
             .await
             .unwrap();
 
@@ -903,8 +1659,8 @@ AND name = '/* not a comment */'
 
     #[tokio::test]
     async fn check_select_generic() {
-        dotenvy::dotenv().ok();
-        let rows = select_generic("COLUMN_TYPE", None, None, None)
+        let pool = &pool::test_pool().await;
+        let rows = select_generic(pool, "COLUMN_TYPE", None, None, None, &[], None)
             .await
             .unwrap();
 
@@ -917,7 +1673,7 @@ AND name = '/* not a comment */'
         fn do_nothing(
             mult: Option<SqlMultipleParameters>,
             sing: Option<SqlSingleParameters>, 
-            glob: &SqlSingleParameters
+            glob: &ChainContext
         ) -> ChainReturn {
             Ok((st!(""), None, None))
         }
@@ -925,7 +1681,7 @@ AND name = '/* not a comment */'
         fn init_insert(
             mult: Option<SqlMultipleParameters>,
             sing: Option<SqlSingleParameters>, 
-            glob: &SqlSingleParameters
+            glob: &ChainContext
         ) -> ChainReturn {
             let sql = st!("INSERT INTO uploader.COLUMN_TYPE (SqlType, ViewType) VALUES ('asdaf', 'sadfas');");
             Ok((sql, None, Some(st!("pk"))))
@@ -934,7 +1690,7 @@ AND name = '/* not a comment */'
         fn delete_init(
             mult: Option<SqlMultipleParameters>,
             sing: Option<SqlSingleParameters>, 
-            glob: &SqlSingleParameters
+            glob: &ChainContext
         ) -> ChainReturn {
             let mut parameter = SqlSingleParameters::new();
             parameter.insert(
@@ -943,7 +1699,7 @@ AND name = '/* not a comment */'
                     .ok_or(anyhow::anyhow!("No 'pk' value found"))?
                     .clone()
             );
-            let sql = st!("DELETE FROM uploader.COLUMN_TYPE WHERE pk = @_pk");
+            let sql = st!("DELETE FROM uploader.COLUMN_TYPE WHERE pk = @pk");
 
             Ok((sql, Some(parameter), None))
         }
@@ -951,7 +1707,7 @@ AND name = '/* not a comment */'
         fn next_insert(
             mult: Option<SqlMultipleParameters>,
             sing: Option<SqlSingleParameters>, 
-            glob: &SqlSingleParameters
+            glob: &ChainContext
         ) -> ChainReturn {
             let mut parameter = SqlSingleParameters::new();
             parameter.insert(
@@ -961,7 +1717,7 @@ AND name = '/* not a comment */'
                     .clone()
             );
 
-            let sql = format!("INSERT INTO uploader.COLUMN_TYPE (SqlType, ViewType) VALUES (@_pk, @_pk);");
+            let sql = format!("INSERT INTO uploader.COLUMN_TYPE (SqlType, ViewType) VALUES (@pk, @pk);");
             
             Ok((sql, Some(parameter), Some(st!("pk"))))
         }
@@ -969,30 +1725,43 @@ AND name = '/* not a comment */'
         fn error_insert<'a>(
             mult: Option<SqlMultipleParameters>,
             sing: Option<SqlSingleParameters>, 
-            glob: &SqlSingleParameters
+            glob: &ChainContext
         ) -> ChainReturn {
             Ok((format!("||ERROR||"), None, None))
         }
 
-        dotenvy::dotenv().ok();
+        let pool = &pool::test_pool().await;
         let mut chain_exec = ChainMap::new();
         chain_exec.push(&init_insert, None, None);
         chain_exec.push(&delete_init, None, None);
         let mut p = SqlSingleParameters::new();
-        let v = chain_executions(chain_exec, p).await;
+        let v = chain_executions(pool, chain_exec, p).await;
         assert!(v.is_ok());
 
         let mut chain_exec = ChainMap::new();
         chain_exec.push(&init_insert, None, None);
         chain_exec.push(&next_insert, None, None);
         chain_exec.push(&next_insert, None, None);
-        chain_exec.push(&error_insert, None, None);
+        chain_exec.push(&error_insert, None, None).named("error");
         let mut p = SqlSingleParameters::new();
 
-        let v = chain_executions(chain_exec, p).await;
+        let v = chain_executions(pool, chain_exec, p).await;
         assert!(v.is_err());
-        let err_msg = v.unwrap_err().to_string();
-        assert!(err_msg.contains("Incorrect syntax near '|'"));
+        let err = v.unwrap_err();
+        let failed = err.downcast_ref::<ChainStepFailed>().unwrap();
+        assert_eq!((failed.step.as_str(), failed.sql.as_str()), ("error", "||ERROR||"));
+        assert!(err.to_string().contains("Incorrect syntax near '|'"));
+        assert!(err.downcast_ref::<tiberius::error::Error>().is_some());
+
+        let mut chain_exec = ChainMap::new();
+        chain_exec.push(&init_insert, None, None).named("init");
+        chain_exec.push(&error_insert, None, None).named("error").non_critical();
+        chain_exec.push(&delete_init, None, None);
+        let mut p = SqlSingleParameters::new();
+
+        let context = chain_executions(pool, chain_exec, p).await.unwrap();
+        assert!(context.identity("init").is_ok());
+        assert_eq!(context.skipped().map(|(name, _)| name).collect::<Vec<_>>(), vec!["error"]);
 
         let mut chain_exec = ChainMap::new();
         chain_exec.push(&do_nothing, None, None);
@@ -1001,7 +1770,7 @@ AND name = '/* not a comment */'
         chain_exec.push(&do_nothing, None, None);
         let mut p = SqlSingleParameters::new();
 
-        let v = chain_executions(chain_exec, p).await;
+        let v = chain_executions(pool, chain_exec, p).await;
 
         assert!(v.is_ok())
     }
@@ -1,7 +1,5 @@
-
-mod utils;
-
 pub mod functions;
+pub mod pool;
 
 pub mod db_types;
 pub mod tiberius_interface;
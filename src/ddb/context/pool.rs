@@ -0,0 +1,206 @@
+use std::env;
+use std::ops::{Deref, DerefMut};
+use std::str::FromStr;
+use std::time::Duration;
+
+use tiberius::{AuthMethod, Client, Config};
+use tokio::net::TcpStream;
+use tokio_util::compat::{Compat, TokioAsyncWriteCompatExt};
+
+pub type Pool = bb8::Pool<MssqlConnectionManager>;
+pub type PooledClient = bb8::PooledConnection<'static, MssqlConnectionManager>;
+
+/// Pool held in the axum state, see `AppState`. Cloning it is cheap and every clone shares the connections,
+/// work that outlives the request, e.g. the upload callbacks, keeps a clone.
+pub async fn from_env() -> anyhow::Result<Pool> {
+    let settings = PoolSettings::from_env()?;
+    let manager = MssqlConnectionManager::new(config_from_env()?);
+
+    build_pool(manager, &settings).await
+}
+
+/// Pool of the tests that need a database, configured by the `.env` file
+#[cfg(test)]
+pub async fn test_pool() -> Pool {
+    dotenvy::dotenv().ok();
+
+    from_env().await.expect("Tests that use the database need the DB_* variables")
+}
+
+pub async fn build_pool(manager: MssqlConnectionManager, settings: &PoolSettings) -> anyhow::Result<Pool> {
+    let pool = bb8::Pool::builder()
+        .min_idle(settings.min_idle)
+        .max_size(settings.max_size)
+        .connection_timeout(settings.acquire_timeout)
+        .idle_timeout(settings.idle_timeout)
+        // Idle connections are checked before being handed out
+        .test_on_check_out(true)
+        .build(manager)
+        .await?;
+
+    Ok(pool)
+}
+
+#[derive(Debug, PartialEq)]
+pub struct PoolSettings {
+    pub min_idle: u32,
+    pub max_size: u32,
+    pub acquire_timeout: Duration,
+    pub idle_timeout: Duration,
+}
+
+impl PoolSettings {
+    pub fn from_env() -> anyhow::Result<Self> {
+        Self::from_lookup(|name| env::var(name).ok())
+    }
+
+    fn from_lookup(lookup: impl Fn(&str) -> Option<String>) -> anyhow::Result<Self> {
+        let var = |name: &str, default: u64| -> anyhow::Result<u64> {
+            match lookup(name) {
+                Some(value) => parse_var(name, &value),
+                None => Ok(default),
+            }
+        };
+
+        let settings = Self {
+            min_idle: u32::try_from(var("DB_POOL_MIN_IDLE", 1)?)?,
+            max_size: u32::try_from(var("DB_POOL_MAX_SIZE", 10)?)?,
+            acquire_timeout: Duration::from_secs(var("DB_POOL_ACQUIRE_TIMEOUT_SECS", 30)?),
+            idle_timeout: Duration::from_secs(var("DB_POOL_IDLE_TIMEOUT_SECS", 600)?),
+        };
+
+        anyhow::ensure!(settings.max_size > 0, "'DB_POOL_MAX_SIZE' must be greater than 0");
+        anyhow::ensure!(
+            settings.min_idle <= settings.max_size,
+            "'DB_POOL_MIN_IDLE' can not be greater than 'DB_POOL_MAX_SIZE'"
+        );
+
+        Ok(settings)
+    }
+}
+
+fn parse_var<T: FromStr>(name: &str, value: &str) -> anyhow::Result<T> {
+    value.trim()
+        .parse()
+        .map_err(|_| anyhow::anyhow!("'{name}' has an invalid value '{value}'"))
+}
+
+fn config_from_env() -> anyhow::Result<Config> {
+    let mut config = Config::new();
+
+    let url = env::var("DB_URL")?;
+    let port: u16 = env::var("DB_PORT")?.parse()?;
+    let database = env::var("DB_NAME")?;
+    let user = env::var("DB_USER")?;
+    let password = env::var("DB_PASS")?;
+
+    config.host(url);
+    config.port(port);
+    config.database(database);
+    config.authentication(AuthMethod::sql_server(user, password));
+    config.trust_cert(); // remove in production
+
+    Ok(config)
+}
+
+pub struct MssqlConnectionManager {
+    config: Config,
+}
+
+impl MssqlConnectionManager {
+    pub fn new(config: Config) -> Self {
+        Self { config }
+    }
+}
+
+/// Client of the pool, with a flag for connections left in an unknown state
+pub struct MssqlConnection {
+    client: Client<Compat<TcpStream>>,
+    broken: bool,
+}
+
+impl MssqlConnection {
+    /// The connection is closed instead of going back to the pool, e.g. after a failed rollback
+    /// or when a result was abandoned halfway
+    pub fn mark_broken(&mut self) {
+        self.broken = true;
+    }
+}
+
+impl Deref for MssqlConnection {
+    type Target = Client<Compat<TcpStream>>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.client
+    }
+}
+
+impl DerefMut for MssqlConnection {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.client
+    }
+}
+
+impl bb8::ManageConnection for MssqlConnectionManager {
+    type Connection = MssqlConnection;
+    type Error = tiberius::error::Error;
+
+    async fn connect(&self) -> Result<Self::Connection, Self::Error> {
+        let tcp = TcpStream::connect(self.config.get_addr()).await?;
+        tcp.set_nodelay(true)?;
+
+        let client = Client::connect(self.config.clone(), tcp.compat_write()).await?;
+
+        Ok(MssqlConnection { client, broken: false })
+    }
+
+    // A request dropped in the middle of a transaction leaves it open, it is undone before reusing the connection
+    async fn is_valid(&self, conn: &mut Self::Connection) -> Result<(), Self::Error> {
+        conn.simple_query("IF @@TRANCOUNT > 0 ROLLBACK TRANSACTION; SELECT 1")
+            .await?
+            .into_row()
+            .await?;
+
+        Ok(())
+    }
+
+    fn has_broken(&self, conn: &mut Self::Connection) -> bool {
+        conn.broken
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    fn settings(vars: &[(&str, &str)]) -> anyhow::Result<PoolSettings> {
+        let vars: HashMap<String, String> = vars.iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect();
+
+        PoolSettings::from_lookup(|name| vars.get(name).cloned())
+    }
+
+    #[test]
+    fn check_pool_settings() {
+        assert_eq!(
+            settings(&[]).unwrap(),
+            PoolSettings {
+                min_idle: 1,
+                max_size: 10,
+                acquire_timeout: Duration::from_secs(30),
+                idle_timeout: Duration::from_secs(600),
+            }
+        );
+
+        let custom = settings(&[("DB_POOL_MAX_SIZE", " 4 "), ("DB_POOL_ACQUIRE_TIMEOUT_SECS", "5")]).unwrap();
+        assert_eq!(custom.max_size, 4);
+        assert_eq!(custom.acquire_timeout, Duration::from_secs(5));
+
+        assert!(settings(&[("DB_POOL_MAX_SIZE", "ten")]).is_err());
+        assert!(settings(&[("DB_POOL_MAX_SIZE", "0")]).is_err());
+        assert!(settings(&[("DB_POOL_MIN_IDLE", "5"), ("DB_POOL_MAX_SIZE", "2")]).is_err());
+    }
+}
@@ -1,12 +0,0 @@
-
-pub fn escape(input: &str) -> String {
-    input
-        .replace("'", "''")
-        .replace("\"", "")
-        .replace(";", "")
-        .replace("--", "")
-        .replace("*", "%")  // Turns dinamic searchs (like) can use '*' in place of '%'
-        .chars()
-        .filter(|c| !c.is_control())
-        .collect::<String>()
-}
\ No newline at end of file
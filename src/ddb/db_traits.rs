@@ -1,11 +1,33 @@
-use futures::{Stream, StreamExt};
-use tiberius::QueryStream;
+use futures::{Stream, StreamExt, TryStreamExt};
+use tiberius::{QueryStream, Row};
 use std::pin::Pin;
 
-pub trait DBLoad: Sized {
+use crate::ddb::context::db_types::{SqlSingleParameters, SqlValue};
+
+pub trait DBLoad: Sized + Send + 'static {
     const LEN: usize;
     const TAB: &'_ str;
     const COLS: &'_ [&'_ str];
+    /// Primary key columns, empty for tables without one
+    const PK: &'_ [&'_ str];
+
+    fn from_row(row: Row) -> anyhow::Result<Self>;
+
+    /// Rows decoded one at a time, as the server sends them
+    fn into_stream(stream: QueryStream<'_>) -> Pin<Box<dyn Stream<Item = anyhow::Result<Self>> + Send + '_>> {
+        Box::pin(stream.into_row_stream().map(|row| Self::from_row(row?)))
+    }
+
+    fn from_stream(stream: QueryStream<'_>) -> Pin<Box<dyn Future<Output = anyhow::Result<Vec<Self>>> + Send + '_>> {
+        Box::pin(Self::into_stream(stream).try_collect())
+    }
+}
+
+/// Values of a row written with `build_insert_clause` and `build_update_clause`, see `#[derive(ToSqlParameters)]`
+pub trait ToSqlParameters {
+    /// One line of `SqlMultipleParameters`
+    fn to_insert_params(&self) -> Vec<(&'static str, SqlValue)>;
 
-    fn from_stream(stream: QueryStream<'_>) -> Pin<Box<dyn Future<Output = anyhow::Result<Vec<Self>>> + Send + '_>>;
-}
\ No newline at end of file
+    /// New values of the row, without the columns of the `WHERE`
+    fn to_update_params(&self) -> SqlSingleParameters;
+}
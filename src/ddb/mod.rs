@@ -5,7 +5,7 @@ pub mod context;
 
 mod db_traits;
 
-pub use db_traits::DBLoad;
+pub use db_traits::{DBLoad, ToSqlParameters};
 pub use context::tiberius_interface;
 
 #[cfg(test)]
@@ -1,12 +1,12 @@
-use macros::dbload;
+use macros::{DBLoad, ToSqlParameters};
 use serde::{Serialize, Deserialize};
-use futures::{Stream, StreamExt};
-use serde_json::map::ValuesMut;
-use std::{pin::Pin, result};
-use tiberius::QueryStream;
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Default, Serialize, Deserialize, DBLoad, ToSqlParameters)]
+#[table("CUSTOM_SQL_SCRIPT")]
 pub struct CustomSqlScript {
+    /// Scripts of a sheet run in `pk` order
+    #[pk]
+    pk: i32,
     sheet_fk: i32,
     run_before_update: bool,
     run_after_update: bool,
@@ -15,30 +15,19 @@ pub struct CustomSqlScript {
 }
 
 impl CustomSqlScript {
-    pub const COL_SHEET_FK: &'static str = "Sheet_fk";
-    pub const COL_RUNBF: &'static str = "RunBeforeUpdate";
-    pub const COL_RUNAF: &'static str = "RunAfterUpdate";
-    pub const COL_RUNAS: &'static str = "RunAsUpdate";
-    pub const COL_SCRIPT: &'static str = "CustomScript";
-    
-    pub fn db_new(
-        sheet_fk: i32, 
-        run_before_update: bool,
-        run_after_update: bool,
-        run_as_update: bool,
-        custom_script: String
-    ) -> Self {
-        Self {
-            sheet_fk,
-            run_before_update,
-            run_after_update,
-            run_as_update,
-            custom_script
-        }
+    pub fn run_before_update(&self) -> bool {
+        self.run_before_update
+    }
+
+    pub fn run_after_update(&self) -> bool {
+        self.run_after_update
     }
-}
 
-use super::super::DBLoad;
-use super::super::tiberius_interface::FromOwnedSql;
+    pub fn run_as_update(&self) -> bool {
+        self.run_as_update
+    }
 
-dbload!(CustomSqlScript, "CUSTOM_SQL_SCRIPT", COL_SHEET_FK, COL_RUNBF, COL_RUNAF, COL_RUNAS, COL_SCRIPT);
+    pub fn custom_script(&self) -> &str {
+        &self.custom_script
+    }
+}
@@ -1,12 +1,10 @@
-use macros::dbload;
+use macros::{DBLoad, ToSqlParameters};
 use serde::{Serialize, Deserialize};
-use futures::{Stream, StreamExt};
-use serde_json::map::ValuesMut;
-use std::{pin::Pin, result};
-use tiberius::QueryStream;
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Default, Serialize, Deserialize, DBLoad, ToSqlParameters)]
+#[table("GROUP")]
 pub struct Group {
+    #[pk]
     pk: i32,
     name: String,
     active: bool,
@@ -15,31 +13,15 @@ pub struct Group {
 }
 
 impl Group {
-    pub const COL_PK: &'static str = "pk";
-    pub const COL_NAME: &'static str = "Name";
-    pub const COL_ACTIVE: &'static str = "Active";
-    pub const COL_BOARD_ID: &'static str = "BoardId";
-    pub const COL_LAST_EDITED_BY_FK: &'static str = "LastEditedBy_fk";
-    
-    pub fn db_new(
-        pk: i32,
-        name: String,
-        active: bool,
-        board_id: i32,
-        last_edited_by_fk: i32
-    ) -> Self {
-        Self {
-            pk,
-            name,
-            active,
-            board_id,
-            last_edited_by_fk
-        }
+    pub fn pk(&self) -> i32 {
+        self.pk
     }
-}
-
 
-use super::super::DBLoad;
-use super::super::tiberius_interface::FromOwnedSql;
+    pub fn active(&self) -> bool {
+        self.active
+    }
 
-dbload!(Group, "GROUP", COL_PK, COL_NAME, COL_ACTIVE, COL_BOARD_ID, COL_LAST_EDITED_BY_FK);
+    pub fn board_id(&self) -> i32 {
+        self.board_id
+    }
+}
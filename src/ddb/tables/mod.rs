@@ -44,6 +44,9 @@ pub use sheet::Sheet;
 mod upload;
 pub use upload::Upload;
 
+mod upload_callback;
+pub use upload_callback::UploadCallback;
+
 mod uploader_permission;
 pub use uploader_permission::UploaderPermission;
 
@@ -62,8 +65,8 @@ mod tests {
     async fn check_table<T>() 
         where T: DBLoad
     {
-        dotenvy::dotenv().ok();
-        let mut client = super::super::context::functions::mssql_client().await.unwrap();
+        let pool = super::super::context::pool::test_pool().await;
+        let mut client = super::super::context::functions::mssql_client(&pool).await.unwrap();
     
         let tab_name = T::TAB;
         let columns = T::COLS;
@@ -84,6 +87,14 @@ mod tests {
         let a = stream.into_row_stream().next().await;
     }
 
+    #[test]
+    fn check_primary_keys() {
+        assert_eq!(Sheet::PK, &[Sheet::COL_PK]);
+        assert_eq!(ProfileGroups::PK, &[ProfileGroups::COL_PROFILE_FK, ProfileGroups::COL_GROUP_FK]);
+        assert_eq!(HistSheet::PK, &[HistSheet::COL_PK]);
+        assert_eq!(CustomSqlScript::PK, &[CustomSqlScript::COL_PK]);
+    }
+
     #[tokio::test]
     async fn check_board() {
         check_table::<Board>().await
@@ -159,6 +170,11 @@ mod tests {
         check_table::<Upload>().await
     }
 
+    #[tokio::test]
+    async fn check_upload_callback() {
+        check_table::<UploadCallback>().await
+    }
+
     #[tokio::test]
     async fn check_uploader_permission() {
         check_table::<UploaderPermission>().await
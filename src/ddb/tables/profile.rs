@@ -1,12 +1,10 @@
-use macros::dbload;
+use macros::{DBLoad, ToSqlParameters};
 use serde::{Serialize, Deserialize};
-use futures::{Stream, StreamExt};
-use serde_json::map::ValuesMut;
-use std::{pin::Pin, result};
-use tiberius::QueryStream;
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Default, Serialize, Deserialize, DBLoad, ToSqlParameters)]
+#[table("PROFILE")]
 pub struct Profile {
+    #[pk]
     pk: i32,
     active: bool,
     board_fk: Option<i32>,
@@ -15,31 +13,23 @@ pub struct Profile {
 }
 
 impl Profile {
-    pub const COL_PK: &'static str = "pk";
-    pub const COL_ACTIVE: &'static str = "Active";
-    pub const COL_BOARD_FK: &'static str = "Board_fk";
-    pub const COL_WORKER_FK: &'static str = "Worker_fk";
-    pub const COL_IS_SUPER_USER: &'static str = "IsSuperUser";
-    
-    pub fn db_new(
-        pk: i32,
-        active: bool,
-        board_fk: Option<i32>,
-        worker_fk: i32,
-        is_super_user: bool,
-    ) -> Self {
-        Self {
-            pk,
-            active,
-            board_fk,
-            worker_fk,
-            is_super_user
-        }
+    pub fn pk(&self) -> i32 {
+        self.pk
     }
-}
 
+    pub fn active(&self) -> bool {
+        self.active
+    }
 
-use super::super::DBLoad;
-use super::super::tiberius_interface::FromOwnedSql;
+    pub fn board_fk(&self) -> Option<i32> {
+        self.board_fk
+    }
 
-dbload!(Profile, "PROFILE", COL_PK, COL_ACTIVE, COL_BOARD_FK?, COL_WORKER_FK, COL_IS_SUPER_USER);
+    pub fn worker_fk(&self) -> i32 {
+        self.worker_fk
+    }
+
+    pub fn is_super_user(&self) -> bool {
+        self.is_super_user
+    }
+}
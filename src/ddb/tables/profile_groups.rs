@@ -1,33 +1,21 @@
-use macros::dbload;
+use macros::{DBLoad, ToSqlParameters};
 use serde::{Serialize, Deserialize};
-use futures::{Stream, StreamExt};
-use serde_json::map::ValuesMut;
-use std::{pin::Pin, result};
-use tiberius::QueryStream;
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Default, Serialize, Deserialize, DBLoad, ToSqlParameters)]
+#[table("PROFILE_GROUPS")]
 pub struct ProfileGroups {
+    #[pk]
     profile_fk: i32,
+    #[pk]
     group_fk: i32,
 }
 
 impl ProfileGroups {
-    pub const COL_PROFILE_FK: &'static str = "Profile_fk";
-    pub const COL_GROUP_FK: &'static str = "Group_fk";
-    
-    pub fn db_new(
-        profile_fk: i32,
-        group_fk: i32,
-    ) -> Self {
-        Self {
-            profile_fk,
-            group_fk,
-        }
+    pub fn profile_fk(&self) -> i32 {
+        self.profile_fk
     }
-}
-
-
-use super::super::DBLoad;
-use super::super::tiberius_interface::FromOwnedSql;
 
-dbload!(ProfileGroups, "PROFILE_GROUPS", COL_PROFILE_FK, COL_GROUP_FK);
+    pub fn group_fk(&self) -> i32 {
+        self.group_fk
+    }
+}
@@ -1,57 +1,42 @@
-use macros::dbload;
+use macros::{DBLoad, ToSqlParameters};
 use serde::{Serialize, Deserialize};
-use futures::{Stream, StreamExt};
-use serde_json::map::ValuesMut;
-use std::{pin::Pin, result};
-use tiberius::QueryStream;
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Default, Serialize, Deserialize, DBLoad, ToSqlParameters)]
+#[table("SHEET_META_DATA")]
 pub struct SheetMetaData {
+    #[pk]
     pk: i32,
     sheet_fk: i32,
     column_name: String,
     column_type_fk: i32,
-    optinal: bool,
-    regex_constrait: Option<String>,
-    last_editeded_by_fk: i32,
+    optional: bool,
+    regex_constraint: Option<String>,
+    last_edited_by_fk: i32,
     description: String
 }
 
 impl SheetMetaData {
-    pub const COL_PK: &'static str = "pk";
-    pub const COL_SHEET_FK: &'static str = "Sheet_fk";
-    pub const COL_COLUMN_NAME: &'static str = "ColumnName";
-    pub const COL_COLUMN_TYPE_FK: &'static str = "ColumnType_fk";
-    pub const COL_OPTIONAL: &'static str = "Optional";
-    pub const COL_REGEX_CONSTRAINT: &'static str = "RegexConstraint";
-    pub const COL_LAST_EDITED_BY_FK: &'static str = "LastEditedBy_fk";
-    pub const COL_DESCRIPTION: &'static str = "Description";
-    
-    pub fn db_new(
-        pk: i32,
-        sheet_fk: i32,
-        column_name: String,
-        column_type_fk: i32,
-        optinal: bool,
-        regex_constrait: Option<String>,
-        last_editeded_by_fk: i32,
-        description: String
-    ) -> Self {
-        Self {
-            pk,
-            sheet_fk,
-            column_name,
-            column_type_fk,
-            optinal,
-            regex_constrait,
-            last_editeded_by_fk,
-            description
-        }
+    pub fn pk(&self) -> i32 {
+        self.pk
     }
-}
 
+    pub fn column_name(&self) -> &str {
+        &self.column_name
+    }
 
-use super::super::DBLoad;
-use super::super::tiberius_interface::FromOwnedSql;
+    pub fn column_type_fk(&self) -> i32 {
+        self.column_type_fk
+    }
+
+    pub fn optional(&self) -> bool {
+        self.optional
+    }
 
-dbload!(SheetMetaData, "SHEET_META_DATA", COL_PK, COL_SHEET_FK, COL_COLUMN_NAME, COL_COLUMN_TYPE_FK, COL_OPTIONAL, COL_REGEX_CONSTRAINT?, COL_LAST_EDITED_BY_FK, COL_DESCRIPTION);
+    pub fn regex_constraint(&self) -> Option<&str> {
+        self.regex_constraint.as_deref()
+    }
+
+    pub fn description(&self) -> &str {
+        &self.description
+    }
+}
@@ -0,0 +1,16 @@
+use chrono::NaiveDateTime;
+use macros::{DBLoad, ToSqlParameters};
+use serde::{Serialize, Deserialize};
+
+#[derive(Debug, Default, Serialize, Deserialize, DBLoad, ToSqlParameters)]
+#[table("UPLOAD_CALLBACK")]
+pub struct UploadCallback {
+    #[pk]
+    pk: i32,
+    upload_fk: i32,
+    attempt: i32,
+    url: String,
+    status_code: Option<i32>,
+    error: Option<String>,
+    attempted_at: NaiveDateTime
+}
@@ -1,13 +1,12 @@
-use macros::dbload;
+use macros::{DBLoad, ToSqlParameters};
 use serde::{Serialize, Deserialize};
-use futures::{Stream, StreamExt};
-use serde_json::map::ValuesMut;
-use std::{pin::Pin, result};
-use tiberius::QueryStream;
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Default, Serialize, Deserialize, DBLoad, ToSqlParameters)]
+#[table("UPLOADER_PERMISSION")]
 pub struct UploaderPermission {
+    #[pk]
     group_fk: i32,
+    #[pk]
     sheet_fk: i32,
     can_view_hist: bool,
     can_upload: bool,
@@ -15,30 +14,19 @@ pub struct UploaderPermission {
 }
 
 impl UploaderPermission {
-    pub const COL_GROUP_FK: &'static str = "Group_fk";
-    pub const COL_SHEET_FK: &'static str = "Sheet_fk";
-    pub const COL_CAN_VIEW_HIST: &'static str = "CanViewHist";
-    pub const COL_CAN_UPLOAD: &'static str = "CanUpload";
-    pub const COL_LAST_EDITED_BY_FK: &'static str = "LastEditedBy_fk";
-    
-    pub fn db_new(
-        group_fk: i32,
-        sheet_fk: i32,
-        can_view_hist: bool,
-        can_upload: bool,
-        last_edited_by_fk: i32,
-    ) -> Self {
-        Self {
-            group_fk,
-            sheet_fk,
-            can_view_hist,
-            can_upload,
-            last_edited_by_fk,
-        }
+    pub fn group_fk(&self) -> i32 {
+        self.group_fk
+    }
+
+    pub fn sheet_fk(&self) -> i32 {
+        self.sheet_fk
     }
-}
 
-use super::super::DBLoad;
-use super::super::tiberius_interface::FromOwnedSql;
+    pub fn can_view_hist(&self) -> bool {
+        self.can_view_hist
+    }
 
-dbload!(UploaderPermission, "UPLOADER_PERMISSION", COL_GROUP_FK, COL_SHEET_FK, COL_CAN_VIEW_HIST, COL_CAN_UPLOAD, COL_LAST_EDITED_BY_FK);
+    pub fn can_upload(&self) -> bool {
+        self.can_upload
+    }
+}
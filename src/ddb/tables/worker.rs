@@ -1,13 +1,11 @@
 use chrono::NaiveDateTime;
-use macros::dbload;
+use macros::{DBLoad, ToSqlParameters};
 use serde::{Serialize, Deserialize};
-use futures::{Stream, StreamExt};
-use serde_json::map::ValuesMut;
-use std::{pin::Pin, result};
-use tiberius::QueryStream;
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Default, Serialize, Deserialize, DBLoad, ToSqlParameters)]
+#[table("WORKER")]
 pub struct Worker {
+    #[pk]
     pk: i32,
     name: String,
     linde_id: String,
@@ -15,27 +13,11 @@ pub struct Worker {
 }
 
 impl Worker {
-    pub const COL_PK: &'static str = "pk";
-    pub const COL_NAME: &'static str = "Name";
-    pub const COL_LINDE_ID: &'static str = "LindeId";
-    pub const COL_EMAIL: &'static str = "Email";
-    
-    pub fn db_new(
-        pk: i32,
-        name: String,
-        linde_id: String,
-        email: String,
-    ) -> Self {
-        Self {
-            pk,
-            name,
-            linde_id,
-            email,
-        }
+    pub fn pk(&self) -> i32 {
+        self.pk
     }
-}
-
-use super::super::DBLoad;
-use super::super::tiberius_interface::FromOwnedSql;
 
-dbload!(Worker, "WORKER", COL_PK, COL_NAME, COL_LINDE_ID, COL_EMAIL);
+    pub fn linde_id(&self) -> &str {
+        &self.linde_id
+    }
+}
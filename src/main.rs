@@ -3,6 +3,8 @@
 use axum::Router;
 use std::net::{IpAddr, Ipv4Addr, SocketAddr};
 
+use file_uploader::api;
+
 mod routes;
 
 #[tokio::main]
@@ -21,7 +23,9 @@ async fn main() {
 
     let listener = tokio::net::TcpListener::bind(addr).await.expect("Failed to bind address");
     
-    let app = routes::app();
+    let state = api::AppState::from_env().await.expect("Failed to connect to the database");
+
+    let app = routes::app(state);
     axum::serve(listener, app).await.unwrap();
 }
 
@@ -0,0 +1,31 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LoginRequest {
+    /// Token signed by the company sign in with `SSO_SECRET`, its `sub` is the Linde ID of the worker
+    pub assertion: String,
+    /// Profile to act as, the first active profile of the worker when not given
+    pub profile_pk: Option<i32>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TokenResponse {
+    /// Sent back as `Authorization: Bearer <token>`
+    pub token: String,
+    /// Unix timestamp, in seconds
+    pub expires_at: i64,
+    pub profile_pk: i32,
+    /// Real profile behind an impersonation token
+    pub impersonator_pk: Option<i32>,
+}
+
+/// Active profile the worker can switch to
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct WorkerProfile {
+    pub pk: i32,
+    pub board_fk: Option<i32>,
+    pub board_name: Option<String>,
+    pub is_super_user: bool,
+    /// Profile of the token used for the request
+    pub current: bool,
+}
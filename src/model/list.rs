@@ -0,0 +1,44 @@
+use serde::{Deserialize, Serialize};
+
+fn default_page() -> u32 {
+    1
+}
+
+fn default_size() -> u32 {
+    50
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ListQuery {
+    /// 1-based
+    #[serde(default = "default_page")]
+    pub page: u32,
+    #[serde(default = "default_size")]
+    pub size: u32,
+    /// Column name, as stored in the database
+    pub order_by: Option<String>,
+    #[serde(default)]
+    pub desc: bool,
+}
+
+impl Default for ListQuery {
+    fn default() -> Self {
+        Self { page: default_page(), size: default_size(), order_by: None, desc: false }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Paged<T> {
+    pub items: Vec<T>,
+    /// Rows matching the listing, on all the pages
+    pub total: i64,
+    pub page: u32,
+    pub size: u32,
+}
+
+#[derive(Debug, Serialize, Deserialize, derive_more::Display, derive_more::Error)]
+#[display("{message}")]
+pub struct InvalidListQuery {
+    #[error(not(source))]
+    pub message: String,
+}
@@ -1,5 +1,23 @@
 mod sheet;
-pub use sheet::NewSheetRequest;
+pub use sheet::{NewSheetRequest, MisspelledColumn, ModelFileMismatch, InvalidSheetDefinition, TemplateFormat, TemplateQuery};
 
 mod sheet_meta_data;
-pub use sheet_meta_data::NewSheetMetaDataRequest;
+pub use sheet_meta_data::{NewSheetMetaDataRequest, EditSheetMetaDataRequest, RefusedColumnChange, ColumnChangesRefused};
+
+mod upload;
+pub use upload::{UploadQuery, UploadReport, UploadIssue, SheetConfigError, UploadCallback};
+
+mod list;
+pub use list::{ListQuery, Paged, InvalidListQuery};
+
+mod auth;
+pub use auth::{LoginRequest, TokenResponse, WorkerProfile};
+
+mod permission;
+pub use permission::{SheetPermission, UploaderRight, ManagerAction};
+
+mod users;
+pub use users::{
+    NewWorkerRequest, EditWorkerRequest, NewProfileRequest, NewGroupRequest, ManagerPermissionRequest, GroupPermissionRequest,
+    WorkerExists, PolicyRefused
+};
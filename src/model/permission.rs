@@ -0,0 +1,61 @@
+use serde::{Deserialize, Serialize};
+
+/// What a profile may do on a sheet, through all of its active groups
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct SheetPermission {
+    pub sheet_pk: i32,
+    pub can_view_hist: bool,
+    pub can_upload: bool,
+}
+
+impl SheetPermission {
+    pub fn none(sheet_pk: i32) -> Self {
+        Self { sheet_pk, can_view_hist: false, can_upload: false }
+    }
+
+    pub fn all(sheet_pk: i32) -> Self {
+        Self { sheet_pk, can_view_hist: true, can_upload: true }
+    }
+
+    pub fn allows(&self, right: UploaderRight) -> bool {
+        match right {
+            UploaderRight::ViewHist => self.can_view_hist,
+            UploaderRight::Upload => self.can_upload,
+        }
+    }
+}
+
+/// Columns of `UPLOADER_PERMISSION`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum UploaderRight {
+    ViewHist,
+    Upload,
+}
+
+/// Columns of `MANAGER_PERMISSION`
+#[derive(Debug, Clone, Copy, PartialEq, derive_more::Display)]
+pub enum ManagerAction {
+    AddWorker,
+    EditWorker,
+    AddProfile,
+    RemoveProfile,
+    AddGroup,
+    RemoveGroup,
+    EditGroup,
+    EditProfileGroups,
+    ImpersonateUsers,
+}
+
+impl ManagerAction {
+    pub const ALL: [ManagerAction; 9] = [
+        ManagerAction::AddWorker,
+        ManagerAction::EditWorker,
+        ManagerAction::AddProfile,
+        ManagerAction::RemoveProfile,
+        ManagerAction::AddGroup,
+        ManagerAction::RemoveGroup,
+        ManagerAction::EditGroup,
+        ManagerAction::EditProfileGroups,
+        ManagerAction::ImpersonateUsers,
+    ];
+}
@@ -1,13 +1,80 @@
+use macros::ToSqlParameters;
 use serde::{Deserialize, Serialize};
 
+use crate::ddb::tables::Sheet;
 
 
 
 
-#[derive(Debug, Serialize, Deserialize)]
+
+#[derive(Debug, Serialize, Deserialize, ToSqlParameters)]
+#[sql(table = Sheet)]
 pub struct NewSheetRequest {
     pub description: String,
     pub table_name: String,
+    // The column is NOT NULL, left to its default until sheets can be edited
+    #[sql(skip)]
     pub days_to_refresh: Option<i32>,
     pub request_after_update: Option<String>
-}
\ No newline at end of file
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MisspelledColumn {
+    pub found: String,
+    pub expected: String,
+}
+
+/// Differences between the header of a `Sheet.Model` file and the declared columns
+#[derive(Debug, Default, Serialize, Deserialize, derive_more::Display, derive_more::Error)]
+#[display("Model file does not match the sheet columns")]
+pub struct ModelFileMismatch {
+    pub missing: Vec<String>,
+    pub extra: Vec<String>,
+    pub misspelled: Vec<MisspelledColumn>,
+    /// Set when the file could not be read at all
+    pub unreadable: Option<String>,
+}
+
+impl ModelFileMismatch {
+    pub fn is_empty(&self) -> bool {
+        self.missing.is_empty() && self.extra.is_empty() && self.misspelled.is_empty() && self.unreadable.is_none()
+    }
+}
+
+/// The new sheet has an invalid table or column name, or a column of an unknown type
+#[derive(Debug, Serialize, Deserialize, derive_more::Display, derive_more::Error)]
+#[display("{message}")]
+pub struct InvalidSheetDefinition {
+    #[error(not(source))]
+    pub message: String,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TemplateFormat {
+    #[default]
+    Xlsx,
+    Csv,
+}
+
+impl TemplateFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            TemplateFormat::Xlsx => "xlsx",
+            TemplateFormat::Csv => "csv",
+        }
+    }
+
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            TemplateFormat::Xlsx => "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet",
+            TemplateFormat::Csv => "text/csv; charset=utf-8",
+        }
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct TemplateQuery {
+    #[serde(default)]
+    pub format: TemplateFormat,
+}
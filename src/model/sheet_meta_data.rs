@@ -1,14 +1,40 @@
+use macros::ToSqlParameters;
 use serde::{Deserialize, Serialize};
 
+use crate::ddb::tables::SheetMetaData;
 
 
 
 
-#[derive(Debug, Serialize, Deserialize)]
+
+#[derive(Debug, Serialize, Deserialize, ToSqlParameters)]
+#[sql(table = SheetMetaData)]
 pub struct NewSheetMetaDataRequest {
+    // Renaming would leave the data table behind
+    #[sql(rename = column_name, skip_update)]
     pub name: String,
     pub column_type_fk: i32,
     pub optional: bool,
     pub regex_constraint: Option<String>,
     pub description: String
-}
\ No newline at end of file
+}
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EditSheetMetaDataRequest {
+    /// `None` for columns being added to the sheet
+    pub pk: Option<i32>,
+    #[serde(flatten)]
+    pub column: NewSheetMetaDataRequest,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RefusedColumnChange {
+    pub column: String,
+    pub reason: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, derive_more::Display, derive_more::Error)]
+#[display("{} column change(s) refused", changes.len())]
+pub struct ColumnChangesRefused {
+    #[error(not(source))]
+    pub changes: Vec<RefusedColumnChange>,
+}
@@ -0,0 +1,47 @@
+use serde::{Deserialize, Serialize};
+
+
+
+
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct UploadQuery {
+    /// Only validates the file, nothing is written to the database
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct UploadReport {
+    pub rows: usize,
+    pub errors: Vec<UploadIssue>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UploadIssue {
+    /// 1-based line of the file or row of the worksheet, `None` when the issue is in the header
+    pub row: Option<usize>,
+    pub column: String,
+    pub value: Option<String>,
+    /// Regex constraint the value failed to match
+    pub pattern: Option<String>,
+    pub message: String,
+}
+
+/// The sheet definition itself is broken, so no file can be validated against it
+#[derive(Debug, Serialize, Deserialize, derive_more::Display, derive_more::Error)]
+#[display("Column '{column}' of the sheet has an invalid regex constraint '{pattern}': {message}")]
+pub struct SheetConfigError {
+    pub column: String,
+    pub pattern: String,
+    pub message: String,
+}
+
+/// Body of the `Sheet.RequestAfterUpdate` request, sent once an upload is committed
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct UploadCallback {
+    pub sheet_pk: i32,
+    pub upload_id: i64,
+    pub rows: usize,
+    pub uploaded_by: i32,
+}
@@ -0,0 +1,84 @@
+use macros::ToSqlParameters;
+use serde::{Deserialize, Serialize};
+
+use crate::ddb::tables::{ ManagerPermission, UploaderPermission, Worker };
+
+#[derive(Debug, Serialize, Deserialize, ToSqlParameters)]
+#[sql(table = Worker)]
+pub struct NewWorkerRequest {
+    pub name: String,
+    pub linde_id: String,
+    pub email: String,
+    /// Board of the first profile of the worker, the one of the manager when not given
+    #[sql(skip)]
+    pub board_fk: Option<i32>,
+    #[sql(skip)]
+    #[serde(default)]
+    pub is_super_user: bool,
+}
+
+/// Managers may only change these columns
+#[derive(Debug, Serialize, Deserialize, ToSqlParameters)]
+#[sql(table = Worker)]
+pub struct EditWorkerRequest {
+    pub name: String,
+    pub linde_id: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct NewProfileRequest {
+    pub worker_fk: i32,
+    /// The board of the manager when not given, always empty for super users
+    pub board_fk: Option<i32>,
+    #[serde(default)]
+    pub is_super_user: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct NewGroupRequest {
+    pub name: String,
+    /// The board of the manager when not given
+    pub board_id: Option<i32>,
+    /// Only super users can create a group with manager permission
+    pub manager_permission: Option<ManagerPermissionRequest>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize, ToSqlParameters)]
+#[sql(table = ManagerPermission)]
+#[serde(default)]
+pub struct ManagerPermissionRequest {
+    pub add_worker: bool,
+    pub edit_worker: bool,
+    pub add_profile: bool,
+    pub remove_profile: bool,
+    pub add_group: bool,
+    pub remove_group: bool,
+    pub edit_group: bool,
+    pub edit_profile_groups: bool,
+    pub impersonate_users: bool,
+}
+
+/// Replaces the `UPLOADER_PERMISSION` of the group on the sheet
+#[derive(Debug, Serialize, Deserialize, ToSqlParameters)]
+#[sql(table = UploaderPermission)]
+pub struct GroupPermissionRequest {
+    pub sheet_fk: i32,
+    pub can_view_hist: bool,
+    pub can_upload: bool,
+}
+
+/// `LindeId` is unique
+#[derive(Debug, Serialize, Deserialize, derive_more::Display, derive_more::Error)]
+#[display("A worker with the Linde ID '{linde_id}' already exists")]
+pub struct WorkerExists {
+    #[error(not(source))]
+    pub linde_id: String,
+}
+
+/// A `MANAGER_PERMISSION` rule forbids the change
+#[derive(Debug, Serialize, Deserialize, derive_more::Display, derive_more::Error)]
+#[display("{message}")]
+pub struct PolicyRefused {
+    #[error(not(source))]
+    pub message: String,
+}
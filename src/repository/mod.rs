@@ -1,14 +1,17 @@
 use crate::ddb::DBLoad;
-use crate::ddb::context::db_types::{SqlMultipleParameters, SqlSingleParameters, ChainReturn};
-use crate::ddb::context::functions::build_insert_clause;
-use crate::ddb::tables::{Sheet, SheetMetaData};
+use crate::ddb::context::db_types::{GenericColumn, GenericTable, Predicate, SqlMultipleParameters, SqlSingleParameters, SqlValue, ChainContext, ChainReturn};
+use crate::ddb::context::functions::{
+    build_add_column_clause, build_alter_column_clause, build_create_table_clause, build_custom_script_clause,
+    build_delete_clause, build_drop_table_clause, build_filter_parameters, build_insert_clause, build_update_clause
+};
+use crate::ddb::tables::{Group, ManagerPermission, Profile, Sheet, SheetMetaData, Upload, Worker};
 use crate::{st, try_get_glob, try_unwrap_in_place};
 
 
 pub fn sheet_insert(
     mult: Option<SqlMultipleParameters>,
     sing: Option<SqlSingleParameters>, 
-    glob: &SqlSingleParameters
+    glob: &ChainContext
 ) -> ChainReturn {
     try_unwrap_in_place!(mult);
     let sql = build_insert_clause(Sheet::TAB, &mult)?;
@@ -23,7 +26,7 @@ pub fn sheet_insert(
 pub fn sheet_meta_data_insert(
     mult: Option<SqlMultipleParameters>,
     sing: Option<SqlSingleParameters>, 
-    glob: &SqlSingleParameters
+    glob: &ChainContext
 ) -> ChainReturn {
     try_unwrap_in_place!(mult);
     let sheet_id = try_get_glob!(glob, Sheet::COL_PK);
@@ -35,4 +38,233 @@ pub fn sheet_meta_data_insert(
         Some(mult.to_single()),
         None
     ))
-}
\ No newline at end of file
+}
+
+pub fn upload_insert(
+    mult: Option<SqlMultipleParameters>,
+    sing: Option<SqlSingleParameters>, 
+    glob: &ChainContext
+) -> ChainReturn {
+    try_unwrap_in_place!(mult);
+    let sheet_id = try_get_glob!(glob, Sheet::COL_PK);
+    mult.add_const_column(sheet_id, Upload::COL_SHEET_FK);
+    let sql = build_insert_clause(Upload::TAB, &mult)?;
+
+    Ok((
+        sql,
+        Some(mult.to_single()),
+        Some(st!(Upload::COL_PK))
+    ))
+}
+
+/// Unlike the other steps the table definition is not a sql parameter, so the step is built around it
+pub fn sheet_table_create(
+    table: GenericTable
+) -> impl Fn(Option<SqlMultipleParameters>, Option<SqlSingleParameters>, &ChainContext) -> ChainReturn + Send + Sync {
+    move |mult, sing, glob| {
+        let sql = build_create_table_clause(&table)?;
+
+        Ok((
+            sql,
+            None,
+            None
+        ))
+    }
+}
+
+/// `sing` holds the new values and the `pk` of the updated row
+pub fn sheet_meta_data_update(
+    mult: Option<SqlMultipleParameters>,
+    sing: Option<SqlSingleParameters>, 
+    glob: &ChainContext
+) -> ChainReturn {
+    try_unwrap_in_place!(sing);
+    let pk = sing
+        .remove(SheetMetaData::COL_PK)
+        .ok_or_else(|| anyhow::anyhow!("No '{}' passed to update the sheet meta data", SheetMetaData::COL_PK))?;
+
+    let mut where_parameters = SqlSingleParameters::new();
+    where_parameters.insert(st!(SheetMetaData::COL_PK), pk);
+
+    let (sql, parameters) = build_update_clause(SheetMetaData::TAB, &sing, Some(&where_parameters))?;
+
+    Ok((
+        sql,
+        Some(parameters),
+        None
+    ))
+}
+
+pub fn sheet_table_add_column(
+    table_name: String,
+    column: GenericColumn,
+) -> impl Fn(Option<SqlMultipleParameters>, Option<SqlSingleParameters>, &ChainContext) -> ChainReturn + Send + Sync {
+    move |mult, sing, glob| {
+        let sql = build_add_column_clause(&table_name, &column)?;
+
+        Ok((
+            sql,
+            None,
+            None
+        ))
+    }
+}
+
+pub fn sheet_table_alter_column(
+    table_name: String,
+    column: GenericColumn,
+    check_conversion: bool,
+) -> impl Fn(Option<SqlMultipleParameters>, Option<SqlSingleParameters>, &ChainContext) -> ChainReturn + Send + Sync {
+    move |mult, sing, glob| {
+        let sql = build_alter_column_clause(&table_name, &column, check_conversion)?;
+
+        Ok((
+            sql,
+            None,
+            None
+        ))
+    }
+}
+
+pub fn table_drop(
+    table_name: String,
+) -> impl Fn(Option<SqlMultipleParameters>, Option<SqlSingleParameters>, &ChainContext) -> ChainReturn + Send + Sync {
+    move |mult, sing, glob| {
+        let sql = build_drop_table_clause(&table_name)?;
+
+        Ok((
+            sql,
+            None,
+            None
+        ))
+    }
+}
+
+pub fn custom_script_run(
+    script: String,
+    label: String,
+    staged_table: Option<String>,
+) -> impl Fn(Option<SqlMultipleParameters>, Option<SqlSingleParameters>, &ChainContext) -> ChainReturn + Send + Sync {
+    move |mult, sing, glob| {
+        let sql = build_custom_script_clause(&script, &label, staged_table.as_deref())?;
+
+        Ok((
+            sql,
+            None,
+            None
+        ))
+    }
+}
+
+pub fn worker_insert(
+    mult: Option<SqlMultipleParameters>,
+    sing: Option<SqlSingleParameters>, 
+    glob: &ChainContext
+) -> ChainReturn {
+    try_unwrap_in_place!(mult);
+    let sql = build_insert_clause(Worker::TAB, &mult)?;
+
+    Ok((
+        sql,
+        Some(mult.to_single()),
+        Some(st!(Worker::COL_PK))
+    ))
+}
+
+/// First profile of the worker inserted before it
+pub fn worker_profile_insert(
+    mult: Option<SqlMultipleParameters>,
+    sing: Option<SqlSingleParameters>, 
+    glob: &ChainContext
+) -> ChainReturn {
+    try_unwrap_in_place!(mult);
+    let worker_id = try_get_glob!(glob, Worker::COL_PK);
+    mult.add_const_column(worker_id, Profile::COL_WORKER_FK);
+    let sql = build_insert_clause(Profile::TAB, &mult)?;
+
+    Ok((
+        sql,
+        Some(mult.to_single()),
+        None
+    ))
+}
+
+pub fn group_insert(
+    mult: Option<SqlMultipleParameters>,
+    sing: Option<SqlSingleParameters>, 
+    glob: &ChainContext
+) -> ChainReturn {
+    try_unwrap_in_place!(mult);
+    let sql = build_insert_clause(Group::TAB, &mult)?;
+
+    Ok((
+        sql,
+        Some(mult.to_single()),
+        Some(st!(Group::COL_PK))
+    ))
+}
+
+pub fn manager_permission_insert(
+    mult: Option<SqlMultipleParameters>,
+    sing: Option<SqlSingleParameters>, 
+    glob: &ChainContext
+) -> ChainReturn {
+    try_unwrap_in_place!(mult);
+    let group_id = try_get_glob!(glob, Group::COL_PK);
+    mult.add_const_column(group_id, ManagerPermission::COL_GROUP_FK);
+    let sql = build_insert_clause(ManagerPermission::TAB, &mult)?;
+
+    Ok((
+        sql,
+        Some(mult.to_single()),
+        None
+    ))
+}
+
+/// Insert of rows that need nothing from the steps before them
+pub fn table_insert(
+    table_name: &'static str,
+) -> impl Fn(Option<SqlMultipleParameters>, Option<SqlSingleParameters>, &ChainContext) -> ChainReturn + Send + Sync {
+    move |mult, sing, glob| {
+        try_unwrap_in_place!(mult);
+        let sql = build_insert_clause(table_name, &mult)?;
+
+        Ok((
+            sql,
+            Some(mult.to_single()),
+            None
+        ))
+    }
+}
+
+/// `sing` holds the new values of the rows matching `filter`
+pub fn table_update(
+    table_name: &'static str,
+    filter: Predicate,
+) -> impl Fn(Option<SqlMultipleParameters>, Option<SqlSingleParameters>, &ChainContext) -> ChainReturn + Send + Sync {
+    move |mult, sing, glob| {
+        try_unwrap_in_place!(sing);
+        let (sql, parameters) = build_update_clause(table_name, &sing, &filter)?;
+
+        Ok((
+            sql,
+            Some(parameters),
+            None
+        ))
+    }
+}
+
+pub fn table_delete(
+    table_name: &'static str,
+    filter: Predicate,
+) -> impl Fn(Option<SqlMultipleParameters>, Option<SqlSingleParameters>, &ChainContext) -> ChainReturn + Send + Sync {
+    move |mult, sing, glob| {
+        let sql = build_delete_clause(table_name, &filter)?;
+
+        Ok((
+            sql,
+            Some(build_filter_parameters(&filter)?),
+            None
+        ))
+    }
+}
@@ -1,26 +1,89 @@
-use file_uploader::api;
+use file_uploader::api::{self, AppState};
 
 use axum::{
+    extract::DefaultBodyLimit,
     routing::{ get, post, put, delete, patch },
     Router,
 };
 
-pub fn app() -> Router {
+// Spreadsheets easily go over axum's default 2MB body limit
+const UPLOAD_BODY_LIMIT: usize = 50 * 1024 * 1024;
+
+pub fn app(state: AppState) -> Router {
 
     let app = root_scream()
-        .merge(sheet_routes());
+        .merge(auth_routes())
+        .merge(sheet_routes())
+        .merge(history_routes())
+        .merge(me_routes())
+        .merge(user_routes());
 
-    app
+    app.with_state(state)
 }
 
-fn root_scream() -> Router {
+fn root_scream() -> Router<AppState> {
     Router::new()
         .route("/", get(api::root::api_scream))
+        .route("/health", get(api::root::health))
+}
+
+fn auth_routes() -> Router<AppState> {
+    let path = "/auth";
+
+    Router::new()
+        .route(&format!("{path}/login"), post(api::auth::login))
+        .route(&format!("{path}/profiles"), get(api::auth::list_profiles))
+        .route(&format!("{path}/profiles/{{pk}}/switch"), post(api::auth::switch_profile))
+        .route(&format!("{path}/impersonate/{{pk}}"), post(api::auth::impersonate))
+}
+
+fn me_routes() -> Router<AppState> {
+    let path = "/me";
+
+    Router::new()
+        .route(&format!("{path}/permissions"), get(api::permission::my_permissions))
+}
+
+fn user_routes() -> Router<AppState> {
+    Router::new()
+        .route("/worker", post(api::users::add_worker))
+        .route("/worker/{pk}", put(api::users::edit_worker))
+        .route("/profile", post(api::users::add_profile))
+        .route("/profile/{pk}", delete(api::users::remove_profile))
+        .route("/profile/{pk}/groups", put(api::users::edit_profile_groups))
+        .route("/group", post(api::users::add_group))
+        .route("/group/{pk}", delete(api::users::remove_group))
+        .route("/group/{pk}/permissions", put(api::users::edit_group_permissions))
 }
 
-fn sheet_routes() -> Router {
+fn sheet_routes() -> Router<AppState> {
     let path = "/sheet";
 
     Router::new()
+        .route(path, get(api::sheet::list_sheets))
         .route(&format!("{path}/add"), post(api::sheet::add_sheet))
+        .route(&format!("{path}/{{pk}}/columns"), put(api::sheet::update_columns))
+        .route(&format!("{path}/{{pk}}/template"), get(api::sheet::sheet_template))
+        .route(&format!("{path}/{{pk}}/model"), put(api::sheet::store_model))
+        .route(
+            &format!("{path}/worksheets"),
+            post(api::upload::list_worksheets).layer(DefaultBodyLimit::max(UPLOAD_BODY_LIMIT))
+        )
+        .route(
+            &format!("{path}/{{pk}}/upload"),
+            post(api::upload::upload_sheet).layer(DefaultBodyLimit::max(UPLOAD_BODY_LIMIT))
+        )
+        .route(&format!("{path}/{{pk}}/export"), get(api::sheet::export_sheet))
+        .route(&format!("{path}/{{pk}}/uploads"), get(api::upload::list_uploads))
+        .route(&format!("{path}/{{pk}}/uploads/export"), get(api::upload::export_uploads))
+}
+
+fn history_routes() -> Router<AppState> {
+    let path = "/history";
+
+    Router::new()
+        .route(&format!("{path}/sheet/{{pk}}"), get(api::history::sheet_history))
+        .route(&format!("{path}/sheet/{{pk}}/columns"), get(api::history::sheet_columns_history))
+        .route(&format!("{path}/sheet/{{pk}}/permissions"), get(api::history::sheet_permissions_history))
+        .route(&format!("{path}/group/{{pk}}"), get(api::history::group_history))
 }
\ No newline at end of file
@@ -0,0 +1,385 @@
+use std::env;
+
+use jsonwebtoken::{Algorithm, DecodingKey, EncodingKey, Header, Validation};
+use serde::{Deserialize, Serialize};
+
+use crate::ddb::context::{ db_types, functions };
+use crate::ddb::context::pool::Pool;
+use crate::ddb::tables::{ Board, Profile, Worker };
+use crate::model;
+
+use db_types::{ OrderBy, Predicate };
+
+use super::policy::{ self, Manager };
+
+const ALGORITHM: Algorithm = Algorithm::HS256;
+const MIN_SECRET_LEN: usize = 32;
+const DEFAULT_EXPIRATION_MINUTES: i64 = 8 * 60;
+
+/// Signs and checks the tokens, built once from the env
+pub struct TokenKeys {
+    encoding: EncodingKey,
+    decoding: DecodingKey,
+    /// Checks the login assertions of the company sign in
+    sso: DecodingKey,
+    lifetime: chrono::Duration,
+}
+
+impl TokenKeys {
+    pub fn new(secret: &[u8], sso_secret: &[u8], lifetime: chrono::Duration) -> Self {
+        Self {
+            encoding: EncodingKey::from_secret(secret),
+            decoding: DecodingKey::from_secret(secret),
+            sso: DecodingKey::from_secret(sso_secret),
+            lifetime,
+        }
+    }
+
+    pub fn from_env() -> anyhow::Result<Self> {
+        Self::from_lookup(|name| env::var(name).ok())
+    }
+
+    fn from_lookup(lookup: impl Fn(&str) -> Option<String>) -> anyhow::Result<Self> {
+        let secret = lookup("JWT_SECRET").ok_or_else(|| anyhow::anyhow!("'JWT_SECRET' not valid on env"))?;
+        anyhow::ensure!(secret.len() >= MIN_SECRET_LEN, "'JWT_SECRET' must have at least {MIN_SECRET_LEN} characters");
+
+        let sso_secret = lookup("SSO_SECRET").ok_or_else(|| anyhow::anyhow!("'SSO_SECRET' not valid on env"))?;
+        anyhow::ensure!(sso_secret.len() >= MIN_SECRET_LEN, "'SSO_SECRET' must have at least {MIN_SECRET_LEN} characters");
+        anyhow::ensure!(sso_secret != secret, "'SSO_SECRET' must differ from 'JWT_SECRET'");
+
+        let minutes = match lookup("JWT_EXPIRATION_MINUTES") {
+            Some(value) => value.trim()
+                .parse::<i64>()
+                .map_err(|_| anyhow::anyhow!("'JWT_EXPIRATION_MINUTES' has an invalid value '{value}'"))?,
+            None => DEFAULT_EXPIRATION_MINUTES,
+        };
+        anyhow::ensure!(minutes > 0, "'JWT_EXPIRATION_MINUTES' must be greater than 0");
+
+        Ok(Self::new(secret.as_bytes(), sso_secret.as_bytes(), chrono::Duration::minutes(minutes)))
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct Claims {
+    /// Linde ID of the worker
+    pub sub: String,
+    pub worker: i32,
+    pub profile: i32,
+    /// Profile of the manager or super user acting as `profile`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub impersonator: Option<i32>,
+    pub iat: i64,
+    pub exp: i64,
+}
+
+/// Login assertion of the company sign in
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SsoClaims {
+    /// Linde ID of the signed in worker
+    pub sub: String,
+    pub exp: i64,
+}
+
+/// Linde ID of a login assertion. Fails unless the company sign in signed it and it has not expired.
+pub fn verify_assertion(keys: &TokenKeys, assertion: &str) -> anyhow::Result<String> {
+    let mut validation = Validation::new(ALGORITHM);
+    validation.leeway = 0;
+    validation.set_required_spec_claims(&["exp", "sub"]);
+
+    let claims = jsonwebtoken::decode::<SsoClaims>(assertion, &keys.sso, &validation)?.claims;
+    anyhow::ensure!(!claims.sub.trim().is_empty(), "The assertion has no Linde ID");
+
+    Ok(claims.sub)
+}
+
+/// Workers have no credentials in this database, the Linde ID is only taken from an assertion signed by the company sign in.
+/// `None` when the assertion is refused, the worker is unknown or can not act as the requested profile.
+pub async fn login(pool: &Pool, keys: &TokenKeys, request: &model::LoginRequest) -> anyhow::Result<Option<model::TokenResponse>> {
+    let linde_id = match verify_assertion(keys, &request.assertion) {
+        Ok(linde_id) => linde_id,
+        Err(e) => {
+            log::debug!("Login assertion refused: {e}");
+            return Ok(None);
+        }
+    };
+
+    let Some(worker) = functions::find_one::<Worker>(pool, &Predicate::eq(Worker::COL_LINDE_ID, linde_id)).await? else {
+        return Ok(None);
+    };
+
+    let profile = match request.profile_pk {
+        Some(profile_pk) => active_profile(pool, profile_pk, worker.pk()).await?,
+        None => first_active_profile(pool, worker.pk()).await?,
+    };
+
+    let Some(profile) = profile else {
+        return Ok(None);
+    };
+
+    issue_token(keys, &worker, &profile, None).map(Some)
+}
+
+/// Profiles are not additive, the new token only carries the chosen one.
+/// `None` when the profile is inactive or belongs to another worker.
+pub async fn switch_profile(pool: &Pool, keys: &TokenKeys, worker_pk: i32, profile_pk: i32) -> anyhow::Result<Option<model::TokenResponse>> {
+    let Some(profile) = active_profile(pool, profile_pk, worker_pk).await? else {
+        return Ok(None);
+    };
+
+    let Some(worker) = functions::find_by_pk::<Worker>(pool, worker_pk).await? else {
+        return Ok(None);
+    };
+
+    issue_token(keys, &worker, &profile, None).map(Some)
+}
+
+/// Token acting as another profile, that keeps the manager as the real actor.
+/// `None` when the profile does not exist or is inactive.
+pub async fn impersonate(pool: &Pool, keys: &TokenKeys, manager: &Manager, profile_pk: i32) -> anyhow::Result<Option<model::TokenResponse>> {
+    let Some(profile) = functions::find_one::<Profile>(pool, &Predicate::and(vec![
+        Predicate::eq(Profile::COL_PK, profile_pk),
+        Predicate::eq(Profile::COL_ACTIVE, true),
+    ])).await? else {
+        return Ok(None);
+    };
+
+    manager.check_impersonation(&policy::load_profile_manager(pool, &profile).await?)?;
+
+    let Some(worker) = functions::find_by_pk::<Worker>(pool, profile.worker_fk()).await? else {
+        return Ok(None);
+    };
+
+    issue_token(keys, &worker, &profile, Some(manager.profile_pk())).map(Some)
+}
+
+/// Active profiles of the worker, with the name of their board
+pub async fn worker_profiles(pool: &Pool, worker_pk: i32, current_pk: i32) -> anyhow::Result<Vec<model::WorkerProfile>> {
+    let profiles = functions::select_from::<Profile>(
+        pool,
+        &Predicate::and(vec![
+            Predicate::eq(Profile::COL_WORKER_FK, worker_pk),
+            Predicate::eq(Profile::COL_ACTIVE, true),
+        ]),
+        None,
+        None,
+        &[OrderBy::asc(Profile::COL_PK)],
+        None,
+    ).await?;
+
+    let mut board_pks = profiles.iter().filter_map(Profile::board_fk).collect::<Vec<_>>();
+    board_pks.sort_unstable();
+    board_pks.dedup();
+
+    let boards = match board_pks.is_empty() {
+        true => Vec::new(),
+        false => functions::select_from::<Board>(pool, &Predicate::is_in(Board::COL_PK, board_pks), None, None, &[], None).await?,
+    };
+
+    Ok(to_worker_profiles(profiles, &boards, current_pk))
+}
+
+fn to_worker_profiles(profiles: Vec<Profile>, boards: &[Board], current_pk: i32) -> Vec<model::WorkerProfile> {
+    profiles.into_iter()
+        .map(|profile| model::WorkerProfile {
+            pk: profile.pk(),
+            board_fk: profile.board_fk(),
+            board_name: profile.board_fk()
+                .and_then(|board_pk| boards.iter().find(|board| board.pk() == board_pk))
+                .map(|board| board.name().to_string()),
+            is_super_user: profile.is_super_user(),
+            current: profile.pk() == current_pk,
+        })
+        .collect()
+}
+
+pub fn issue_token(
+    keys: &TokenKeys,
+    worker: &Worker,
+    profile: &Profile,
+    impersonator: Option<i32>,
+) -> anyhow::Result<model::TokenResponse> {
+    let now = chrono::Utc::now();
+    let claims = Claims {
+        sub: worker.linde_id().to_string(),
+        worker: worker.pk(),
+        profile: profile.pk(),
+        impersonator,
+        iat: now.timestamp(),
+        exp: (now + keys.lifetime).timestamp(),
+    };
+
+    let token = jsonwebtoken::encode(&Header::new(ALGORITHM), &claims, &keys.encoding)?;
+
+    Ok(model::TokenResponse { token, expires_at: claims.exp, profile_pk: profile.pk(), impersonator_pk: impersonator })
+}
+
+/// Fails for a token that is expired, malformed or signed with another secret
+pub fn decode_token(keys: &TokenKeys, token: &str) -> anyhow::Result<Claims> {
+    let mut validation = Validation::new(ALGORITHM);
+    validation.leeway = 0;
+
+    Ok(jsonwebtoken::decode::<Claims>(token, &keys.decoding, &validation)?.claims)
+}
+
+/// Profile of a valid token. It is read again on every request so a deactivated profile stops working at once.
+/// Impersonation is checked again too, it ends as soon as the impersonator could no longer start it.
+pub async fn token_profile(pool: &Pool, claims: &Claims) -> anyhow::Result<Option<Profile>> {
+    let Some(profile) = active_profile(pool, claims.profile, claims.worker).await? else {
+        return Ok(None);
+    };
+
+    let Some(impersonator_pk) = claims.impersonator else {
+        return Ok(Some(profile));
+    };
+
+    let Some(impersonator) = functions::find_one::<Profile>(pool, &Predicate::and(vec![
+        Predicate::eq(Profile::COL_PK, impersonator_pk),
+        Predicate::eq(Profile::COL_ACTIVE, true),
+    ])).await? else {
+        return Ok(None);
+    };
+
+    let manager = policy::load_profile_manager(pool, &impersonator).await?;
+    if let Err(refused) = manager.check_impersonation(&policy::load_profile_manager(pool, &profile).await?) {
+        log::debug!("Impersonation of profile {} by {impersonator_pk} ended: {refused}", profile.pk());
+        return Ok(None);
+    }
+
+    Ok(Some(profile))
+}
+
+async fn active_profile(pool: &Pool, profile_pk: i32, worker_pk: i32) -> anyhow::Result<Option<Profile>> {
+    functions::find_one::<Profile>(pool, &Predicate::and(vec![
+        Predicate::eq(Profile::COL_PK, profile_pk),
+        Predicate::eq(Profile::COL_WORKER_FK, worker_pk),
+        Predicate::eq(Profile::COL_ACTIVE, true),
+    ])).await
+}
+
+async fn first_active_profile(pool: &Pool, worker_pk: i32) -> anyhow::Result<Option<Profile>> {
+    let profiles = functions::select_from::<Profile>(
+        pool,
+        &Predicate::and(vec![
+            Predicate::eq(Profile::COL_WORKER_FK, worker_pk),
+            Predicate::eq(Profile::COL_ACTIVE, true),
+        ]),
+        None,
+        Some(1),
+        &[OrderBy::asc(Profile::COL_PK)],
+        None,
+    ).await?;
+
+    Ok(profiles.into_iter().next())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::st;
+
+    const SECRET: &[u8] = b"0123456789abcdef0123456789abcdef";
+    const SSO_SECRET: &[u8] = b"fedcba9876543210fedcba9876543210";
+
+    fn keys(lifetime: chrono::Duration) -> TokenKeys {
+        TokenKeys::new(SECRET, SSO_SECRET, lifetime)
+    }
+
+    fn assertion(secret: &[u8], linde_id: &str, minutes: i64) -> String {
+        let claims = SsoClaims { sub: linde_id.to_string(), exp: (chrono::Utc::now() + chrono::Duration::minutes(minutes)).timestamp() };
+        jsonwebtoken::encode(&Header::new(ALGORITHM), &claims, &EncodingKey::from_secret(secret)).unwrap()
+    }
+
+    fn profile(pk: i32, board: Option<i32>, is_super_user: bool) -> Profile {
+        Profile::default().with_pk(pk).with_active(true).with_board_fk(board).with_worker_fk(3).with_is_super_user(is_super_user)
+    }
+
+    fn worker_and_profile() -> (Worker, Profile) {
+        (
+            Worker::default().with_pk(3).with_name(st!("Ana")).with_linde_id(st!("LI0003")).with_email(st!("ana@linde.com")),
+            profile(12, Some(2), false),
+        )
+    }
+
+    #[test]
+    fn check_token_round_trip() {
+        let keys = keys(chrono::Duration::minutes(5));
+        let (worker, profile) = worker_and_profile();
+
+        let response = issue_token(&keys, &worker, &profile, None).unwrap();
+        let claims = decode_token(&keys, &response.token).unwrap();
+
+        assert_eq!((claims.sub.as_str(), claims.worker, claims.profile), ("LI0003", 3, 12));
+        assert_eq!(claims.exp, response.expires_at);
+        assert_eq!((response.profile_pk, claims.impersonator), (12, None));
+
+        let response = issue_token(&keys, &worker, &profile, Some(1)).unwrap();
+        assert_eq!(decode_token(&keys, &response.token).unwrap().impersonator, Some(1));
+        assert_eq!(response.impersonator_pk, Some(1));
+    }
+
+    #[test]
+    fn check_refused_tokens() {
+        let (worker, profile) = worker_and_profile();
+
+        let expired = keys(chrono::Duration::minutes(-1));
+        let token = issue_token(&expired, &worker, &profile, None).unwrap().token;
+        assert!(decode_token(&expired, &token).is_err());
+
+        let keys = keys(chrono::Duration::minutes(5));
+        let other = TokenKeys::new(b"another secret, also 32 characters", SSO_SECRET, chrono::Duration::minutes(5));
+        let token = issue_token(&other, &worker, &profile, None).unwrap().token;
+        assert!(decode_token(&keys, &token).is_err());
+
+        assert!(decode_token(&keys, "not.a.token").is_err());
+    }
+
+    #[test]
+    fn check_login_needs_a_signed_assertion() {
+        let keys = keys(chrono::Duration::minutes(5));
+
+        assert_eq!(verify_assertion(&keys, &assertion(SSO_SECRET, "LI0003", 5)).unwrap(), "LI0003");
+
+        assert!(verify_assertion(&keys, "LI0003").is_err());
+        assert!(serde_json::from_str::<model::LoginRequest>(r#"{"linde_id": "LI0003"}"#).is_err());
+
+        assert!(verify_assertion(&keys, &assertion(SSO_SECRET, "LI0003", -1)).is_err());
+        assert!(verify_assertion(&keys, &assertion(SSO_SECRET, " ", 5)).is_err());
+        // Our own tokens are not assertions
+        assert!(verify_assertion(&keys, &assertion(SECRET, "LI0003", 5)).is_err());
+    }
+
+    #[test]
+    fn check_worker_profiles() {
+        let profiles = vec![
+            profile(12, Some(2), false),
+            profile(15, None, true),
+        ];
+        let boards = vec![Board::default().with_pk(2).with_name(st!("Logistics")).with_active(true)];
+
+        let listed = to_worker_profiles(profiles, &boards, 15);
+        assert_eq!(listed[0].board_name.as_deref(), Some("Logistics"));
+        assert_eq!((listed[1].board_name.as_deref(), listed[1].is_super_user), (None, true));
+        assert_eq!(listed.iter().filter(|profile| profile.current).map(|profile| profile.pk).collect::<Vec<_>>(), vec![15]);
+    }
+
+    #[test]
+    fn check_keys_from_lookup() {
+        let vars = HashMap::from([("JWT_SECRET", "too short")]);
+        assert!(TokenKeys::from_lookup(|name| vars.get(name).map(|v| v.to_string())).is_err());
+
+        let secret = std::str::from_utf8(SECRET).unwrap();
+        let vars = HashMap::from([("JWT_SECRET", secret), ("SSO_SECRET", std::str::from_utf8(SSO_SECRET).unwrap()), ("JWT_EXPIRATION_MINUTES", "30")]);
+        let keys = TokenKeys::from_lookup(|name| vars.get(name).map(|v| v.to_string())).unwrap();
+        assert_eq!(keys.lifetime, chrono::Duration::minutes(30));
+
+        let vars = HashMap::from([("JWT_SECRET", secret)]);
+        assert!(TokenKeys::from_lookup(|name| vars.get(name).map(|v| v.to_string())).is_err());
+
+        let vars = HashMap::from([("JWT_SECRET", secret), ("SSO_SECRET", secret)]);
+        assert!(TokenKeys::from_lookup(|name| vars.get(name).map(|v| v.to_string())).is_err());
+
+        assert!(TokenKeys::from_lookup(|_| None).is_err());
+    }
+}
@@ -0,0 +1,148 @@
+use chrono::{ NaiveDate, NaiveDateTime, NaiveTime };
+use futures::{ stream, StreamExt };
+use tiberius::{ ColumnData, FromSql };
+
+use crate::ddb::context::{ db_types, functions };
+use crate::ddb::context::pool::Pool;
+use crate::ddb::DBLoad;
+use crate::ddb::tables::{ Sheet, Upload };
+use crate::st;
+
+use db_types::{ OrderBy, ToSqlValue };
+use functions::RowStream;
+
+/// Same formats `SqlValue::from_raw` reads first, so an export can be uploaded back as is
+const DATE_FORMAT: &str = "%Y-%m-%d";
+const TIME_FORMAT: &str = "%H:%M:%S";
+const DATE_TIME_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+/// Data table of the sheet as CSV, written row by row while it is read.
+/// Returns the file name and its chunks, or `None` if there is no active sheet with the given pk.
+pub async fn export_sheet(pool: &Pool, sheet_pk: i32) -> anyhow::Result<Option<(String, RowStream<Vec<u8>>)>> {
+    let Some((sheet, _, table)) = super::get_sheet_table(pool, sheet_pk).await? else {
+        return Ok(None);
+    };
+
+    let names = table.columns().iter().map(|column| column.name()).collect::<Vec<_>>();
+    let columns = names.iter().map(|name| format!("[{name}]")).collect::<Vec<_>>();
+
+    // BOM so Excel opens the file as UTF-8
+    let mut header = "\u{feff}".as_bytes().to_vec();
+    header.extend(csv_line(&names)?);
+
+    let rows = functions::stream_generic(
+        pool,
+        table.name(),
+        None,
+        Some(columns.iter().map(String::as_str).collect()),
+        &[],
+    ).await?;
+
+    let lines = rows.map(|row| {
+        let cells = row?.cells()
+            .map(|(_, data)| to_raw(data))
+            .collect::<anyhow::Result<Vec<_>>>()?;
+
+        csv_line(&cells)
+    });
+
+    let file: RowStream<Vec<u8>> = Box::pin(stream::once(async { Ok(header) }).chain(lines));
+
+    Ok(Some((format!("{}.csv", sheet.table_name()), file)))
+}
+
+/// `UPLOAD` rows of the sheet as JSON lines, most recent first, without the uploaded files
+pub async fn export_uploads(pool: &Pool, sheet_pk: i32) -> anyhow::Result<RowStream<Vec<u8>>> {
+    let mut where_parameters = db_types::SqlSingleParameters::new();
+    where_parameters.insert(st!(Upload::COL_SHEET_FK), sheet_pk.to_sql_value());
+
+    let columns = Upload::COLS.iter()
+        .map(|col| match *col == Upload::COL_FILE_UPLOADED {
+            true => format!("0x AS [{col}]"),
+            false => format!("[{col}]"),
+        })
+        .collect::<Vec<_>>();
+
+    let uploads = functions::stream_from::<Upload>(
+        pool,
+        Some(&where_parameters),
+        Some(columns.iter().map(String::as_str).collect()),
+        &[OrderBy::desc(Upload::COL_UPLOADED_AT), OrderBy::desc(Upload::COL_PK)],
+    ).await?;
+
+    Ok(Box::pin(uploads.map(|upload| {
+        let mut line = serde_json::to_vec(&upload?)?;
+        line.push(b'\n');
+
+        Ok(line)
+    })))
+}
+
+fn csv_line<T: AsRef<[u8]>>(cells: &[T]) -> anyhow::Result<Vec<u8>> {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    writer.write_record(cells)?;
+
+    Ok(writer.into_inner()?)
+}
+
+/// Cell as it is written in a file, empty for NULL
+fn to_raw(data: &ColumnData<'static>) -> anyhow::Result<String> {
+    fn text<T: ToString>(value: Option<T>) -> String {
+        value.map(|value| value.to_string()).unwrap_or_default()
+    }
+
+    let raw = match data {
+        ColumnData::U8(v) => text(*v),
+        ColumnData::I16(v) => text(*v),
+        ColumnData::I32(v) => text(*v),
+        ColumnData::I64(v) => text(*v),
+        ColumnData::F32(v) => text(*v),
+        ColumnData::F64(v) => text(*v),
+        ColumnData::Numeric(v) => text(v.map(db_types::numeric_to_decimal)),
+        ColumnData::Bit(v) => text(v.map(u8::from)),
+        ColumnData::String(v) => text(v.as_deref()),
+        ColumnData::Guid(v) => text(*v),
+        ColumnData::Xml(v) => text(v.as_deref()),
+        ColumnData::Binary(v) => text(v.as_deref().map(|bytes| format!("0x{}", hex::encode(bytes)))),
+
+        ColumnData::Date(_) => text(NaiveDate::from_sql(data)?.map(|v| v.format(DATE_FORMAT))),
+        ColumnData::Time(_) => text(NaiveTime::from_sql(data)?.map(|v| v.format(TIME_FORMAT))),
+        ColumnData::DateTime(_) | ColumnData::SmallDateTime(_) | ColumnData::DateTime2(_) => {
+            text(NaiveDateTime::from_sql(data)?.map(|v| v.format(DATE_TIME_FORMAT)))
+        }
+        // Same UTC values the bulk insert wrote
+        ColumnData::DateTimeOffset(_) => {
+            text(chrono::DateTime::<chrono::Utc>::from_sql(data)?.map(|v| v.naive_utc().format(DATE_TIME_FORMAT)))
+        }
+    };
+
+    Ok(raw)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use db_types::SqlValue;
+    use tiberius::IntoSql;
+
+    #[test]
+    fn check_to_raw_reads_back() {
+        let date_time = NaiveDate::from_ymd_opt(2025, 12, 21).unwrap().and_hms_opt(8, 30, 5).unwrap();
+
+        let raw = to_raw(&date_time.into_sql()).unwrap();
+        assert_eq!(raw, "2025-12-21 08:30:05");
+        assert!(matches!(SqlValue::from_raw(&raw, tiberius::ColumnType::Datetime2).unwrap(), SqlValue::DateTime(v) if v == date_time));
+
+        let numeric = SqlValue::Decimal(st!("-12.5")).to_column_data(tiberius::ColumnType::Decimaln).unwrap();
+        assert_eq!(to_raw(&numeric).unwrap(), "-12.5");
+
+        assert_eq!(to_raw(&ColumnData::Bit(Some(true))).unwrap(), "1");
+        assert_eq!(to_raw(&ColumnData::I32(None)).unwrap(), "");
+        assert_eq!(to_raw(&ColumnData::Binary(Some(vec![0xab, 0x01].into()))).unwrap(), "0xab01");
+    }
+
+    #[test]
+    fn check_csv_line() {
+        assert_eq!(csv_line(&["a", "b,c", ""]).unwrap(), b"a,\"b,c\",\n");
+    }
+}
@@ -0,0 +1,179 @@
+use crate::ddb::context::{ db_types, functions };
+use crate::ddb::context::pool::Pool;
+use crate::ddb::tables::{ Group, HistGroup, HistSheet, HistSheetMetaData, HistUploaderPermission, Sheet, Upload };
+use crate::ddb::DBLoad;
+use crate::model;
+use crate::st;
+
+use super::policy::Manager;
+
+use db_types::{ OrderBy, Page, ToSqlValue };
+
+/// One page of `T`, with the total count of the rows matching `where_parameters`.
+/// `key` columns are always appended to the order so rows never move between pages.
+/// `files` are binary columns read as empty, they are never sent in listings.
+async fn list<T>(
+    pool: &Pool,
+    where_parameters: Option<&db_types::SqlSingleParameters>,
+    query: &model::ListQuery,
+    default_order: &[OrderBy],
+    key: &[&str],
+    files: &[&str],
+) -> anyhow::Result<model::Paged<T>>
+where
+    T: DBLoad,
+{
+    let order_by = resolve_order(T::COLS, query, default_order, key)?;
+    let page = Page::new(query.page, query.size)
+        .map_err(|e| model::InvalidListQuery { message: e.to_string() })?;
+
+    let columns = (!files.is_empty()).then(|| {
+        T::COLS.iter()
+            .map(|col| match files.contains(col) {
+                true => format!("0x AS [{col}]"),
+                false => format!("[{col}]"),
+            })
+            .collect::<Vec<_>>()
+    });
+
+    let total = functions::count_from::<T>(pool, where_parameters).await?;
+    let items = functions::select_from::<T>(
+        pool,
+        where_parameters,
+        columns.as_ref().map(|columns| columns.iter().map(String::as_str).collect()),
+        None,
+        &order_by,
+        Some(page),
+    ).await?;
+
+    Ok(model::Paged { items, total, page: page.number(), size: page.size() })
+}
+
+fn resolve_order(
+    columns: &[&str],
+    query: &model::ListQuery,
+    default_order: &[OrderBy],
+    key: &[&str],
+) -> anyhow::Result<Vec<OrderBy>> {
+    let mut order_by = match &query.order_by {
+        Some(column) => {
+            let Some(column) = columns.iter().find(|c| c.eq_ignore_ascii_case(column)) else {
+                return Err(model::InvalidListQuery { message: format!("Can not order by '{column}'") }.into());
+            };
+
+            let direction = if query.desc { db_types::OrderDirection::Desc } else { db_types::OrderDirection::Asc };
+            vec![OrderBy::new(column, direction)]
+        }
+        None => default_order.to_vec(),
+    };
+
+    for column in key {
+        if !order_by.iter().any(|order| order.column() == *column) {
+            order_by.push(OrderBy::asc(column));
+        }
+    }
+
+    Ok(order_by)
+}
+
+fn where_fk(column: &str, pk: i32) -> db_types::SqlSingleParameters {
+    let mut where_parameters = db_types::SqlSingleParameters::new();
+    where_parameters.insert(st!(column), pk.to_sql_value());
+
+    where_parameters
+}
+
+pub async fn list_sheets(pool: &Pool, query: &model::ListQuery) -> anyhow::Result<model::Paged<Sheet>> {
+    list::<Sheet>(
+        pool,
+        None,
+        query,
+        &[OrderBy::asc(Sheet::COL_DESCRIPTION)],
+        &[Sheet::COL_PK],
+        &[Sheet::COL_MODEL],
+    ).await
+}
+
+/// Most recent first
+pub async fn list_uploads(pool: &Pool, sheet_pk: i32, query: &model::ListQuery) -> anyhow::Result<model::Paged<Upload>> {
+    list::<Upload>(
+        pool,
+        Some(&where_fk(Upload::COL_SHEET_FK, sheet_pk)),
+        query,
+        &[OrderBy::desc(Upload::COL_UPLOADED_AT), OrderBy::desc(Upload::COL_PK)],
+        &[Upload::COL_PK],
+        &[Upload::COL_FILE_UPLOADED],
+    ).await
+}
+
+pub async fn sheet_history(pool: &Pool, sheet_pk: i32, query: &model::ListQuery) -> anyhow::Result<model::Paged<HistSheet>> {
+    list::<HistSheet>(
+        pool,
+        Some(&where_fk(HistSheet::COL_SHEET_FK, sheet_pk)),
+        query,
+        &[OrderBy::desc(HistSheet::COL_EDITED_AT), OrderBy::desc(HistSheet::COL_PK)],
+        &[HistSheet::COL_PK],
+        &[HistSheet::COL_MODEL],
+    ).await
+}
+
+pub async fn sheet_columns_history(pool: &Pool, sheet_pk: i32, query: &model::ListQuery) -> anyhow::Result<model::Paged<HistSheetMetaData>> {
+    list::<HistSheetMetaData>(
+        pool,
+        Some(&where_fk(HistSheetMetaData::COL_SHEET_FK, sheet_pk)),
+        query,
+        &[OrderBy::desc(HistSheetMetaData::COL_EDITED_AT), OrderBy::desc(HistSheetMetaData::COL_PK)],
+        &[HistSheetMetaData::COL_PK],
+        &[],
+    ).await
+}
+
+pub async fn sheet_permissions_history(pool: &Pool, sheet_pk: i32, query: &model::ListQuery) -> anyhow::Result<model::Paged<HistUploaderPermission>> {
+    list::<HistUploaderPermission>(
+        pool,
+        Some(&where_fk(HistUploaderPermission::COL_SHEET_FK, sheet_pk)),
+        query,
+        &[OrderBy::desc(HistUploaderPermission::COL_EDITED_AT), OrderBy::desc(HistUploaderPermission::COL_PK)],
+        &[HistUploaderPermission::COL_PK],
+        &[],
+    ).await
+}
+
+/// Managers only read the history of the groups of their board. `None` when the group does not exist.
+pub async fn group_history(pool: &Pool, manager: &Manager, group_pk: i32, query: &model::ListQuery) -> anyhow::Result<Option<model::Paged<HistGroup>>> {
+    let Some(group) = functions::find_by_pk::<Group>(pool, group_pk).await? else {
+        return Ok(None);
+    };
+
+    manager.check_board(Some(group.board_id()))?;
+
+    list::<HistGroup>(
+        pool,
+        Some(&where_fk(HistGroup::COL_GROUP_FK, group_pk)),
+        query,
+        &[OrderBy::desc(HistGroup::COL_EDITED_AT), OrderBy::desc(HistGroup::COL_PK)],
+        &[HistGroup::COL_PK],
+        &[],
+    ).await.map(Some)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_resolve_order() {
+        let default_order = [OrderBy::desc(Upload::COL_UPLOADED_AT), OrderBy::desc(Upload::COL_PK)];
+
+        let order = resolve_order(Upload::COLS, &model::ListQuery::default(), &default_order, &[Upload::COL_PK]).unwrap();
+        assert_eq!(order, default_order.to_vec());
+
+        let query = model::ListQuery { order_by: Some(st!("sheetused")), desc: true, ..Default::default() };
+        let order = resolve_order(Upload::COLS, &query, &default_order, &[Upload::COL_PK]).unwrap();
+        assert_eq!(order, vec![OrderBy::desc(Upload::COL_SHEET_USED), OrderBy::asc(Upload::COL_PK)]);
+
+        let query = model::ListQuery { order_by: Some(st!("pk]; DROP TABLE x --")), ..Default::default() };
+        let error = resolve_order(Upload::COLS, &query, &default_order, &[Upload::COL_PK]).unwrap_err();
+        assert!(error.downcast_ref::<model::InvalidListQuery>().is_some());
+    }
+}
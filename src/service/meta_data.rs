@@ -0,0 +1,335 @@
+use regex::Regex;
+
+use crate::ddb::context::{ db_types, functions };
+use crate::ddb::context::pool::Pool;
+use crate::ddb::tables::{ ColumnType, Sheet, SheetMetaData };
+use crate::ddb::ToSqlParameters;
+use crate::repository;
+use crate::model;
+use crate::st;
+
+use db_types::ToSqlValue;
+
+// Error number used by `build_alter_column_clause` when a value can not be converted
+const CONVERSION_ERROR_CODE: u32 = 50000;
+
+enum ColumnChange {
+    Add(model::NewSheetMetaDataRequest, db_types::GenericColumn),
+    Edit {
+        pk: i32,
+        column: model::NewSheetMetaDataRequest,
+        /// New definition of the data table column and if its type changed
+        alter: Option<(db_types::GenericColumn, bool)>,
+    },
+}
+
+/// Applies the requested columns to the sheet meta data and to its data table, in one transaction.
+/// `columns` must list every column of the sheet, refused changes are returned as `model::ColumnChangesRefused`.
+/// Returns `None` if there is no active sheet with the given pk.
+pub async fn update_sheet_meta_data(
+    pool: &Pool,
+    sheet_pk: i32,
+    columns: Vec<model::EditSheetMetaDataRequest>,
+    user_id: i32,
+) -> anyhow::Result<Option<()>> {
+    let Some((sheet, current, table)) = super::get_sheet_table(pool, sheet_pk).await? else {
+        return Ok(None);
+    };
+
+    let column_types = functions::select_from::<ColumnType>(pool, None, None, None, &[], None).await?;
+
+    let changes = plan_changes(&current, &table, columns, &column_types)
+        .map_err(|changes| model::ColumnChangesRefused { changes })?;
+
+    if changes.is_empty() {
+        return Ok(Some(()));
+    }
+
+    let mut meta_insert_param = db_types::SqlMultipleParameters::new();
+    let mut meta_updates = Vec::new();
+    let mut table_alters: Vec<db_types::ChainExecBox> = Vec::new();
+    let mut retyped_columns = Vec::new();
+
+    for change in changes {
+        match change {
+            ColumnChange::Add(column, generic_column) => {
+                meta_insert_param.add_row(&column)?;
+
+                table_alters.push(Box::new(repository::sheet_table_add_column(st!(table.name()), generic_column)));
+            }
+            ColumnChange::Edit { pk, column, alter } => {
+                let mut meta_update_param = column.to_update_params();
+                meta_update_param.insert(st!(SheetMetaData::COL_PK),                pk.to_sql_value());
+                meta_update_param.insert(st!(SheetMetaData::COL_LAST_EDITED_BY_FK), user_id.to_sql_value());
+                meta_updates.push(meta_update_param);
+
+                if let Some((generic_column, retyped)) = alter {
+                    if retyped {
+                        retyped_columns.push(column.name);
+                    }
+
+                    table_alters.push(Box::new(repository::sheet_table_alter_column(st!(table.name()), generic_column, retyped)));
+                }
+            }
+        }
+    }
+
+    let mut chain_map = db_types::ChainMap::new();
+
+    if meta_insert_param.len() > 0 {
+        meta_insert_param.add_const_column(user_id.to_sql_value(), SheetMetaData::COL_LAST_EDITED_BY_FK);
+        chain_map.push(&repository::sheet_meta_data_insert, Some(meta_insert_param), None);
+    }
+
+    for meta_update_param in meta_updates {
+        chain_map.push(&repository::sheet_meta_data_update, None, Some(meta_update_param));
+    }
+
+    for table_alter in &table_alters {
+        chain_map.push(table_alter.as_ref(), None, None);
+    }
+
+    let mut global_values = db_types::SqlSingleParameters::new();
+    global_values.insert(st!(Sheet::COL_PK), sheet.pk().to_sql_value());
+
+    if let Err(e) = functions::chain_executions(pool, chain_map, global_values).await {
+        return Err(conversion_refused(e, &retyped_columns));
+    }
+
+    Ok(Some(()))
+}
+
+// A failed type conversion is a refused change, not a server error
+fn conversion_refused(error: anyhow::Error, retyped_columns: &[String]) -> anyhow::Error {
+    let Some(tiberius::error::Error::Server(token)) = error.downcast_ref::<tiberius::error::Error>() else {
+        return error;
+    };
+
+    if token.code() != CONVERSION_ERROR_CODE {
+        return error;
+    }
+
+    let column = retyped_columns.iter()
+        .find(|name| token.message().contains(&format!("'{name}'")))
+        .cloned()
+        .unwrap_or_default();
+
+    model::ColumnChangesRefused {
+        changes: vec![model::RefusedColumnChange { column, reason: st!(token.message()) }],
+    }.into()
+}
+
+/// Compares the requested columns with the current ones.
+/// `current` must follow the order of `table.columns()`.
+fn plan_changes(
+    current: &[SheetMetaData],
+    table: &db_types::GenericTable,
+    requested: Vec<model::EditSheetMetaDataRequest>,
+    column_types: &[ColumnType],
+) -> Result<Vec<ColumnChange>, Vec<model::RefusedColumnChange>> {
+    let refuse = |column: &str, reason: &str| model::RefusedColumnChange { column: st!(column), reason: st!(reason) };
+
+    let mut refused = Vec::new();
+    let mut changes = Vec::new();
+
+    for existing in current {
+        if !requested.iter().any(|r| r.pk == Some(existing.pk())) {
+            refused.push(refuse(existing.column_name(), "Columns can not be removed from a sheet"));
+        }
+    }
+
+    for (idx, request) in requested.iter().enumerate() {
+        let repeated = requested[..idx].iter().any(|other| {
+            other.column.name.eq_ignore_ascii_case(&request.column.name)
+                || (request.pk.is_some() && other.pk == request.pk)
+        });
+
+        if repeated {
+            refused.push(refuse(&request.column.name, "Column is repeated"));
+        }
+    }
+
+    for request in requested {
+        let model::EditSheetMetaDataRequest { pk, column } = request;
+
+        if let Some(pattern) = &column.regex_constraint
+            && Regex::new(pattern).is_err()
+        {
+            refused.push(refuse(&column.name, "Regex constraint is not valid"));
+            continue;
+        }
+
+        let generic_column = match super::to_generic_column(&column.name, column.column_type_fk, column.optional, column_types) {
+            Ok(generic_column) => generic_column,
+            Err(e) => {
+                refused.push(refuse(&column.name, &e.to_string()));
+                continue;
+            }
+        };
+
+        let Some(pk) = pk else {
+            // The rows already in the data table would have no value for it
+            if !column.optional {
+                refused.push(refuse(&column.name, "A column added to a sheet must be optional"));
+                continue;
+            }
+
+            changes.push(ColumnChange::Add(column, generic_column));
+            continue;
+        };
+
+        let Some(idx) = current.iter().position(|c| c.pk() == pk) else {
+            refused.push(refuse(&column.name, "Column does not belong to this sheet"));
+            continue;
+        };
+
+        let existing = &current[idx];
+
+        if existing.column_name() != column.name {
+            refused.push(refuse(existing.column_name(), "Columns can not be renamed"));
+            continue;
+        }
+
+        if existing.optional() && !column.optional {
+            refused.push(refuse(&column.name, "An optional column can not become required"));
+            continue;
+        }
+
+        let retyped = table.columns()[idx].typing() != generic_column.typing();
+        let relaxed = !existing.optional() && column.optional;
+
+        let unchanged = !retyped
+            && !relaxed
+            && existing.column_type_fk() == column.column_type_fk
+            && existing.regex_constraint() == column.regex_constraint.as_deref()
+            && existing.description() == column.description;
+
+        if unchanged {
+            continue;
+        }
+
+        changes.push(ColumnChange::Edit {
+            pk,
+            column,
+            alter: (retyped || relaxed).then_some((generic_column, retyped)),
+        });
+    }
+
+    if refused.is_empty() { Ok(changes) } else { Err(refused) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn column_types() -> Vec<ColumnType> {
+        vec![
+            ColumnType::default().with_pk(1).with_sql_type(st!("INT")).with_view_type(st!("Inteiro")),
+            ColumnType::default().with_pk(2).with_sql_type(st!("FLOAT")).with_view_type(st!("Numeros flutuantes")),
+            ColumnType::default().with_pk(3).with_sql_type(st!("NVARCHAR(MAX)")).with_view_type(st!("Texto")),
+        ]
+    }
+
+    fn existing(pk: i32, name: &str, column_type_fk: i32, optional: bool) -> SheetMetaData {
+        SheetMetaData::default()
+            .with_pk(pk)
+            .with_sheet_fk(1)
+            .with_column_name(st!(name))
+            .with_column_type_fk(column_type_fk)
+            .with_optional(optional)
+            .with_description(st!(name))
+    }
+
+    fn current() -> (Vec<SheetMetaData>, db_types::GenericTable) {
+        let current = vec![
+            existing(10, "Code", 1, false),
+            existing(11, "Note", 3, true),
+        ];
+
+        let table = db_types::GenericTable::new(st!("DATA"), vec![
+            db_types::GenericColumn::new(st!("Code"), tiberius::ColumnType::Int4, false),
+            db_types::GenericColumn::new(st!("Note"), tiberius::ColumnType::NVarchar, true),
+        ]);
+
+        (current, table)
+    }
+
+    fn request(pk: Option<i32>, name: &str, column_type_fk: i32, optional: bool) -> model::EditSheetMetaDataRequest {
+        model::EditSheetMetaDataRequest {
+            pk,
+            column: model::NewSheetMetaDataRequest {
+                name: st!(name),
+                column_type_fk,
+                optional,
+                regex_constraint: None,
+                description: st!(name),
+            },
+        }
+    }
+
+    #[test]
+    fn check_plan_changes_accepted() {
+        let (current, table) = current();
+        let requested = vec![
+            request(Some(10), "Code", 2, true),
+            request(Some(11), "Note", 3, true),
+            request(None, "Amount", 2, true),
+        ];
+
+        let changes = plan_changes(&current, &table, requested, &column_types()).unwrap();
+
+        assert_eq!(changes.len(), 2);
+        assert!(matches!(
+            &changes[0],
+            ColumnChange::Edit { pk: 10, alter: Some((column, true)), .. } if column.optional()
+        ));
+        assert!(matches!(&changes[1], ColumnChange::Add(column, _) if column.name == "Amount"));
+    }
+
+    #[test]
+    fn check_plan_changes_relax_only() {
+        let (current, table) = current();
+        let requested = vec![request(Some(10), "Code", 1, true), request(Some(11), "Note", 3, true)];
+
+        let changes = plan_changes(&current, &table, requested, &column_types()).unwrap();
+
+        assert!(matches!(&changes[..], [ColumnChange::Edit { pk: 10, alter: Some((_, false)), .. }]));
+    }
+
+    #[test]
+    fn check_plan_changes_refused() {
+        let (current, table) = current();
+        let requested = vec![
+            request(Some(10), "Codes", 1, false),
+            request(None, "Other", 99, false),
+        ];
+
+        let refused = plan_changes(&current, &table, requested, &column_types()).err().unwrap();
+        let reasons: Vec<(&str, &str)> = refused.iter().map(|r| (r.column.as_str(), r.reason.as_str())).collect();
+
+        assert_eq!(reasons.len(), 3);
+        assert!(reasons.contains(&("Note", "Columns can not be removed from a sheet")));
+        assert!(reasons.contains(&("Code", "Columns can not be renamed")));
+        assert!(reasons.iter().any(|(column, _)| *column == "Other"));
+
+        let requested = vec![request(Some(10), "Code", 1, false), request(Some(11), "Note", 3, false)];
+        let refused = plan_changes(&current, &table, requested, &column_types()).err().unwrap();
+
+        assert_eq!(refused[0].reason, "An optional column can not become required");
+    }
+
+    #[test]
+    fn check_plan_changes_new_column_optional() {
+        let (current, table) = current();
+        let requested = vec![
+            request(Some(10), "Code", 1, false),
+            request(Some(11), "Note", 3, true),
+            request(None, "Amount", 2, false),
+        ];
+
+        let refused = plan_changes(&current, &table, requested, &column_types()).err().unwrap();
+
+        assert_eq!(refused.len(), 1);
+        assert_eq!((refused[0].column.as_str(), refused[0].reason.as_str()), ("Amount", "A column added to a sheet must be optional"));
+    }
+}
@@ -1,97 +1,145 @@
 use crate::ddb::context::{ db_types, functions };
-use crate::ddb::tables::{ Sheet, SheetMetaData };
+use crate::ddb::context::pool::Pool;
+use crate::ddb::tables::{ ColumnType, Sheet, SheetMetaData };
 use crate::ddb::DBLoad;
 use crate::repository;
 use crate::model;
+use crate::st;
+
+use db_types::{ ToGenericColumnType, ToSqlValue };
+
+pub mod auth;
+pub mod export;
+pub mod listing;
+pub mod meta_data;
+pub mod permission;
+pub mod policy;
+pub mod template;
+pub mod upload;
+pub mod users;
+
+/// Loads an active sheet with its columns, and the `GenericTable` that describes its data table
+pub async fn get_sheet_table(
+    pool: &Pool,
+    sheet_pk: i32,
+) -> anyhow::Result<Option<(Sheet, Vec<SheetMetaData>, db_types::GenericTable)>> {
+    let Some(sheet) = functions::find_by_pk::<Sheet>(pool, sheet_pk).await? else {
+        return Ok(None);
+    };
+
+    if !sheet.active() {
+        return Ok(None);
+    }
+
+    let mut meta_where = db_types::SqlSingleParameters::new();
+    meta_where.insert(st!(SheetMetaData::COL_SHEET_FK), sheet_pk.to_sql_value());
+
+    let columns = functions::select_from::<SheetMetaData>(pool, Some(&meta_where), None, None, &[], None).await?;
+    let column_types = functions::select_from::<ColumnType>(pool, None, None, None, &[], None).await?;
+
+    let generic_columns = columns.iter()
+        .map(|c| to_generic_column(c.column_name(), c.column_type_fk(), c.optional(), &column_types))
+        .collect::<anyhow::Result<Vec<_>>>()?;
+
+    let table = db_types::GenericTable::new(st!(sheet.table_name()), generic_columns);
+
+    Ok(Some((sheet, columns, table)))
+}
 
-use db_types::ToSqlValue;
+fn to_generic_column(
+    name: &str,
+    column_type_fk: i32,
+    optional: bool,
+    column_types: &[ColumnType],
+) -> anyhow::Result<db_types::GenericColumn> {
+    let column_type = column_types.iter()
+        .find(|t| t.pk() == column_type_fk)
+        .ok_or_else(|| anyhow::anyhow!("Column '{name}' has an unknown type ({column_type_fk})"))?;
+
+    Ok(db_types::GenericColumn::new(
+        st!(name),
+        st!(column_type.sql_type()).to_generic_column_type()?,
+        optional,
+    ))
+}
 
 pub async fn add_sheet_to_db_(
+    pool: &Pool,
     new_sheet: model::NewSheetRequest,
     columns: Vec<model::NewSheetMetaDataRequest>,
     user_id: i32,
     model_file: Option<Vec<u8>>,
 ) -> anyhow::Result<()> {
+    if let Some(model_file) = &model_file {
+        let names: Vec<&str> = columns.iter().map(|c| c.name.as_str()).collect();
+        upload::check_model_file(model_file, &names)?;
+    }
+
+    // Refused before anything is written, these are mistakes of the request and not of the database
+    let invalid = |e: anyhow::Error| model::InvalidSheetDefinition { message: e.to_string() };
+
+    functions::check_identifier(&new_sheet.table_name).map_err(invalid)?;
+    for column in &columns {
+        functions::check_identifier(&column.name).map_err(invalid)?;
+    }
+
+    let column_types = functions::select_from::<ColumnType>(pool, None, None, None, &[], None).await?;
+    let data_table = db_types::GenericTable::new(
+        new_sheet.table_name.clone(),
+        columns.iter()
+            .map(|c| to_generic_column(&c.name, c.column_type_fk, c.optional, &column_types))
+            .collect::<anyhow::Result<Vec<_>>>()
+            .map_err(invalid)?,
+    );
     
     let mut chain_map = db_types::ChainMap::new();
 
     let mut sheet_insert_param = db_types::SqlMultipleParameters::new();
-    sheet_insert_param.add_line(
-        vec![
-            (Sheet::COL_TABLE_NAME, new_sheet.table_name.to_sql_value()),
-            (Sheet::COL_DESCRIPTION, new_sheet.description.to_sql_value()),
-            (Sheet::COL_REQUEST_AFTER_UPDATE, new_sheet.request_after_update.to_sql_value()),
-            (Sheet::COL_MODEL, model_file.to_sql_value()),
-            (Sheet::COL_LAST_EDITED_BY_FK, user_id.to_sql_value()),
-        ]
-    );
+    sheet_insert_param.add_row(&new_sheet)?;
+    sheet_insert_param.add_const_column(model_file.to_sql_value(), Sheet::COL_MODEL);
+    sheet_insert_param.add_const_column(user_id.to_sql_value(), Sheet::COL_LAST_EDITED_BY_FK);
 
-    chain_map.push(&repository::sheet_insert, Some(sheet_insert_param), None);
-
-    let mut meta_insert_param = db_types::SqlMultipleParameters::new();
-    for column in columns {
-        meta_insert_param.add_line(
-            vec![
-                (SheetMetaData::COL_DESCRIPTION,        column.description.to_sql_value()),
-                (SheetMetaData::COL_COLUMN_NAME,        column.name.to_sql_value()),
-                (SheetMetaData::COL_COLUMN_TYPE_FK,     column.column_type_fk.to_sql_value()),
-                (SheetMetaData::COL_OPTIONAL,           column.optional.to_sql_value()),
-                (SheetMetaData::COL_REGEX_CONSTRAINT,   column.regex_constraint.to_sql_value()),
-                (SheetMetaData::COL_LAST_EDITED_BY_FK,  user_id.to_sql_value()),
-            ]
-        );
-    }
+    chain_map.push(&repository::sheet_insert, Some(sheet_insert_param), None).named("sheet");
+
+    let mut meta_insert_param = db_types::SqlMultipleParameters::from_rows(&columns)?;
+    meta_insert_param.add_const_column(user_id.to_sql_value(), SheetMetaData::COL_LAST_EDITED_BY_FK);
+
+    chain_map.push(&repository::sheet_meta_data_insert, Some(meta_insert_param), None).named("sheet_meta_data");
 
-    chain_map.push(&repository::sheet_meta_data_insert, Some(meta_insert_param), None);
+    // DDL runs in the same transaction, a failing CREATE TABLE also drops the sheet
+    let sheet_table_create = repository::sheet_table_create(data_table);
+    chain_map.push(&sheet_table_create, None, None).named("sheet_table");
 
-    let a = functions::chain_executions(chain_map, db_types::SqlSingleParameters::new()).await?;
+    functions::chain_executions(pool, chain_map, db_types::SqlSingleParameters::new()).await?;
 
     Ok(())
 }
 
 pub async fn add_sheet_to_db(
+    pool: &Pool,
     new_sheet: model::NewSheetRequest,
     columns: Vec<model::NewSheetMetaDataRequest>,
     user_id: i32,
     model_file: Option<Vec<u8>>,
 ) -> anyhow::Result<()> {
     let mut insert_parameters = db_types::SqlMultipleParameters::new();
-    
-    insert_parameters.add_line(
-        vec![
-            (Sheet::COL_TABLE_NAME,             new_sheet.table_name.to_sql_value()),
-            (Sheet::COL_DESCRIPTION,            new_sheet.description.to_sql_value()),
-            (Sheet::COL_REQUEST_AFTER_UPDATE,   new_sheet.request_after_update.to_sql_value()),
-            (Sheet::COL_MODEL,                  model_file.to_sql_value()),
-            (Sheet::COL_LAST_EDITED_BY_FK,      user_id.to_sql_value()),
-        ]
-    );
+    insert_parameters.add_row(&new_sheet)?;
+    insert_parameters.add_const_column(model_file.to_sql_value(), Sheet::COL_MODEL);
+    insert_parameters.add_const_column(user_id.to_sql_value(), Sheet::COL_LAST_EDITED_BY_FK);
 
     let sheet_insert = functions::build_insert_clause(Sheet::TAB, &insert_parameters)?;
 
-    let sheet_id = functions::get_identity(sheet_insert, Some(&insert_parameters.to_single()))
+    let sheet_id = functions::get_identity(pool, sheet_insert, Some(&insert_parameters.to_single()))
         .await?
         .ok_or_else(|| anyhow::anyhow!("No Id was generated by the insert in the Sheet table"))?;
 
-    let mut insert_parameters = db_types::SqlMultipleParameters::new();
-
-    for column in columns {
-        insert_parameters.add_line(
-            vec![
-                (SheetMetaData::COL_SHEET_FK,           sheet_id.to_sql_value()),
-                (SheetMetaData::COL_DESCRIPTION,        column.description.to_sql_value()),
-                (SheetMetaData::COL_COLUMN_NAME,        column.name.to_sql_value()),
-                (SheetMetaData::COL_COLUMN_TYPE_FK,     column.column_type_fk.to_sql_value()),
-                (SheetMetaData::COL_OPTIONAL,           column.optional.to_sql_value()),
-                (SheetMetaData::COL_REGEX_CONSTRAINT,   column.regex_constraint.to_sql_value()),
-                (SheetMetaData::COL_LAST_EDITED_BY_FK,  user_id.to_sql_value()),
-            ]
-        );
-    }
+    let mut insert_parameters = db_types::SqlMultipleParameters::from_rows(&columns)?;
+    insert_parameters.add_const_column(sheet_id.to_sql_value(), SheetMetaData::COL_SHEET_FK);
+    insert_parameters.add_const_column(user_id.to_sql_value(), SheetMetaData::COL_LAST_EDITED_BY_FK);
 
     let sheet_meta_insert = functions::build_insert_clause(SheetMetaData::TAB, &insert_parameters)?;
 
-    let _ = functions::run_query(sheet_meta_insert, Some(&insert_parameters.to_single())).await?;
+    let _ = functions::run_query(pool, sheet_meta_insert, Some(&insert_parameters.to_single())).await?;
 
     Ok(())
 }
\ No newline at end of file
@@ -0,0 +1,109 @@
+use std::collections::BTreeMap;
+
+use crate::ddb::context::{ db_types, functions };
+use crate::ddb::context::pool::Pool;
+use crate::ddb::tables::{ Group, ProfileGroups, Sheet, UploaderPermission };
+use crate::model;
+
+use db_types::Predicate;
+
+/// Rights of the profile on every sheet it has any permission on. Super users get every active sheet.
+pub async fn effective_permissions(pool: &Pool, profile_pk: i32, is_super_user: bool) -> anyhow::Result<Vec<model::SheetPermission>> {
+    if is_super_user {
+        let sheet_pks = functions::select_column_from::<Sheet, i32>(pool, Sheet::COL_PK, &Predicate::eq(Sheet::COL_ACTIVE, true), None).await?;
+
+        return Ok(sheet_pks.into_iter().flatten().map(model::SheetPermission::all).collect());
+    }
+
+    let grants = group_grants(pool, profile_pk, None).await?;
+
+    Ok(combine(&grants))
+}
+
+/// Rights of the profile on one sheet, nothing when no group of the profile grants any
+pub async fn sheet_permission(pool: &Pool, profile_pk: i32, is_super_user: bool, sheet_pk: i32) -> anyhow::Result<model::SheetPermission> {
+    if is_super_user {
+        return Ok(model::SheetPermission::all(sheet_pk));
+    }
+
+    let grants = group_grants(pool, profile_pk, Some(sheet_pk)).await?;
+
+    Ok(combine(&grants).pop().unwrap_or_else(|| model::SheetPermission::none(sheet_pk)))
+}
+
+/// Pks of the active groups the profile is in
+pub async fn active_group_pks(pool: &Pool, profile_pk: i32) -> anyhow::Result<Vec<i32>> {
+    let group_pks = functions::select_from::<ProfileGroups>(
+        pool, &Predicate::eq(ProfileGroups::COL_PROFILE_FK, profile_pk), None, None, &[], None
+    ).await?
+        .iter()
+        .map(ProfileGroups::group_fk)
+        .collect::<Vec<_>>();
+
+    if group_pks.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let active_groups = functions::select_from::<Group>(
+        pool,
+        &Predicate::and(vec![Predicate::is_in(Group::COL_PK, group_pks), Predicate::eq(Group::COL_ACTIVE, true)]),
+        None, None, &[], None
+    ).await?;
+
+    Ok(active_groups.iter().map(Group::pk).collect())
+}
+
+/// `UPLOADER_PERMISSION` rows of the active groups of the profile
+async fn group_grants(pool: &Pool, profile_pk: i32, sheet_pk: Option<i32>) -> anyhow::Result<Vec<UploaderPermission>> {
+    let active_groups = active_group_pks(pool, profile_pk).await?;
+
+    if active_groups.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let mut predicates = vec![Predicate::is_in(UploaderPermission::COL_GROUP_FK, active_groups)];
+    if let Some(sheet_pk) = sheet_pk {
+        predicates.push(Predicate::eq(UploaderPermission::COL_SHEET_FK, sheet_pk));
+    }
+
+    functions::select_from::<UploaderPermission>(pool, &Predicate::and(predicates), None, None, &[], None).await
+}
+
+/// Groups are additive, a right granted by any group of the profile is granted
+fn combine(grants: &[UploaderPermission]) -> Vec<model::SheetPermission> {
+    let mut sheets = BTreeMap::new();
+
+    for grant in grants {
+        let permission = sheets.entry(grant.sheet_fk())
+            .or_insert_with(|| model::SheetPermission::none(grant.sheet_fk()));
+
+        permission.can_view_hist |= grant.can_view_hist();
+        permission.can_upload |= grant.can_upload();
+    }
+
+    sheets.into_values().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn grant(group_fk: i32, sheet_fk: i32) -> UploaderPermission {
+        UploaderPermission::default().with_group_fk(group_fk).with_sheet_fk(sheet_fk)
+    }
+
+    #[test]
+    fn check_combine_is_additive() {
+        let grants = vec![
+            grant(1, 7).with_can_upload(true),
+            grant(2, 7).with_can_view_hist(true),
+            grant(2, 3),
+        ];
+
+        assert_eq!(combine(&grants), vec![
+            model::SheetPermission::none(3),
+            model::SheetPermission::all(7),
+        ]);
+        assert!(combine(&[]).is_empty());
+    }
+}
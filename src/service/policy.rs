@@ -0,0 +1,350 @@
+use crate::ddb::context::{ db_types, functions };
+use crate::ddb::context::pool::Pool;
+use crate::ddb::tables::{ ManagerPermission, Profile };
+use crate::model::{ ManagerAction, PolicyRefused };
+
+use db_types::Predicate;
+
+/// Profile changing users, groups or profiles, with the `MANAGER_PERMISSION` of its active groups.
+/// Super users pass every rule, managers only act inside their own board.
+#[derive(Debug)]
+pub struct Manager {
+    profile_pk: i32,
+    /// Profile acting as this one, see `impersonated_by`
+    impersonator: Option<i32>,
+    board: Option<i32>,
+    is_super_user: bool,
+    grants: Vec<ManagerPermission>,
+}
+
+/// Loads the manager permissions of the profile, an empty list for profiles that are not managers
+pub async fn load_manager(pool: &Pool, profile_pk: i32, board: Option<i32>, is_super_user: bool) -> anyhow::Result<Manager> {
+    let grants = match is_super_user {
+        true => Vec::new(),
+        false => {
+            let group_pks = super::permission::active_group_pks(pool, profile_pk).await?;
+
+            match group_pks.is_empty() {
+                true => Vec::new(),
+                false => functions::select_from::<ManagerPermission>(
+                    pool, &Predicate::is_in(ManagerPermission::COL_GROUP_FK, group_pks), None, None, &[], None
+                ).await?,
+            }
+        }
+    };
+
+    Ok(Manager::new(profile_pk, board, is_super_user, grants))
+}
+
+pub async fn load_profile_manager(pool: &Pool, profile: &Profile) -> anyhow::Result<Manager> {
+    load_manager(pool, profile.pk(), profile.board_fk(), profile.is_super_user()).await
+}
+
+fn refused(message: impl Into<String>) -> Result<(), PolicyRefused> {
+    Err(PolicyRefused { message: message.into() })
+}
+
+impl Manager {
+    pub fn new(profile_pk: i32, board: Option<i32>, is_super_user: bool, grants: Vec<ManagerPermission>) -> Self {
+        Self { profile_pk, impersonator: None, board, is_super_user, grants }
+    }
+
+    /// Changes are checked against this profile, but recorded as made by `impersonator`
+    pub fn impersonated_by(mut self, impersonator: Option<i32>) -> Self {
+        self.impersonator = impersonator;
+        self
+    }
+
+    pub fn profile_pk(&self) -> i32 {
+        self.profile_pk
+    }
+
+    /// Written to `LastEditedBy_fk`, the real person behind the change
+    pub fn editor_pk(&self) -> i32 {
+        self.impersonator.unwrap_or(self.profile_pk)
+    }
+
+    pub fn board(&self) -> Option<i32> {
+        self.board
+    }
+
+    pub fn is_super_user(&self) -> bool {
+        self.is_super_user
+    }
+
+    /// Permissions are additive, one group granting the action is enough
+    pub fn authorize(&self, action: ManagerAction) -> Result<(), PolicyRefused> {
+        if self.is_super_user || self.grants.iter().any(|grant| grant.allows(action)) {
+            return Ok(());
+        }
+
+        refused(format!("Missing the manager permission '{action}'"))
+    }
+
+    /// Managers only see the groups and profiles of their own board, super user profiles have none
+    pub fn check_board(&self, board: Option<i32>) -> Result<(), PolicyRefused> {
+        if self.is_super_user || (board.is_some() && board == self.board) {
+            return Ok(());
+        }
+
+        refused("Managers can only change groups and profiles of their own board")
+    }
+
+    /// Board of a new profile. Managers can not create super users, and their profiles land in their board.
+    pub fn new_profile_board(&self, board: Option<i32>, is_super_user: bool) -> Result<Option<i32>, PolicyRefused> {
+        if is_super_user {
+            if !self.is_super_user {
+                refused("Managers can not create super users")?;
+            }
+
+            if board.is_some() {
+                refused("A super user profile is not tied to a board")?;
+            }
+
+            return Ok(None);
+        }
+
+        let board = board.or(self.board);
+        if board.is_none() {
+            refused("A profile that is not a super user needs a board")?;
+        }
+
+        self.check_board(board)?;
+
+        Ok(board)
+    }
+
+    /// Board of a new group. Managers create groups in their own board, super users have none and must say which.
+    pub fn new_group_board(&self, board: Option<i32>) -> Result<i32, PolicyRefused> {
+        let Some(board) = board.or(self.board) else {
+            return Err(PolicyRefused { message: "A group needs a board".to_string() });
+        };
+
+        self.check_board(Some(board))?;
+
+        Ok(board)
+    }
+
+    /// Managers can only change a worker that has a profile in their board, and never a super user
+    pub fn check_worker(&self, profiles: &[Profile]) -> Result<(), PolicyRefused> {
+        if self.is_super_user {
+            return Ok(());
+        }
+
+        if profiles.iter().any(Profile::is_super_user) {
+            refused("Managers can not change super users")?;
+        }
+
+        if !profiles.iter().any(|profile| profile.board_fk().is_some() && profile.board_fk() == self.board) {
+            refused("Managers can only change workers of their own board")?;
+        }
+
+        Ok(())
+    }
+
+    /// Keeps manager permissions in the few groups super users give them to
+    pub fn check_manager_permission_grant(&self, grants_manager_permission: bool) -> Result<(), PolicyRefused> {
+        if grants_manager_permission && !self.is_super_user {
+            refused("Groups created by managers can not have manager permissions")?;
+        }
+
+        Ok(())
+    }
+
+    pub fn check_group_removal(&self, has_manager_permission: bool) -> Result<(), PolicyRefused> {
+        if has_manager_permission && !self.is_super_user {
+            refused("Groups with manager permissions can not be removed by managers")?;
+        }
+
+        Ok(())
+    }
+
+    /// Membership of groups with manager permissions is left to super users, and managers never
+    /// change the groups of their own profile. `changed_grants` are the `MANAGER_PERMISSION` rows
+    /// of the groups the profile joins or leaves.
+    pub fn check_membership_change(&self, profile_pk: i32, changed_grants: &[ManagerPermission]) -> Result<(), PolicyRefused> {
+        if self.is_super_user {
+            return Ok(());
+        }
+
+        if profile_pk == self.profile_pk || Some(profile_pk) == self.impersonator {
+            refused("Managers can not change the groups of their own profile")?;
+        }
+
+        if !changed_grants.is_empty() {
+            refused("Managers can not add or remove profiles of groups with manager permissions")?;
+        }
+
+        Ok(())
+    }
+
+    /// Nobody can act as a super user, and managers only as profiles of their board
+    /// that have no manager permission they lack themselves
+    pub fn check_impersonation(&self, target: &Manager) -> Result<(), PolicyRefused> {
+        self.authorize(ManagerAction::ImpersonateUsers)?;
+
+        if self.impersonator.is_some() {
+            refused("An impersonated profile can not impersonate another one")?;
+        }
+
+        if target.is_super_user {
+            refused("Super users can not be impersonated")?;
+        }
+
+        if target.profile_pk == self.profile_pk {
+            refused("A profile can not impersonate itself")?;
+        }
+
+        self.check_board(target.board)?;
+
+        let escalates = ManagerAction::ALL.into_iter()
+            .any(|action| target.authorize(action).is_ok() && self.authorize(action).is_err());
+
+        if escalates {
+            refused("Managers can not impersonate a profile with manager permissions they do not have")?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BOARD: i32 = 2;
+
+    /// Manager of `BOARD` allowed only to add workers
+    fn manager() -> Manager {
+        let grant = ManagerPermission::default().with_group_fk(5).with_add_worker(true);
+        Manager::new(12, Some(BOARD), false, vec![grant])
+    }
+
+    /// Manager of `BOARD` allowed only to impersonate
+    fn impersonator() -> Manager {
+        let grant = ManagerPermission::default().with_group_fk(5).with_impersonate_users(true);
+        Manager::new(12, Some(BOARD), false, vec![grant])
+    }
+
+    fn profile(pk: i32, board: Option<i32>, is_super_user: bool) -> Profile {
+        Profile::default().with_pk(pk).with_active(true).with_board_fk(board).with_is_super_user(is_super_user)
+    }
+
+    fn super_user() -> Manager {
+        Manager::new(1, None, true, Vec::new())
+    }
+
+    #[test]
+    fn check_authorize_needs_a_granting_group() {
+        let mut manager = manager();
+        assert!(manager.authorize(ManagerAction::AddWorker).is_ok());
+        assert!(manager.authorize(ManagerAction::RemoveGroup).is_err());
+
+        manager.grants.push(ManagerPermission::default().with_group_fk(6).with_remove_group(true));
+        assert!(manager.authorize(ManagerAction::RemoveGroup).is_ok());
+
+        assert!(Manager::new(12, Some(BOARD), false, Vec::new()).authorize(ManagerAction::AddWorker).is_err());
+        assert!(super_user().authorize(ManagerAction::EditProfileGroups).is_ok());
+    }
+
+    #[test]
+    fn check_no_super_user_creation() {
+        let refused = manager().new_profile_board(None, true).unwrap_err();
+        assert_eq!(refused.message, "Managers can not create super users");
+
+        assert_eq!(super_user().new_profile_board(None, true).unwrap(), None);
+        assert!(super_user().new_profile_board(Some(BOARD), true).is_err());
+    }
+
+    #[test]
+    fn check_own_board_only() {
+        let manager = manager();
+        assert!(manager.check_board(Some(BOARD)).is_ok());
+        assert!(manager.check_board(Some(BOARD + 1)).is_err());
+        assert!(manager.check_board(None).is_err());
+
+        assert_eq!(manager.new_profile_board(None, false).unwrap(), Some(BOARD));
+        assert!(manager.new_profile_board(Some(BOARD + 1), false).is_err());
+
+        assert!(manager.check_worker(&[profile(20, Some(BOARD), false)]).is_ok());
+        assert!(manager.check_worker(&[profile(21, Some(BOARD + 1), false)]).is_err());
+        assert!(manager.check_worker(&[profile(22, None, true)]).is_err());
+
+        assert_eq!(manager.new_group_board(None).unwrap(), BOARD);
+        assert!(manager.new_group_board(Some(BOARD + 1)).is_err());
+
+        assert!(super_user().check_board(Some(BOARD + 1)).is_ok());
+        assert_eq!(super_user().new_group_board(Some(BOARD + 1)).unwrap(), BOARD + 1);
+        assert_eq!(super_user().new_group_board(None).unwrap_err().message, "A group needs a board");
+        assert!(super_user().new_profile_board(None, false).is_err());
+    }
+
+    #[test]
+    fn check_manager_groups_without_manager_permission() {
+        assert!(manager().check_manager_permission_grant(false).is_ok());
+        assert!(manager().check_manager_permission_grant(true).is_err());
+        assert!(super_user().check_manager_permission_grant(true).is_ok());
+    }
+
+    #[test]
+    fn check_manager_groups_not_removed() {
+        assert!(manager().check_group_removal(false).is_ok());
+        assert!(manager().check_group_removal(true).is_err());
+        assert!(super_user().check_group_removal(true).is_ok());
+    }
+
+    #[test]
+    fn check_membership_without_manager_groups() {
+        let manager_group = || ManagerPermission::default().with_group_fk(6).with_edit_profile_groups(true);
+
+        assert!(manager().check_membership_change(20, &[]).is_ok());
+
+        let refused = manager().check_membership_change(20, &[manager_group()]).unwrap_err();
+        assert_eq!(refused.message, "Managers can not add or remove profiles of groups with manager permissions");
+
+        let refused = manager().check_membership_change(12, &[]).unwrap_err();
+        assert_eq!(refused.message, "Managers can not change the groups of their own profile");
+        assert!(impersonator().impersonated_by(Some(3)).check_membership_change(3, &[]).is_err());
+
+        assert!(super_user().check_membership_change(1, &[manager_group()]).is_ok());
+    }
+
+    #[test]
+    fn check_impersonation() {
+        let in_board = Manager::new(20, Some(BOARD), false, Vec::new());
+        let elsewhere = Manager::new(21, Some(BOARD + 1), false, Vec::new());
+        let other_super_user = Manager::new(22, None, true, Vec::new());
+
+        assert!(manager().check_impersonation(&in_board).is_err());
+
+        let manager = impersonator();
+        assert!(manager.check_impersonation(&in_board).is_ok());
+        assert!(manager.check_impersonation(&elsewhere).is_err());
+        assert!(manager.check_impersonation(&other_super_user).is_err());
+        assert!(manager.impersonated_by(Some(3)).check_impersonation(&in_board).is_err());
+
+        assert!(super_user().check_impersonation(&elsewhere).is_ok());
+        assert!(super_user().check_impersonation(&other_super_user).is_err());
+    }
+
+    #[test]
+    fn check_impersonation_without_escalation() {
+        let manager = impersonator();
+
+        let group_editor = ManagerPermission::default().with_group_fk(6).with_add_group(true).with_edit_group(true);
+        let other_manager = Manager::new(20, Some(BOARD), false, vec![group_editor]);
+        let refused = manager.check_impersonation(&other_manager).unwrap_err();
+        assert_eq!(refused.message, "Managers can not impersonate a profile with manager permissions they do not have");
+
+        let same_grants = ManagerPermission::default().with_group_fk(6).with_impersonate_users(true);
+        assert!(manager.check_impersonation(&Manager::new(20, Some(BOARD), false, vec![same_grants])).is_ok());
+
+        assert!(super_user().check_impersonation(&other_manager).is_ok());
+    }
+
+    #[test]
+    fn check_impersonated_changes_recorded_to_the_impersonator() {
+        assert_eq!(manager().editor_pk(), 12);
+        assert_eq!(manager().impersonated_by(Some(1)).editor_pk(), 1);
+    }
+}
@@ -0,0 +1,230 @@
+use rust_xlsxwriter::{ Format, Note, Workbook };
+
+use crate::ddb::context::{ db_types, functions };
+use crate::ddb::context::pool::Pool;
+use crate::ddb::DBLoad;
+use crate::ddb::tables::{ ColumnType, Sheet };
+use crate::model;
+use crate::st;
+
+use db_types::ToSqlValue;
+
+const TEMPLATE_WORKSHEET: &str = "Data";
+const MIN_COLUMN_WIDTH: usize = 12;
+
+/// What a template tells the user about one column of the sheet
+#[derive(Debug)]
+struct TemplateColumn {
+    name: String,
+    description: String,
+    view_type: String,
+    typing: tiberius::ColumnType,
+    optional: bool,
+    regex_constraint: Option<String>,
+}
+
+impl TemplateColumn {
+    /// e.g. `Inteiro, required, pattern: [0-9]{4}`
+    fn hint(&self) -> String {
+        let mut hint = format!("{}, {}", self.view_type, if self.optional { "optional" } else { "required" });
+
+        if let Some(pattern) = &self.regex_constraint {
+            hint.push_str(&format!(", pattern: {pattern}"));
+        }
+
+        hint
+    }
+}
+
+/// Generates an empty file, ready to be filled and uploaded, from the sheet columns.
+/// Returns the file name and content, or `None` if there is no active sheet with the given pk.
+pub async fn sheet_template(
+    pool: &Pool,
+    sheet_pk: i32,
+    format: model::TemplateFormat,
+) -> anyhow::Result<Option<(String, Vec<u8>)>> {
+    let Some((sheet, file)) = build_template(pool, sheet_pk, format).await? else {
+        return Ok(None);
+    };
+
+    Ok(Some((format!("{}.{}", sheet.table_name(), format.extension()), file)))
+}
+
+/// Saves the template of the sheet as its `Model`, `None` if there is no active sheet with the given pk
+pub async fn store_model(
+    pool: &Pool,
+    sheet_pk: i32,
+    format: model::TemplateFormat,
+    user_id: i32,
+) -> anyhow::Result<Option<()>> {
+    let Some((sheet, file)) = build_template(pool, sheet_pk, format).await? else {
+        return Ok(None);
+    };
+
+    let mut new_values = db_types::SqlSingleParameters::new();
+    new_values.insert(st!(Sheet::COL_MODEL), file.to_sql_value());
+    new_values.insert(st!(Sheet::COL_LAST_EDITED_BY_FK), user_id.to_sql_value());
+
+    let mut where_parameters = db_types::SqlSingleParameters::new();
+    where_parameters.insert(st!(Sheet::COL_PK), sheet.pk().to_sql_value());
+
+    let (sql, parameters) = functions::build_update_clause(Sheet::TAB, &new_values, Some(&where_parameters))?;
+
+    functions::run_query(pool, sql, Some(&parameters)).await?;
+
+    Ok(Some(()))
+}
+
+async fn build_template(
+    pool: &Pool,
+    sheet_pk: i32,
+    format: model::TemplateFormat,
+) -> anyhow::Result<Option<(Sheet, Vec<u8>)>> {
+    let Some((sheet, columns, table)) = super::get_sheet_table(pool, sheet_pk).await? else {
+        return Ok(None);
+    };
+
+    let column_types = functions::select_from::<ColumnType>(pool, None, None, None, &[], None).await?;
+
+    let template_columns = columns.iter()
+        .zip(table.columns())
+        .map(|(column, generic_column)| TemplateColumn {
+            name: st!(column.column_name()),
+            description: st!(column.description()),
+            view_type: column_types.iter()
+                .find(|t| t.pk() == column.column_type_fk())
+                .map(|t| st!(t.view_type()))
+                .unwrap_or_default(),
+            typing: generic_column.typing(),
+            optional: column.optional(),
+            regex_constraint: column.regex_constraint().map(str::to_string),
+        })
+        .collect::<Vec<_>>();
+
+    let file = match format {
+        model::TemplateFormat::Xlsx => build_xlsx(&template_columns)?,
+        model::TemplateFormat::Csv => build_csv(&template_columns)?,
+    };
+
+    Ok(Some((sheet, file)))
+}
+
+// Header row with the column names, descriptions and hints go in the header notes
+fn build_xlsx(columns: &[TemplateColumn]) -> anyhow::Result<Vec<u8>> {
+    let mut workbook = Workbook::new();
+    let worksheet = workbook.add_worksheet();
+    worksheet.set_name(TEMPLATE_WORKSHEET)?;
+
+    let header_format = Format::new().set_bold();
+
+    for (idx, column) in columns.iter().enumerate() {
+        let col = u16::try_from(idx)?;
+
+        worksheet.write_string_with_format(0, col, &column.name, &header_format)?;
+        worksheet.insert_note(0, col, &Note::new(format!("{}\n({})", column.description, column.hint())))?;
+        worksheet.set_column_width(col, column.name.chars().count().max(MIN_COLUMN_WIDTH) as f64)?;
+
+        // Cells are formatted so they are read back as `SqlValue::from_raw` expects them
+        if let Some(num_format) = cell_format(column.typing) {
+            worksheet.set_column_format(col, &Format::new().set_num_format(num_format))?;
+        }
+    }
+
+    worksheet.set_freeze_panes(1, 0)?;
+
+    Ok(workbook.save_to_buffer()?)
+}
+
+// Header row with the column names, descriptions and hints go in comment lines before it
+fn build_csv(columns: &[TemplateColumn]) -> anyhow::Result<Vec<u8>> {
+    let comment = char::from(super::upload::parser::COMMENT);
+
+    // BOM so Excel opens the file as UTF-8
+    let mut file = "\u{feff}".as_bytes().to_vec();
+
+    for column in columns {
+        let description = column.description.replace(['\r', '\n'], " ");
+        file.extend(format!("{comment} {}: {description} ({})\n", column.name, column.hint()).as_bytes());
+    }
+
+    let mut writer = csv::Writer::from_writer(file);
+    writer.write_record(columns.iter().map(|column| &column.name))?;
+
+    Ok(writer.into_inner()?)
+}
+
+fn cell_format(typing: tiberius::ColumnType) -> Option<&'static str> {
+    match typing {
+        tiberius::ColumnType::Daten => Some("yyyy-mm-dd"),
+        tiberius::ColumnType::Timen => Some("hh:mm:ss"),
+        tiberius::ColumnType::Datetime2 | tiberius::ColumnType::DatetimeOffsetn => Some("yyyy-mm-dd hh:mm:ss"),
+
+        // Keeps leading zeros and stops Excel from turning codes into numbers
+        tiberius::ColumnType::BigVarChar
+        | tiberius::ColumnType::NVarchar
+        | tiberius::ColumnType::Guid
+        | tiberius::ColumnType::Xml => Some("@"),
+
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::upload::parser;
+
+    fn columns() -> Vec<TemplateColumn> {
+        vec![
+            TemplateColumn {
+                name: st!("Code"),
+                description: st!("Product code"),
+                view_type: st!("Inteiro"),
+                typing: tiberius::ColumnType::Int4,
+                optional: false,
+                regex_constraint: Some(st!("[0-9]{4}")),
+            },
+            TemplateColumn {
+                name: st!("Note, free"),
+                description: st!("Anything\nelse"),
+                view_type: st!("Texto"),
+                typing: tiberius::ColumnType::NVarchar,
+                optional: true,
+                regex_constraint: None,
+            },
+        ]
+    }
+
+    #[test]
+    fn check_hint() {
+        let columns = columns();
+
+        assert_eq!(columns[0].hint(), "Inteiro, required, pattern: [0-9]{4}");
+        assert_eq!(columns[1].hint(), "Texto, optional");
+    }
+
+    #[test]
+    fn check_build_xlsx() {
+        let file = build_xlsx(&columns()).unwrap();
+        let (worksheet, table) = parser::parse_file(&file, None).unwrap();
+
+        assert_eq!(worksheet.as_deref(), Some(TEMPLATE_WORKSHEET));
+        assert_eq!(table.header, vec!["Code", "Note, free"]);
+        assert!(table.rows.is_empty());
+    }
+
+    #[test]
+    fn check_build_csv() {
+        let file = build_csv(&columns()).unwrap();
+        let text = String::from_utf8(file.clone()).unwrap();
+
+        assert!(text.contains("# Code: Product code (Inteiro, required, pattern: [0-9]{4})\n"));
+        assert!(text.contains("# Note, free: Anything else (Texto, optional)\n"));
+
+        let (worksheet, table) = parser::parse_file(&file, None).unwrap();
+
+        assert_eq!(worksheet, None);
+        assert_eq!(table.header, vec!["Code", "Note, free"]);
+        assert!(table.rows.is_empty());
+    }
+}
@@ -0,0 +1,252 @@
+use std::time::Duration;
+
+use chrono::NaiveDateTime;
+use reqwest::Method;
+
+use crate::ddb::context::{ db_types, functions };
+use crate::ddb::context::pool::Pool;
+use crate::ddb::tables::UploadCallback;
+use crate::ddb::DBLoad;
+use crate::model;
+
+use db_types::ToSqlValue;
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// `Sheet.RequestAfterUpdate` is either a bare url, sent as POST, or `<METHOD> <url>`
+#[derive(Debug, PartialEq)]
+pub struct CallbackRequest {
+    pub method: Method,
+    pub url: String,
+}
+
+impl CallbackRequest {
+    pub fn parse(request: &str) -> anyhow::Result<Self> {
+        let request = request.trim();
+
+        let (method, url) = match request.split_once(char::is_whitespace) {
+            Some((method, url)) => (Method::from_bytes(method.to_uppercase().as_bytes())?, url.trim()),
+            None => (Method::POST, request),
+        };
+
+        let url = reqwest::Url::parse(url)?;
+        anyhow::ensure!(matches!(url.scheme(), "http" | "https"), "'{url}' is not an http url");
+
+        Ok(Self { method, url: url.to_string() })
+    }
+}
+
+/// Waits `backoff`, then twice as long after each failed attempt
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    pub attempts: u32,
+    pub backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self { attempts: 5, backoff: Duration::from_secs(2) }
+    }
+}
+
+#[derive(Debug)]
+pub struct CallbackAttempt {
+    pub attempt: u32,
+    pub status_code: Option<u16>,
+    pub error: Option<String>,
+    pub attempted_at: NaiveDateTime,
+}
+
+impl CallbackAttempt {
+    pub fn succeeded(&self) -> bool {
+        self.error.is_none()
+    }
+}
+
+/// Sends the callback in the background, an upload never fails because of it.
+/// The attempts are recorded with `pool`, a clone of the one of the request.
+pub fn spawn_callback(pool: Pool, request: String, payload: model::UploadCallback) {
+    tokio::spawn(async move {
+        let request = match CallbackRequest::parse(&request) {
+            Ok(request) => request,
+            Err(e) => {
+                log::error!("Sheet {} has an invalid request after update '{request}': {e}", payload.sheet_pk);
+                return;
+            }
+        };
+
+        let client = reqwest::Client::new();
+        let attempts = send_callback(&client, &request, &payload, &RetryPolicy::default()).await;
+
+        if !attempts.last().is_some_and(CallbackAttempt::succeeded) {
+            log::warn!("Request after update of upload {} failed after {} attempt(s)", payload.upload_id, attempts.len());
+        }
+
+        if let Err(e) = record_attempts(&pool, payload.upload_id, &request, &attempts).await {
+            log::error!("Attempts of the request after update of upload {} were not recorded: {e:?}", payload.upload_id);
+        }
+    });
+}
+
+/// Sends `payload` until a 2xx response or until the policy runs out of attempts.
+/// GET requests carry the payload in the query string, the other methods as a JSON body.
+pub async fn send_callback(
+    client: &reqwest::Client,
+    request: &CallbackRequest,
+    payload: &model::UploadCallback,
+    policy: &RetryPolicy,
+) -> Vec<CallbackAttempt> {
+    let mut attempts = Vec::new();
+    let mut backoff = policy.backoff;
+
+    for attempt in 1..=policy.attempts {
+        let attempted_at = chrono::Local::now().naive_local();
+
+        let builder = client.request(request.method.clone(), &request.url).timeout(REQUEST_TIMEOUT);
+        let builder = match request.method {
+            Method::GET => builder.query(payload),
+            _ => builder.json(payload),
+        };
+
+        let (status_code, error) = match builder.send().await {
+            Ok(response) if response.status().is_success() => (Some(response.status().as_u16()), None),
+            Ok(response) => (Some(response.status().as_u16()), Some(format!("Responded with {}", response.status()))),
+            Err(e) => (None, Some(e.to_string())),
+        };
+
+        attempts.push(CallbackAttempt { attempt, status_code, error, attempted_at });
+
+        if attempts.last().is_some_and(CallbackAttempt::succeeded) {
+            break;
+        }
+
+        if attempt < policy.attempts {
+            tokio::time::sleep(backoff).await;
+            backoff *= 2;
+        }
+    }
+
+    attempts
+}
+
+async fn record_attempts(
+    pool: &Pool,
+    upload_id: i64,
+    request: &CallbackRequest,
+    attempts: &[CallbackAttempt],
+) -> anyhow::Result<()> {
+    if attempts.is_empty() {
+        return Ok(());
+    }
+
+    let mut insert_param = db_types::SqlMultipleParameters::new();
+    for attempt in attempts {
+        insert_param.add_line(
+            vec![
+                (UploadCallback::COL_UPLOAD_FK,     upload_id.to_sql_value()),
+                (UploadCallback::COL_ATTEMPT,       (attempt.attempt as i32).to_sql_value()),
+                (UploadCallback::COL_URL,           request.url.clone().to_sql_value()),
+                (UploadCallback::COL_STATUS_CODE,   attempt.status_code.map(i32::from).to_sql_value()),
+                (UploadCallback::COL_ERROR,         attempt.error.clone().to_sql_value()),
+                (UploadCallback::COL_ATTEMPTED_AT,  attempt.attempted_at.to_sql_value()),
+            ]
+        )?;
+    }
+
+    let sql = functions::build_insert_clause(UploadCallback::TAB, &insert_param)?;
+    functions::run_query(pool, sql, Some(&insert_param.to_single())).await?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use axum::{ Json, Router, extract::State, http::StatusCode, routing::post };
+
+    use super::*;
+
+    type Received = Arc<Mutex<Vec<model::UploadCallback>>>;
+
+    /// Local server answering `failures` times with 503 before accepting the callback
+    async fn mock_server(failures: usize) -> (String, Received) {
+        let received = Received::default();
+
+        let app = Router::new()
+            .route("/refresh", post(async move |State(received): State<Received>, Json(payload): Json<model::UploadCallback>| {
+                let mut received = received.lock().unwrap();
+                received.push(payload);
+
+                if received.len() > failures { StatusCode::OK } else { StatusCode::SERVICE_UNAVAILABLE }
+            }))
+            .with_state(received.clone());
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        (format!("http://{address}/refresh"), received)
+    }
+
+    fn payload() -> model::UploadCallback {
+        model::UploadCallback { sheet_pk: 1, upload_id: 7, rows: 120, uploaded_by: 3 }
+    }
+
+    fn policy() -> RetryPolicy {
+        RetryPolicy { attempts: 3, backoff: Duration::from_millis(1) }
+    }
+
+    #[test]
+    fn check_parse_callback_request() {
+        let request = CallbackRequest::parse("https://example.com/refresh").unwrap();
+        assert_eq!(request.method, Method::POST);
+        assert_eq!(request.url, "https://example.com/refresh");
+
+        let request = CallbackRequest::parse(" put  http://example.com/refresh?x=1 ").unwrap();
+        assert_eq!(request.method, Method::PUT);
+        assert_eq!(request.url, "http://example.com/refresh?x=1");
+
+        assert!(CallbackRequest::parse("refresh the dataset").is_err());
+        assert!(CallbackRequest::parse("ftp://example.com").is_err());
+    }
+
+    #[tokio::test]
+    async fn check_send_callback_retries() {
+        let (url, received) = mock_server(2).await;
+        let request = CallbackRequest::parse(&url).unwrap();
+
+        let attempts = send_callback(&reqwest::Client::new(), &request, &payload(), &policy()).await;
+
+        assert_eq!(attempts.len(), 3);
+        assert_eq!(attempts.iter().map(|a| a.status_code).collect::<Vec<_>>(), vec![Some(503), Some(503), Some(200)]);
+        assert!(!attempts[0].succeeded());
+        assert!(attempts[2].succeeded());
+        assert_eq!(received.lock().unwrap().last(), Some(&payload()));
+    }
+
+    #[tokio::test]
+    async fn check_send_callback_gives_up() {
+        let (url, received) = mock_server(usize::MAX).await;
+        let request = CallbackRequest::parse(&url).unwrap();
+
+        let attempts = send_callback(&reqwest::Client::new(), &request, &payload(), &policy()).await;
+
+        assert_eq!(attempts.len(), 3);
+        assert!(attempts.iter().all(|a| !a.succeeded()));
+        assert_eq!(received.lock().unwrap().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn check_send_callback_unreachable() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/refresh", listener.local_addr().unwrap());
+        drop(listener);
+
+        let request = CallbackRequest::parse(&url).unwrap();
+        let attempts = send_callback(&reqwest::Client::new(), &request, &payload(), &policy()).await;
+
+        assert_eq!(attempts.len(), 3);
+        assert!(attempts.iter().all(|a| a.status_code.is_none() && a.error.is_some()));
+    }
+}
@@ -0,0 +1,155 @@
+mod callback;
+pub(super) mod parser;
+mod scripts;
+mod validation;
+
+use crate::ddb::context::{ db_types, functions };
+use crate::ddb::context::pool::Pool;
+use crate::ddb::tables::{ Sheet, Upload };
+use crate::repository;
+use crate::model;
+use crate::st;
+
+use db_types::ToSqlValue;
+
+pub fn list_worksheets(file: &[u8]) -> anyhow::Result<Vec<String>> {
+    anyhow::ensure!(parser::is_workbook(file), "File is not a workbook");
+
+    parser::list_worksheets(file)
+}
+
+/// Checks that the header of a `Sheet.Model` file (first worksheet for workbooks) has exactly the sheet columns
+pub fn check_model_file(file: &[u8], names: &[&str]) -> Result<(), model::ModelFileMismatch> {
+    let mismatch = match parser::parse_file(file, None) {
+        Ok((_, raw)) => validation::compare_model_header(names, &raw.header),
+        Err(e) => model::ModelFileMismatch {
+            unreadable: Some(format!("File could not be read: {e}")),
+            ..Default::default()
+        },
+    };
+
+    if mismatch.is_empty() { Ok(()) } else { Err(mismatch) }
+}
+
+/// Validates `file` (CSV or workbook) against the sheet definition and, when every cell is valid,
+/// loads it into the sheet data table and records it in `UPLOAD`.
+/// The sheet `CUSTOM_SQL_SCRIPT`s run in the same transaction, before, after or in place of the insert.
+/// Once committed, the sheet `RequestAfterUpdate` is sent in the background.
+/// For workbooks `worksheet` selects the worksheet by name or index, the first one is used by default.
+/// A `dry_run` stops after the validation, nothing is written.
+/// Returns `None` if there is no active sheet with the given pk.
+pub async fn upload_file(
+    pool: &Pool,
+    sheet_pk: i32,
+    file: Vec<u8>,
+    worksheet: Option<String>,
+    user_id: i32,
+    dry_run: bool,
+) -> anyhow::Result<Option<model::UploadReport>> {
+    let Some((sheet, columns, table)) = super::get_sheet_table(pool, sheet_pk).await? else {
+        return Ok(None);
+    };
+
+    let constraints = validation::compile_constraints(&columns)?;
+
+    let (sheet_used, raw) = match parser::parse_file(&file, worksheet.as_deref()) {
+        Ok(parsed) => parsed,
+        Err(e) => return Ok(Some(unreadable_file(e))),
+    };
+
+    let (rows, report) = validation::validate(&table, &constraints, &raw);
+    if dry_run || !report.errors.is_empty() {
+        return Ok(Some(report));
+    }
+
+    let scripts = scripts::load_scripts(pool, sheet.pk()).await?;
+
+    // "As update" scripts replace the default insert, the rows are staged for them instead
+    let staged_table = scripts.iter()
+        .any(|script| script.run_as_update())
+        .then(|| format!("{}_STAGED_{}", table.name(), &uuid::Uuid::new_v4().simple().to_string()[..8]));
+
+    let before_steps = scripts::script_steps(&scripts, scripts::ScriptPhase::Before, None);
+    let as_update_steps = scripts::script_steps(&scripts, scripts::ScriptPhase::As, staged_table.as_deref());
+    let after_steps = scripts::script_steps(&scripts, scripts::ScriptPhase::After, None);
+
+    let staged_table_create = staged_table.as_ref().map(|staged_table| repository::sheet_table_create(
+        db_types::GenericTable::new(staged_table.clone(), table.columns().to_vec())
+    ));
+    let staged_table_drop = staged_table.clone().map(repository::table_drop);
+
+    let mut chain_map = db_types::ChainMap::new();
+
+    for step in &before_steps {
+        chain_map.push(step.as_ref(), None, None);
+    }
+
+    if let Some(staged_table_create) = &staged_table_create {
+        chain_map.push(staged_table_create, None, None);
+    }
+
+    let data_table = db_types::GenericTable::new(
+        staged_table.clone().unwrap_or_else(|| st!(table.name())),
+        table.columns().to_vec(),
+    );
+    chain_map.push_bulk_insert(db_types::BulkInsert::new(data_table, rows));
+
+    for step in &as_update_steps {
+        chain_map.push(step.as_ref(), None, None);
+    }
+
+    if let Some(staged_table_drop) = &staged_table_drop {
+        chain_map.push(staged_table_drop, None, None);
+    }
+
+    for step in &after_steps {
+        chain_map.push(step.as_ref(), None, None);
+    }
+
+    let mut upload_insert_param = db_types::SqlMultipleParameters::new();
+    upload_insert_param.add_line(
+        vec![
+            (Upload::COL_FILE_UPLOADED, file.to_sql_value()),
+            (Upload::COL_UPLOADED_BY_FK, user_id.to_sql_value()),
+            (Upload::COL_SHEET_USED, sheet_used.to_sql_value()),
+        ]
+    )?;
+
+    chain_map.push(&repository::upload_insert, Some(upload_insert_param), None).named("upload");
+
+    let mut global_values = db_types::SqlSingleParameters::new();
+    global_values.insert(st!(Sheet::COL_PK), sheet.pk().to_sql_value());
+    global_values.insert(st!(Sheet::COL_TABLE_NAME), st!(sheet.table_name()).to_sql_value());
+
+    // Everything is rolled back when a custom script fails
+    let context = match functions::chain_executions(pool, chain_map, global_values).await {
+        Ok(context) => context,
+        Err(e) => return scripts::script_failed(e, report.rows).map(Some),
+    };
+
+    if let Some(request) = sheet.request_after_update() {
+        let upload_id = context.identity("upload")?;
+
+        callback::spawn_callback(pool.clone(), st!(request), model::UploadCallback {
+            sheet_pk: sheet.pk(),
+            upload_id,
+            rows: report.rows,
+            uploaded_by: user_id,
+        });
+    }
+
+    Ok(Some(report))
+}
+
+fn unreadable_file(error: anyhow::Error) -> model::UploadReport {
+    model::UploadReport {
+        rows: 0,
+        errors: vec![model::UploadIssue {
+            row: None,
+            column: String::new(),
+            value: None,
+            pattern: None,
+            message: format!("File could not be read: {error}"),
+        }],
+    }
+}
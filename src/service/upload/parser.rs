@@ -0,0 +1,251 @@
+use std::io::Cursor;
+
+use calamine::{Data, Reader};
+
+use crate::st;
+
+const ZIP_MAGIC: &[u8] = b"PK\x03\x04"; // xlsx, xlsb and ods
+const OLE_MAGIC: &[u8] = &[0xD0, 0xCF, 0x11, 0xE0, 0xA1, 0xB1, 0x1A, 0xE1]; // xls
+pub const COMMENT: u8 = b'#';
+
+/// Plain text view of an uploaded file, before any type coercion
+#[derive(Debug, Default)]
+pub struct RawTable {
+    pub header: Vec<String>,
+    pub rows: Vec<Vec<String>>,
+    /// Line of the file, or row of the worksheet, each row starts at. Both count from 1 like editors do.
+    pub lines: Vec<usize>,
+}
+
+/// Reads a workbook or a CSV file, the worksheet name is returned when a workbook was read
+pub fn parse_file(file: &[u8], worksheet: Option<&str>) -> anyhow::Result<(Option<String>, RawTable)> {
+    if is_workbook(file) {
+        let (name, table) = parse_excel(file, worksheet)?;
+        return Ok((Some(name), table));
+    }
+
+    Ok((None, parse_csv(file)?))
+}
+
+pub fn is_workbook(file: &[u8]) -> bool {
+    file.starts_with(ZIP_MAGIC) || file.starts_with(OLE_MAGIC)
+}
+
+pub fn list_worksheets(file: &[u8]) -> anyhow::Result<Vec<String>> {
+    let workbook = calamine::open_workbook_auto_from_rs(Cursor::new(file))?;
+
+    Ok(workbook.sheet_names())
+}
+
+pub fn parse_excel(file: &[u8], worksheet: Option<&str>) -> anyhow::Result<(String, RawTable)> {
+    let mut workbook = calamine::open_workbook_auto_from_rs(Cursor::new(file))?;
+    let name = select_worksheet(&workbook.sheet_names(), worksheet)?;
+    let range = workbook.worksheet_range(&name)?;
+
+    // The range starts at the first used cell, rows are numbered before blank ones are dropped
+    let first_row = range.start().map_or(0, |(row, _)| row as usize) + 1;
+
+    let mut rows = range.rows()
+        .enumerate()
+        .map(|(offset, row)| (first_row + offset, row.iter().map(cell_to_string).collect::<Vec<String>>()))
+        .filter(|(_, row)| row.iter().any(|cell| !cell.trim().is_empty()));
+
+    let header = rows.next()
+        .ok_or_else(|| anyhow::anyhow!("Worksheet '{name}' is empty"))?
+        .1
+        .into_iter()
+        .map(|cell| cell.trim().to_string())
+        .collect();
+
+    let (lines, rows) = rows.unzip();
+
+    Ok((name, RawTable { header, rows, lines }))
+}
+
+/// Picks a worksheet by name (case insensitive) or by its 0-based position, defaults to the first one
+fn select_worksheet(names: &[String], wanted: Option<&str>) -> anyhow::Result<String> {
+    let Some(wanted) = wanted.map(str::trim) else {
+        return names.first()
+            .cloned()
+            .ok_or_else(|| anyhow::anyhow!("Workbook has no worksheets"));
+    };
+
+    names.iter()
+        .find(|name| name.eq_ignore_ascii_case(wanted))
+        .or_else(|| wanted.parse::<usize>().ok().and_then(|idx| names.get(idx)))
+        .cloned()
+        .ok_or_else(|| anyhow::anyhow!("Worksheet '{wanted}' not found, available worksheets are {names:?}"))
+}
+
+// Written in formats accepted by `SqlValue::from_raw`
+fn cell_to_string(cell: &Data) -> String {
+    match cell {
+        Data::Float(v) if v.fract() == 0.0 && v.abs() < 1e15 => format!("{}", *v as i64),
+
+        Data::DateTime(v) if v.is_duration() || v.as_f64() < 1.0 => v.as_datetime()
+            .map(|dt| dt.format("%H:%M:%S").to_string())
+            .unwrap_or_default(),
+
+        Data::DateTime(v) => match v.as_datetime() {
+            Some(dt) if dt.time() == chrono::NaiveTime::MIN => dt.format("%Y-%m-%d").to_string(),
+            Some(dt) => dt.format("%Y-%m-%d %H:%M:%S").to_string(),
+            None => String::new(),
+        },
+
+        Data::Error(_) => st!("#ERROR"),
+
+        _ => cell.to_string(),
+    }
+}
+
+/// The comment lines generated templates write before the header to describe the columns are skipped.
+/// Any other line, even one starting with '#', is the header or data.
+pub fn parse_csv(file: &[u8]) -> anyhow::Result<RawTable> {
+    let file = file.strip_prefix("\u{feff}".as_bytes()).unwrap_or(file);
+    let (file, comment_lines) = skip_comments(file);
+
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(guess_delimiter(file))
+        .flexible(true)
+        .from_reader(file);
+
+    let header = reader.headers()?
+        .iter()
+        .map(|name| name.trim().to_string())
+        .collect();
+
+    let mut rows = Vec::new();
+    let mut lines = Vec::new();
+    for record in reader.records() {
+        let record = record?;
+
+        // Blank lines are dropped and a quoted cell may take several lines, so rows are not counted.
+        // The position of a record is where the reader started it, before the blank lines it skipped.
+        let position = record.position().ok_or_else(|| anyhow::anyhow!("CSV record without a position"))?;
+        let start = usize::try_from(position.byte())?;
+        let blank_lines = file[start..].iter()
+            .take_while(|b| matches!(b, b'\r' | b'\n'))
+            .filter(|b| **b == b'\n')
+            .count();
+        lines.push(comment_lines + usize::try_from(position.line())? + blank_lines);
+        rows.push(record.iter().map(str::to_string).collect());
+    }
+
+    Ok(RawTable { header, rows, lines })
+}
+
+/// The file after the template comments and how many lines they took
+fn skip_comments(mut file: &[u8]) -> (&[u8], usize) {
+    let mut skipped = 0;
+
+    loop {
+        let end = file.iter().position(|b| *b == b'\n');
+        if !is_template_comment(&file[..end.unwrap_or(file.len())]) {
+            return (file, skipped);
+        }
+
+        file = end.map_or(&[], |end| &file[end + 1..]);
+        skipped += 1;
+    }
+}
+
+/// `# <name>: <description> (<hint>)`, as written by `template::build_csv`
+fn is_template_comment(line: &[u8]) -> bool {
+    let line = String::from_utf8_lossy(line);
+    let line = line.trim_end();
+
+    line.starts_with(char::from(COMMENT)) && line[1..].starts_with(' ') && line.contains(": ") && line.ends_with(')')
+}
+
+// Excel exports CSV files with ';' when the system uses ',' as decimal separator
+fn guess_delimiter(file: &[u8]) -> u8 {
+    let first_line = file.split(|b| *b == b'\n').next().unwrap_or_default();
+    let count = |delimiter: u8| first_line.iter().filter(|b| **b == delimiter).count();
+
+    if count(b';') > count(b',') { b';' } else { b',' }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_parse_csv() {
+        let file = "\u{feff}Name;Value\nA;1,5\nB;\n";
+        let table = parse_csv(file.as_bytes()).unwrap();
+
+        assert_eq!(table.header, vec!["Name", "Value"]);
+        assert_eq!(table.rows, vec![vec!["A", "1,5"], vec!["B", ""]]);
+
+        let file = "Name,Value\n\"A, B\",1\n";
+        let table = parse_csv(file.as_bytes()).unwrap();
+
+        assert_eq!(table.rows, vec![vec!["A, B", "1"]]);
+
+        let file = "\u{feff}# Name: a, b, c (Texto, required)\n# Value: 1, 2 (Decimal, optional)\nName;Value\n#123;1\nB;2\n";
+        let table = parse_csv(file.as_bytes()).unwrap();
+
+        assert_eq!(table.header, vec!["Name", "Value"]);
+        assert_eq!(table.rows, vec![vec!["#123", "1"], vec!["B", "2"]]);
+
+        assert!(parse_csv(b"# Name: only comments (Texto, optional)").unwrap().header.is_empty());
+
+        // Only the comments of templates are skipped
+        let table = parse_csv(b"#id;Value\n1;2\n").unwrap();
+        assert_eq!(table.header, vec!["#id", "Value"]);
+    }
+
+    #[test]
+    fn check_parse_csv_lines() {
+        let file = "# Name: a name (Texto, required)\n# Note: free (Texto, optional)\nName,Note\nA,1\n\nB,\"two\nlines\"\nC,3\n";
+        let table = parse_csv(file.as_bytes()).unwrap();
+
+        assert_eq!(table.rows.len(), 3);
+        assert_eq!(table.lines, vec![4, 6, 8]);
+
+        assert_eq!(parse_csv(b"Name\nA\n").unwrap().lines, vec![2]);
+    }
+
+    #[test]
+    fn check_parse_excel_rows() {
+        let mut workbook = rust_xlsxwriter::Workbook::new();
+        let worksheet = workbook.add_worksheet();
+        worksheet.write_string(2, 1, "Name").unwrap();
+        worksheet.write_string(3, 1, "A").unwrap();
+        worksheet.write_string(6, 1, "B").unwrap();
+        let file = workbook.save_to_buffer().unwrap();
+
+        let (_, table) = parse_excel(&file, None).unwrap();
+
+        assert_eq!(table.header, vec!["Name"]);
+        assert_eq!(table.rows, vec![vec!["A"], vec!["B"]]);
+        assert_eq!(table.lines, vec![4, 7]);
+    }
+
+    #[test]
+    fn check_select_worksheet() {
+        let names = vec![st!("Data"), st!("2024"), st!("Notes")];
+
+        assert_eq!(select_worksheet(&names, None).unwrap(), "Data");
+        assert_eq!(select_worksheet(&names, Some("notes")).unwrap(), "Notes");
+        assert_eq!(select_worksheet(&names, Some("2024")).unwrap(), "2024");
+        assert_eq!(select_worksheet(&names, Some("2")).unwrap(), "Notes");
+        assert!(select_worksheet(&names, Some("7")).is_err());
+        assert!(select_worksheet(&[], None).is_err());
+    }
+
+    #[test]
+    fn check_cell_to_string() {
+        assert_eq!(cell_to_string(&Data::Float(3.0)), "3");
+        assert_eq!(cell_to_string(&Data::Float(1.25)), "1.25");
+        assert_eq!(cell_to_string(&Data::Bool(true)), "true");
+        assert_eq!(cell_to_string(&Data::Empty), "");
+    }
+
+    #[test]
+    fn check_is_workbook() {
+        assert!(is_workbook(b"PK\x03\x04rest of the zip"));
+        assert!(is_workbook(OLE_MAGIC));
+        assert!(!is_workbook(b"Name;Value"));
+    }
+}
@@ -0,0 +1,112 @@
+use crate::ddb::context::{ db_types, functions };
+use crate::ddb::context::pool::Pool;
+use crate::ddb::tables::CustomSqlScript;
+use crate::ddb::DBLoad;
+use crate::repository;
+use crate::model;
+use crate::st;
+
+use db_types::{ OrderBy, ToSqlValue };
+
+#[derive(Debug, Clone, Copy, PartialEq, derive_more::Display)]
+pub enum ScriptPhase {
+    #[display("run before update")]
+    Before,
+    #[display("run as update")]
+    As,
+    #[display("run after update")]
+    After,
+}
+
+/// Scripts of the sheet in the order they run, the order their position is reported in
+pub async fn load_scripts(pool: &Pool, sheet_pk: i32) -> anyhow::Result<Vec<CustomSqlScript>> {
+    let mut script_where = db_types::SqlSingleParameters::new();
+    script_where.insert(st!(CustomSqlScript::COL_SHEET_FK), sheet_pk.to_sql_value());
+
+    functions::select_from::<CustomSqlScript>(pool, Some(&script_where), None, None, &[OrderBy::asc(CustomSqlScript::COL_PK)], None).await
+}
+
+/// One step per script flagged for `phase`, in load order.
+/// Scripts are labeled by their 1-based position among all the scripts of the sheet.
+pub fn script_steps(
+    scripts: &[CustomSqlScript],
+    phase: ScriptPhase,
+    staged_table: Option<&str>,
+) -> Vec<db_types::ChainExecBox> {
+    scripts.iter()
+        .enumerate()
+        .filter(|(_, script)| match phase {
+            ScriptPhase::Before => script.run_before_update(),
+            ScriptPhase::As => script.run_as_update(),
+            ScriptPhase::After => script.run_after_update(),
+        })
+        .map(|(idx, script)| -> db_types::ChainExecBox {
+            Box::new(repository::custom_script_run(
+                st!(script.custom_script()),
+                format!("Custom script {} ({phase}) failed", idx + 1),
+                staged_table.map(str::to_string),
+            ))
+        })
+        .collect()
+}
+
+/// A failed custom script is reported like a file issue, anything else stays an error
+pub fn script_failed(error: anyhow::Error, rows: usize) -> anyhow::Result<model::UploadReport> {
+    let Some(tiberius::error::Error::Server(token)) = error.downcast_ref::<tiberius::error::Error>() else {
+        return Err(error);
+    };
+
+    if token.code() != functions::CUSTOM_SCRIPT_ERROR {
+        return Err(error);
+    }
+
+    Ok(model::UploadReport {
+        rows,
+        errors: vec![model::UploadIssue {
+            row: None,
+            column: String::new(),
+            value: None,
+            pattern: None,
+            message: st!(token.message()),
+        }],
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn step_sql(step: &db_types::ChainExecBox) -> String {
+        step(None, None, &db_types::ChainContext::default()).unwrap().0
+    }
+
+    #[test]
+    fn check_script_steps() {
+        let scripts = vec![
+            CustomSqlScript::default().with_pk(1).with_run_before_update(true).with_run_after_update(true).with_custom_script(st!("SELECT 1")),
+            CustomSqlScript::default().with_pk(2).with_run_as_update(true).with_custom_script(st!("SELECT 2")),
+            CustomSqlScript::default().with_pk(3).with_run_after_update(true).with_custom_script(st!("SELECT 3")),
+        ];
+
+        let before = script_steps(&scripts, ScriptPhase::Before, None);
+        assert_eq!(before.len(), 1);
+        assert!(step_sql(&before[0]).contains("N'Custom script 1 (run before update) failed: '"));
+
+        let after = script_steps(&scripts, ScriptPhase::After, None);
+        assert_eq!(after.len(), 2);
+        assert!(step_sql(&after[1]).contains("N'SELECT 3'"));
+        assert!(step_sql(&after[1]).contains("N'Custom script 3 (run after update) failed: '"));
+
+        let as_update = script_steps(&scripts, ScriptPhase::As, Some("SALES_STAGED_1"));
+        assert_eq!(as_update.len(), 1);
+        assert!(step_sql(&as_update[0]).contains("SELECT * INTO #STAGED FROM uploader.[SALES_STAGED_1];"));
+        assert!(step_sql(&as_update[0]).contains("N'Custom script 2 (run as update) failed: '"));
+    }
+
+    #[test]
+    fn check_script_failed_keeps_other_errors() {
+        let error = script_failed(anyhow::anyhow!("connection lost"), 3).unwrap_err();
+
+        assert_eq!(error.to_string(), "connection lost");
+    }
+}
@@ -0,0 +1,374 @@
+use regex::Regex;
+
+use crate::ddb::context::db_types::{GenericTable, SqlValue};
+use crate::ddb::tables::SheetMetaData;
+use crate::model::{MisspelledColumn, ModelFileMismatch, SheetConfigError, UploadIssue, UploadReport};
+use crate::st;
+
+use super::parser::RawTable;
+
+pub struct RegexConstraint {
+    pattern: String,
+    regex: Regex,
+}
+
+/// Compiles the `RegexConstraint` of every column once, in the same order as `columns`.
+/// Constraints must match the whole cell.
+pub fn compile_constraints(columns: &[SheetMetaData]) -> Result<Vec<Option<RegexConstraint>>, SheetConfigError> {
+    columns.iter()
+        .map(|column| {
+            let Some(pattern) = column.regex_constraint() else {
+                return Ok(None);
+            };
+
+            let regex = Regex::new(&format!("^(?:{pattern})$")).map_err(|e| SheetConfigError {
+                column: st!(column.column_name()),
+                pattern: st!(pattern),
+                message: e.to_string(),
+            })?;
+
+            Ok(Some(RegexConstraint { pattern: st!(pattern), regex }))
+        })
+        .collect()
+}
+
+/// Matches the file header against the sheet columns, coerces every cell and checks the regex constraints.
+/// `constraints` must follow the order of `table.columns()`, like the returned rows do.
+/// Absent optional columns are `SqlValue::None`.
+/// A file without data rows is an issue too, it would be recorded as an upload that loaded nothing.
+pub fn validate(
+    table: &GenericTable,
+    constraints: &[Option<RegexConstraint>],
+    raw: &RawTable,
+) -> (Vec<Vec<SqlValue>>, UploadReport) {
+    let mut report = UploadReport { rows: raw.rows.len(), errors: Vec::new() };
+
+    let positions = match_header(table, raw, &mut report);
+    if !report.errors.is_empty() {
+        return (Vec::new(), report);
+    }
+
+    if raw.rows.is_empty() {
+        report.errors.push(UploadIssue {
+            row: None,
+            column: String::new(),
+            value: None,
+            pattern: None,
+            message: st!("File has no data rows"),
+        });
+        return (Vec::new(), report);
+    }
+
+    let mut rows = Vec::with_capacity(raw.rows.len());
+    for (raw_row, line) in raw.rows.iter().zip(&raw.lines) {
+        let mut row = Vec::with_capacity(positions.len());
+
+        for ((column, position), constraint) in table.columns().iter().zip(&positions).zip(constraints) {
+            let cell = position
+                .and_then(|idx| raw_row.get(idx))
+                .map(String::as_str)
+                .unwrap_or_default();
+
+            let issue = |message: String, pattern: Option<&str>| UploadIssue {
+                row: Some(*line),
+                column: st!(column.name()),
+                value: Some(st!(cell)),
+                pattern: pattern.map(str::to_string),
+                message,
+            };
+
+            if let Some(constraint) = constraint
+                && !cell.trim().is_empty()
+                && !constraint.regex.is_match(cell.trim())
+            {
+                report.errors.push(issue(st!("Value does not match the column constraint"), Some(&constraint.pattern)));
+            }
+
+            match SqlValue::from_raw(cell, column.typing()) {
+                Ok(SqlValue::None) if !column.optional() => report.errors.push(issue(st!("Value is required"), None)),
+                Ok(value) => row.push(value),
+                Err(e) => report.errors.push(issue(e.to_string(), None)),
+            }
+        }
+
+        rows.push(row);
+    }
+
+    (rows, report)
+}
+
+fn match_header(table: &GenericTable, raw: &RawTable, report: &mut UploadReport) -> Vec<Option<usize>> {
+    let header_issue = |column: &str, message: &str| UploadIssue {
+        row: None,
+        column: st!(column),
+        value: None,
+        pattern: None,
+        message: st!(message),
+    };
+
+    // Column names are unique per sheet and compared case insensitive like the database collation
+    let find = |name: &str| raw.header.iter().position(|h| h.eq_ignore_ascii_case(name));
+
+    let positions: Vec<Option<usize>> = table.columns().iter().map(|c| find(c.name())).collect();
+
+    for (column, position) in table.columns().iter().zip(&positions) {
+        if position.is_none() && !column.optional() {
+            report.errors.push(header_issue(column.name(), "Required column is missing from the file"));
+        }
+    }
+
+    for (idx, name) in raw.header.iter().enumerate() {
+        if find(name) != Some(idx) {
+            report.errors.push(header_issue(name, "Column is repeated in the file"));
+        }
+        else if !table.columns().iter().any(|c| c.name().eq_ignore_ascii_case(name)) {
+            report.errors.push(header_issue(name, "Column is not part of the sheet"));
+        }
+    }
+
+    positions
+}
+
+// Names closer than this are taken as a typo of each other
+const MAX_TYPO_DISTANCE: usize = 2;
+
+/// Compares the header of a model file with the declared column names (case insensitive).
+/// A missing column and an extra one with close names are reported as misspelled.
+pub fn compare_model_header(names: &[&str], header: &[String]) -> ModelFileMismatch {
+    let mut mismatch = ModelFileMismatch::default();
+
+    let header: Vec<&str> = header.iter()
+        .map(|h| h.trim())
+        .filter(|h| !h.is_empty())
+        .collect();
+
+    let mut missing: Vec<&str> = names.iter()
+        .copied()
+        .filter(|name| !header.iter().any(|h| h.eq_ignore_ascii_case(name)))
+        .collect();
+
+    for (idx, found) in header.iter().enumerate() {
+        if header[..idx].iter().any(|h| h.eq_ignore_ascii_case(found)) {
+            mismatch.extra.push(st!(*found));
+            continue;
+        }
+
+        if names.iter().any(|name| name.eq_ignore_ascii_case(found)) {
+            continue;
+        }
+
+        let closest = missing.iter()
+            .enumerate()
+            .map(|(idx, expected)| (idx, typo_distance(found, expected)))
+            .filter(|(_, distance)| *distance <= MAX_TYPO_DISTANCE)
+            .min_by_key(|(_, distance)| *distance);
+
+        match closest {
+            Some((idx, _)) => mismatch.misspelled.push(MisspelledColumn {
+                found: st!(*found),
+                expected: st!(missing.remove(idx)),
+            }),
+            None => mismatch.extra.push(st!(*found)),
+        }
+    }
+
+    mismatch.missing = missing.into_iter().map(str::to_string).collect();
+
+    mismatch
+}
+
+// Levenshtein distance, ignoring case, spaces and separators
+fn typo_distance(a: &str, b: &str) -> usize {
+    let normalize = |s: &str| s.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect::<Vec<char>>();
+
+    let (a, b) = (normalize(a), normalize(b));
+
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.iter().enumerate() {
+        let mut current = vec![i + 1];
+
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != cb);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+
+        previous = current;
+    }
+
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use tiberius::ColumnType;
+
+    use super::*;
+    use crate::ddb::context::db_types::GenericColumn;
+
+    fn table() -> GenericTable {
+        GenericTable::new(st!("TEST"), vec![
+            GenericColumn::new(st!("Name"), ColumnType::NVarchar, false),
+            GenericColumn::new(st!("Amount"), ColumnType::Int4, false),
+            GenericColumn::new(st!("Note"), ColumnType::NVarchar, true),
+        ])
+    }
+
+    fn measures() -> GenericTable {
+        GenericTable::new(st!("MEASURES"), vec![
+            GenericColumn::new(st!("Weight"), ColumnType::Float8, true),
+            GenericColumn::new(st!("Price"), ColumnType::Decimaln, true),
+        ])
+    }
+
+    fn no_constraints() -> Vec<Option<RegexConstraint>> {
+        vec![None, None, None]
+    }
+
+    fn column(name: &str, regex_constraint: Option<&str>) -> SheetMetaData {
+        SheetMetaData::default()
+            .with_column_name(st!(name))
+            .with_regex_constraint(regex_constraint.map(str::to_string))
+    }
+
+    fn constraint(pattern: &str) -> Option<RegexConstraint> {
+        compile_constraints(&[column("Name", Some(pattern))]).unwrap().pop().unwrap()
+    }
+
+    fn raw(header: &[&str], rows: &[&[&str]]) -> RawTable {
+        RawTable {
+            header: header.iter().map(|h| st!(*h)).collect(),
+            rows: rows.iter().map(|r| r.iter().map(|c| st!(*c)).collect()).collect(),
+            // Header on the first line, one line per row
+            lines: (2..rows.len() + 2).collect(),
+        }
+    }
+
+    #[test]
+    fn check_validate_valid_file() {
+        let (rows, report) = validate(&table(), &no_constraints(), &raw(&["amount", "NAME"], &[&["1", "A"], &["2", "B"]]));
+
+        assert!(report.errors.is_empty());
+        assert_eq!(report.rows, 2);
+        assert!(matches!(rows[1][..], [SqlValue::Str(ref n), SqlValue::Int(2), SqlValue::None] if n == "B"));
+    }
+
+    #[test]
+    fn check_validate_header() {
+        let (rows, report) = validate(&table(), &no_constraints(), &raw(&["Name", "Extra", "name"], &[&["A", "B", "C"]]));
+
+        assert!(rows.is_empty());
+        assert_eq!(report.errors.len(), 3);
+        assert!(report.errors.iter().all(|e| e.row.is_none()));
+        assert!(report.errors.iter().any(|e| e.column == "Amount"));
+    }
+
+    #[test]
+    fn check_validate_no_data_rows() {
+        let (rows, report) = validate(&table(), &no_constraints(), &raw(&["Name", "Amount"], &[]));
+
+        assert!(rows.is_empty());
+        assert_eq!(report.rows, 0);
+        assert_eq!(report.errors.len(), 1);
+        assert_eq!(report.errors[0].message, "File has no data rows");
+    }
+
+    #[test]
+    fn check_validate_cells() {
+        let (_, report) = validate(&table(), &no_constraints(), &raw(&["Name", "Amount"], &[&["A", "x"], &["", "1"]]));
+
+        assert_eq!(report.errors.len(), 2);
+        assert_eq!(report.errors[0].row, Some(2));
+        assert_eq!(report.errors[0].column, "Amount");
+        assert_eq!(report.errors[1].row, Some(3));
+        assert_eq!(report.errors[1].message, "Value is required");
+    }
+
+    #[test]
+    fn check_validate_reports_file_lines() {
+        let file = "# Name: a name (Texto, required)\n# Amount: a number (Inteiro, required)\nName;Amount\nA;1\n\nB;x\n";
+        let raw = super::super::parser::parse_csv(file.as_bytes()).unwrap();
+
+        let (_, report) = validate(&table(), &no_constraints(), &raw);
+
+        assert_eq!(report.errors.len(), 1);
+        assert_eq!(report.errors[0].row, Some(6));
+    }
+
+    #[test]
+    fn check_validate_non_finite_numbers() {
+        let file = raw(&["Weight", "Price"], &[&["1,5", "2.25"], &["NaN", "inf"], &["infinity", ""]]);
+
+        let (_, report) = validate(&measures(), &[None, None], &file);
+
+        assert_eq!(report.errors.len(), 3);
+        assert_eq!(
+            report.errors.iter().map(|e| (e.row, e.column.as_str())).collect::<Vec<_>>(),
+            vec![(Some(3), "Weight"), (Some(3), "Price"), (Some(4), "Weight")]
+        );
+    }
+
+    #[test]
+    fn check_validate_regex_constraint() {
+        let constraints = vec![constraint("[A-Z]{2}"), constraint(r"\d+"), None];
+        let file = raw(&["Name", "Amount", "Note"], &[&["AB", "10", ""], &["ABC", "7", ""], &["ab", "", ""]]);
+
+        let (_, report) = validate(&table(), &constraints, &file);
+
+        assert_eq!(report.errors.len(), 3);
+        assert_eq!(report.errors[0].row, Some(3));
+        assert_eq!(report.errors[0].value.as_deref(), Some("ABC"));
+        assert_eq!(report.errors[0].pattern.as_deref(), Some("[A-Z]{2}"));
+        assert_eq!(report.errors[1].column, "Name");
+        // An empty required cell is reported once, as missing
+        assert_eq!(report.errors[2].column, "Amount");
+        assert!(report.errors[2].pattern.is_none());
+    }
+
+    #[test]
+    fn check_compile_constraints() {
+        let columns = vec![
+            column("Ok", Some("a|b")),
+            column("Free", None),
+            column("Broken", Some("(")),
+        ];
+
+        let error = compile_constraints(&columns).err().unwrap();
+        assert_eq!(error.column, "Broken");
+        assert_eq!(error.pattern, "(");
+
+        let constraints = compile_constraints(&columns[..2]).unwrap();
+        assert!(constraints[0].as_ref().unwrap().regex.is_match("b"));
+        assert!(!constraints[0].as_ref().unwrap().regex.is_match("ab"));
+        assert!(constraints[1].is_none());
+    }
+
+    #[test]
+    fn check_compare_model_header() {
+        let names = ["Name", "Amount", "Due Date", "Note"];
+
+        let header = vec![st!("name"), st!("Amount"), st!("Due Date"), st!("Note"), st!("")];
+        assert!(compare_model_header(&names, &header).is_empty());
+
+        let header = vec![st!("Name"), st!("Amout"), st!("Due_date"), st!("Extra"), st!("Name")];
+        let mismatch = compare_model_header(&names, &header);
+
+        assert_eq!(mismatch.missing, vec!["Note"]);
+        assert_eq!(mismatch.extra, vec!["Extra", "Name"]);
+        assert_eq!(
+            mismatch.misspelled.iter().map(|m| (m.found.as_str(), m.expected.as_str())).collect::<Vec<_>>(),
+            vec![("Amout", "Amount"), ("Due_date", "Due Date")]
+        );
+    }
+
+    #[test]
+    fn check_typo_distance() {
+        assert_eq!(typo_distance("Amount", "amount"), 0);
+        assert_eq!(typo_distance("Due Date", "due_date"), 0);
+        assert_eq!(typo_distance("Amout", "Amount"), 1);
+        assert_eq!(typo_distance("Note", "Name"), 2);
+        assert_eq!(typo_distance("", "Code"), 4);
+    }
+}
@@ -0,0 +1,321 @@
+use crate::ddb::context::{ db_types, functions };
+use crate::ddb::context::pool::Pool;
+use crate::ddb::tables::{ Group, ManagerPermission, Profile, ProfileGroups, UploaderPermission, Worker };
+use crate::ddb::{ DBLoad, ToSqlParameters };
+use crate::model::{ self, ManagerAction };
+use crate::repository;
+
+use db_types::{ Predicate, StepReturns, ToSqlValue };
+
+use super::policy::Manager;
+
+/// New worker with its first profile, returns the pk of the worker
+pub async fn add_worker(pool: &Pool, manager: &Manager, request: model::NewWorkerRequest) -> anyhow::Result<i32> {
+    manager.authorize(ManagerAction::AddWorker)?;
+    let board = manager.new_profile_board(request.board_fk, request.is_super_user)?;
+
+    if functions::exists::<Worker>(pool, &Predicate::eq(Worker::COL_LINDE_ID, request.linde_id.clone())).await? {
+        return Err(model::WorkerExists { linde_id: request.linde_id }.into());
+    }
+
+    let mut worker_insert_param = db_types::SqlMultipleParameters::new();
+    worker_insert_param.add_row(&request)?;
+
+    let mut profile_insert_param = db_types::SqlMultipleParameters::new();
+    profile_insert_param.add_line(vec![
+        (Profile::COL_ACTIVE, true.to_sql_value()),
+        (Profile::COL_BOARD_FK, board.to_sql_value()),
+        (Profile::COL_IS_SUPER_USER, request.is_super_user.to_sql_value()),
+    ])?;
+
+    let mut chain_map = db_types::ChainMap::new();
+    chain_map.push(&repository::worker_insert, Some(worker_insert_param), None).named("worker");
+    chain_map.push(&repository::worker_profile_insert, Some(profile_insert_param), None).named("profile");
+
+    let context = functions::chain_executions(pool, chain_map, db_types::SqlSingleParameters::new()).await?;
+
+    Ok(i32::try_from(context.identity("worker")?)?)
+}
+
+/// `None` when there is no such worker
+pub async fn edit_worker(pool: &Pool, manager: &Manager, worker_pk: i32, request: model::EditWorkerRequest) -> anyhow::Result<Option<()>> {
+    manager.authorize(ManagerAction::EditWorker)?;
+
+    let Some(worker) = functions::find_by_pk::<Worker>(pool, worker_pk).await? else {
+        return Ok(None);
+    };
+
+    let profiles = functions::select_from::<Profile>(
+        pool, &Predicate::eq(Profile::COL_WORKER_FK, worker_pk), None, None, &[], None
+    ).await?;
+    manager.check_worker(&profiles)?;
+
+    let linde_id_taken = Predicate::and(vec![
+        Predicate::eq(Worker::COL_LINDE_ID, request.linde_id.clone()),
+        Predicate::not_eq(Worker::COL_PK, worker_pk),
+    ]);
+    if functions::exists::<Worker>(pool, &linde_id_taken).await? {
+        return Err(model::WorkerExists { linde_id: request.linde_id }.into());
+    }
+
+    let worker_update = repository::table_update(Worker::TAB, Predicate::eq(Worker::COL_PK, worker.pk()));
+
+    let mut chain_map = db_types::ChainMap::new();
+    chain_map.push(&worker_update, None, Some(request.to_update_params())).named("worker");
+
+    functions::chain_executions(pool, chain_map, db_types::SqlSingleParameters::new()).await?;
+
+    Ok(Some(()))
+}
+
+/// Returns the pk of the profile, `None` when there is no such worker
+pub async fn add_profile(pool: &Pool, manager: &Manager, request: model::NewProfileRequest) -> anyhow::Result<Option<i32>> {
+    manager.authorize(ManagerAction::AddProfile)?;
+    let board = manager.new_profile_board(request.board_fk, request.is_super_user)?;
+
+    if functions::find_by_pk::<Worker>(pool, request.worker_fk).await?.is_none() {
+        return Ok(None);
+    }
+
+    let mut profile_insert_param = db_types::SqlMultipleParameters::new();
+    profile_insert_param.add_line(vec![
+        (Profile::COL_ACTIVE, true.to_sql_value()),
+        (Profile::COL_BOARD_FK, board.to_sql_value()),
+        (Profile::COL_WORKER_FK, request.worker_fk.to_sql_value()),
+        (Profile::COL_IS_SUPER_USER, request.is_super_user.to_sql_value()),
+    ])?;
+
+    let profile_insert = repository::table_insert(Profile::TAB);
+
+    let mut chain_map = db_types::ChainMap::new();
+    chain_map.push(&profile_insert, Some(profile_insert_param), None).named("profile").returns(StepReturns::Identity);
+
+    let context = functions::chain_executions(pool, chain_map, db_types::SqlSingleParameters::new()).await?;
+
+    Ok(Some(i32::try_from(context.identity("profile")?)?))
+}
+
+/// Soft delete, the profile stops working and leaves all of its groups. `None` when there is no such profile.
+pub async fn remove_profile(pool: &Pool, manager: &Manager, profile_pk: i32) -> anyhow::Result<Option<()>> {
+    manager.authorize(ManagerAction::RemoveProfile)?;
+
+    let Some(profile) = functions::find_by_pk::<Profile>(pool, profile_pk).await? else {
+        return Ok(None);
+    };
+    manager.check_board(profile.board_fk())?;
+
+    let mut deactivate = db_types::SqlSingleParameters::new();
+    deactivate.insert(Profile::COL_ACTIVE.to_string(), false.to_sql_value());
+
+    let profile_update = repository::table_update(Profile::TAB, Predicate::eq(Profile::COL_PK, profile_pk));
+    let groups_delete = repository::table_delete(ProfileGroups::TAB, Predicate::eq(ProfileGroups::COL_PROFILE_FK, profile_pk));
+
+    let mut chain_map = db_types::ChainMap::new();
+    chain_map.push(&profile_update, None, Some(deactivate)).named("profile");
+    chain_map.push(&groups_delete, None, None).named("profile_groups");
+
+    functions::chain_executions(pool, chain_map, db_types::SqlSingleParameters::new()).await?;
+
+    Ok(Some(()))
+}
+
+/// Returns the pk of the group
+pub async fn add_group(pool: &Pool, manager: &Manager, request: model::NewGroupRequest) -> anyhow::Result<i32> {
+    manager.authorize(ManagerAction::AddGroup)?;
+
+    let board = manager.new_group_board(request.board_id)?;
+    manager.check_manager_permission_grant(request.manager_permission.is_some())?;
+
+    let mut group_insert_param = db_types::SqlMultipleParameters::new();
+    group_insert_param.add_line(vec![
+        (Group::COL_NAME, request.name.to_sql_value()),
+        (Group::COL_ACTIVE, true.to_sql_value()),
+        (Group::COL_BOARD_ID, board.to_sql_value()),
+        (Group::COL_LAST_EDITED_BY_FK, manager.editor_pk().to_sql_value()),
+    ])?;
+
+    let mut chain_map = db_types::ChainMap::new();
+    chain_map.push(&repository::group_insert, Some(group_insert_param), None).named("group");
+
+    if let Some(manager_permission) = &request.manager_permission {
+        let mut permission_insert_param = db_types::SqlMultipleParameters::new();
+        permission_insert_param.add_row(manager_permission)?;
+
+        chain_map.push(&repository::manager_permission_insert, Some(permission_insert_param), None).named("manager_permission");
+    }
+
+    let context = functions::chain_executions(pool, chain_map, db_types::SqlSingleParameters::new()).await?;
+
+    Ok(i32::try_from(context.identity("group")?)?)
+}
+
+/// Soft delete, like profiles. `None` when there is no such group.
+pub async fn remove_group(pool: &Pool, manager: &Manager, group_pk: i32) -> anyhow::Result<Option<()>> {
+    manager.authorize(ManagerAction::RemoveGroup)?;
+
+    let Some(group) = functions::find_by_pk::<Group>(pool, group_pk).await? else {
+        return Ok(None);
+    };
+    manager.check_board(Some(group.board_id()))?;
+
+    let has_manager_permission = functions::exists::<ManagerPermission>(
+        pool, &Predicate::eq(ManagerPermission::COL_GROUP_FK, group_pk)
+    ).await?;
+    manager.check_group_removal(has_manager_permission)?;
+
+    let mut deactivate = db_types::SqlSingleParameters::new();
+    deactivate.insert(Group::COL_ACTIVE.to_string(), false.to_sql_value());
+    deactivate.insert(Group::COL_LAST_EDITED_BY_FK.to_string(), manager.editor_pk().to_sql_value());
+
+    let group_update = repository::table_update(Group::TAB, Predicate::eq(Group::COL_PK, group_pk));
+    let profiles_delete = repository::table_delete(ProfileGroups::TAB, Predicate::eq(ProfileGroups::COL_GROUP_FK, group_pk));
+
+    let mut chain_map = db_types::ChainMap::new();
+    chain_map.push(&group_update, None, Some(deactivate)).named("group");
+    chain_map.push(&profiles_delete, None, None).named("profile_groups");
+
+    functions::chain_executions(pool, chain_map, db_types::SqlSingleParameters::new()).await?;
+
+    Ok(Some(()))
+}
+
+/// Sets the `UPLOADER_PERMISSION` of the group on each sheet of the request, other sheets are left as they are.
+/// `None` when there is no such group.
+pub async fn edit_group_permissions(
+    pool: &Pool,
+    manager: &Manager,
+    group_pk: i32,
+    permissions: Vec<model::GroupPermissionRequest>,
+) -> anyhow::Result<Option<()>> {
+    manager.authorize(ManagerAction::EditGroup)?;
+
+    let Some(group) = functions::find_by_pk::<Group>(pool, group_pk).await? else {
+        return Ok(None);
+    };
+    manager.check_board(Some(group.board_id()))?;
+
+    let granted_sheets = functions::select_from::<UploaderPermission>(
+        pool, &Predicate::eq(UploaderPermission::COL_GROUP_FK, group_pk), None, None, &[], None
+    ).await?
+        .iter()
+        .map(UploaderPermission::sheet_fk)
+        .collect::<Vec<_>>();
+
+    let (updated, inserted): (Vec<_>, Vec<_>) = permissions.into_iter()
+        .partition(|permission| granted_sheets.contains(&permission.sheet_fk));
+
+    let permission_updates = updated.iter()
+        .map(|permission| {
+            let filter = Predicate::and(vec![
+                Predicate::eq(UploaderPermission::COL_GROUP_FK, group_pk),
+                Predicate::eq(UploaderPermission::COL_SHEET_FK, permission.sheet_fk),
+            ]);
+
+            let mut new_values = permission.to_update_params();
+            new_values.remove(UploaderPermission::COL_SHEET_FK);
+            new_values.insert(UploaderPermission::COL_LAST_EDITED_BY_FK.to_string(), manager.editor_pk().to_sql_value());
+
+            (repository::table_update(UploaderPermission::TAB, filter), new_values)
+        })
+        .collect::<Vec<_>>();
+    let permission_insert = repository::table_insert(UploaderPermission::TAB);
+
+    let mut chain_map = db_types::ChainMap::new();
+
+    for (permission_update, new_values) in &permission_updates {
+        chain_map.push(permission_update, None, Some(new_values.clone()));
+    }
+
+    if !inserted.is_empty() {
+        let mut permission_insert_param = db_types::SqlMultipleParameters::from_rows(&inserted)?;
+        permission_insert_param.add_const_column(group_pk.to_sql_value(), UploaderPermission::COL_GROUP_FK);
+        permission_insert_param.add_const_column(manager.editor_pk().to_sql_value(), UploaderPermission::COL_LAST_EDITED_BY_FK);
+
+        chain_map.push(&permission_insert, Some(permission_insert_param), None).named("new_permissions");
+    }
+
+    if chain_map.is_empty() {
+        return Ok(Some(()));
+    }
+
+    functions::chain_executions(pool, chain_map, db_types::SqlSingleParameters::new()).await?;
+
+    Ok(Some(()))
+}
+
+/// Replaces the groups of the profile, every group must be active and of the board of the profile.
+/// Managers can not move profiles in or out of groups with manager permissions, see `Manager::check_membership_change`.
+/// `None` when there is no such profile.
+pub async fn edit_profile_groups(pool: &Pool, manager: &Manager, profile_pk: i32, mut group_pks: Vec<i32>) -> anyhow::Result<Option<()>> {
+    manager.authorize(ManagerAction::EditProfileGroups)?;
+
+    let Some(profile) = functions::find_by_pk::<Profile>(pool, profile_pk).await? else {
+        return Ok(None);
+    };
+    manager.check_board(profile.board_fk())?;
+
+    group_pks.sort_unstable();
+    group_pks.dedup();
+
+    let groups = match group_pks.is_empty() {
+        true => Vec::new(),
+        false => functions::select_from::<Group>(
+            pool,
+            &Predicate::and(vec![Predicate::is_in(Group::COL_PK, group_pks.clone()), Predicate::eq(Group::COL_ACTIVE, true)]),
+            None, None, &[], None
+        ).await?,
+    };
+
+    if let Some(missing) = group_pks.iter().find(|pk| !groups.iter().any(|group| group.pk() == **pk)) {
+        return Err(model::PolicyRefused { message: format!("Group {missing} does not exist or is not active") }.into());
+    }
+
+    for group in &groups {
+        if group.board_id() != profile.board_fk().unwrap_or_default() {
+            return Err(model::PolicyRefused { message: format!("Group {} is not of the board of the profile", group.pk()) }.into());
+        }
+    }
+
+    // Groups the profile joins or leaves
+    let current_pks = functions::select_from::<ProfileGroups>(
+        pool, &Predicate::eq(ProfileGroups::COL_PROFILE_FK, profile_pk), None, None, &[], None
+    ).await?
+        .iter()
+        .map(ProfileGroups::group_fk)
+        .collect::<Vec<_>>();
+    let changed_pks = current_pks.iter()
+        .filter(|pk| !group_pks.contains(pk))
+        .chain(group_pks.iter().filter(|pk| !current_pks.contains(pk)))
+        .copied()
+        .collect::<Vec<_>>();
+
+    let changed_grants = match changed_pks.is_empty() {
+        true => Vec::new(),
+        false => functions::select_from::<ManagerPermission>(
+            pool, &Predicate::is_in(ManagerPermission::COL_GROUP_FK, changed_pks), None, None, &[], None
+        ).await?,
+    };
+    manager.check_membership_change(profile_pk, &changed_grants)?;
+
+    let groups_delete = repository::table_delete(ProfileGroups::TAB, Predicate::eq(ProfileGroups::COL_PROFILE_FK, profile_pk));
+    let groups_insert = repository::table_insert(ProfileGroups::TAB);
+
+    let mut chain_map = db_types::ChainMap::new();
+    chain_map.push(&groups_delete, None, None).named("old_groups");
+
+    if !group_pks.is_empty() {
+        let mut groups_insert_param = db_types::SqlMultipleParameters::new();
+        for group_pk in &group_pks {
+            groups_insert_param.add_line(vec![
+                (ProfileGroups::COL_PROFILE_FK, profile_pk.to_sql_value()),
+                (ProfileGroups::COL_GROUP_FK, group_pk.to_sql_value()),
+            ])?;
+        }
+
+        chain_map.push(&groups_insert, Some(groups_insert_param), None).named("new_groups");
+    }
+
+    functions::chain_executions(pool, chain_map, db_types::SqlSingleParameters::new()).await?;
+
+    Ok(Some(()))
+}